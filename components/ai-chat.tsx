@@ -30,13 +30,20 @@ import {
 	PromptInputTools,
 } from "@/components/ai-elements/prompt-input";
 import { PreviewMessage, ThinkingMessage } from "@/components/preview-message";
-import { useUpdateModelProvider } from "@/hooks/use-app-utils";
+import { useQueryModels, useUpdateModelProvider } from "@/hooks/use-app-utils";
 import { AppConfig } from "@/lib/ipc/utils";
-import { Model, models } from "@/lib/llms";
+import { Model, parseChatError } from "@/lib/llms";
 import { useChat } from "@ai-sdk/react";
 import { ChatStatus } from "ai";
 import { ArrowDownIcon, CheckIcon, RefreshCcw } from "lucide-react";
-import { Dispatch, RefObject, SetStateAction, useState } from "react";
+import {
+	Dispatch,
+	ReactNode,
+	RefObject,
+	SetStateAction,
+	useState,
+} from "react";
+import { useSettingsDialog } from "./settings-provider";
 import { Button } from "./ui/button";
 
 type UseChat = ReturnType<typeof useChat>;
@@ -57,9 +64,12 @@ type AIChatProps = {
 	messages: UseChat["messages"];
 	regenerate: UseChat["regenerate"];
 	setMessages: UseChat["setMessages"];
+	/** Shown above the messages, eg the branch switcher. */
+	header?: ReactNode;
 };
 
 export const AIChat = ({
+	header,
 	error,
 	status,
 	model,
@@ -79,6 +89,9 @@ export const AIChat = ({
 	const disabled = !config;
 	const { mutate, isPending } = useUpdateModelProvider();
 	const [modelSelectorOpen, setModelSelectorOpen] = useState(false);
+	const { data: models } = useQueryModels();
+	const { toggleDialog } = useSettingsDialog();
+	const chatError = error ? parseChatError(error) : null;
 
 	const chefs = Array.from(new Set(models.map((m) => m.chef)));
 	const handleSubmit = (message: PromptInputMessage) => {
@@ -102,6 +115,8 @@ export const AIChat = ({
 					className="absolute inset-0 touch-pan-y overflow-y-auto thin-scrollbar"
 					ref={messagesContainerRef}>
 					<div className="mx-auto flex min-w-0 max-w-4xl flex-col gap-4 px-2 py-4 md:gap-6 md:px-4">
+						{header}
+
 						{messages.length === 0 && <Greeting />}
 
 						{messages.map((message, index) => (
@@ -123,19 +138,38 @@ export const AIChat = ({
 							/>
 						))}
 
-						{error && (
+						{chatError && (
 							<div className="flex flex-col w-full justify-center gap-y-1.5">
-								<div>An error occurred.</div>
-								<Button
-									variant="outline"
-									className="w-fit"
-									size="sm"
-									onClick={() =>
-										regenerate()
-									}>
-									<RefreshCcw className="size-4 mr-1" />
-									Retry{" "}
-								</Button>
+								<div>{chatError.message}</div>
+								{chatError.code === "missing_api_key" ? (
+									<Button
+										variant="outline"
+										className="w-fit"
+										size="sm"
+										onClick={() =>
+											toggleDialog("api")
+										}>
+										Add api key
+									</Button>
+								) : chatError.code ===
+										"unsupported_model" ||
+								  chatError.code ===
+										"unsupported_provider" ? (
+									<div className="text-sm text-muted-foreground">
+										Pick another model to continue.
+									</div>
+								) : (
+									<Button
+										variant="outline"
+										className="w-fit"
+										size="sm"
+										onClick={() =>
+											regenerate()
+										}>
+										<RefreshCcw className="size-4 mr-1" />
+										Retry{" "}
+									</Button>
+								)}
 							</div>
 						)}
 
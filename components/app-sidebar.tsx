@@ -20,7 +20,8 @@ type AppSidebarProps = React.ComponentProps<typeof Sidebar> & {};
 
 export function AppSidebar({ ...props }: AppSidebarProps) {
 	const navigate = useNavigate();
-	const { data, isLoading } = useQueryRecentChats();
+	const { data, isLoading, hasNextPage, isFetchingNextPage, fetchNextPage } =
+		useQueryRecentChats();
 
 	return (
 		<Sidebar {...props}>
@@ -59,7 +60,13 @@ export function AppSidebar({ ...props }: AppSidebarProps) {
 				</SidebarMenu>
 			</SidebarHeader>
 
-			<SidebarHistory isLoading={isLoading} recentChats={data} />
+			<SidebarHistory
+				isLoading={isLoading}
+				recentChats={data ?? []}
+				hasMore={hasNextPage}
+				isLoadingMore={isFetchingNextPage}
+				onLoadMore={() => fetchNextPage()}
+			/>
 			<SidebarRail />
 		</Sidebar>
 	);
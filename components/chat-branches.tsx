@@ -0,0 +1,43 @@
+import { ChatBranchRecord } from "@/lib/ipc/chats";
+import { ChevronLeft, ChevronRight } from "lucide-react";
+import { Button } from "./ui/button";
+
+export const ChatBranches = ({
+	branches,
+	branchId,
+	disabled,
+	onSwitch,
+}: {
+	branches: ChatBranchRecord[];
+	branchId: string;
+	disabled: boolean;
+	onSwitch: (branchId: string) => void;
+}) => {
+	if (branches.length < 2) return null;
+
+	const index = branches.findIndex((b) => b.id === branchId);
+
+	return (
+		<div className="flex items-center justify-center gap-1 text-xs text-muted-foreground">
+			<Button
+				variant="ghost"
+				size="icon"
+				className="size-6"
+				disabled={disabled || index <= 0}
+				onClick={() => onSwitch(branches[index - 1].id)}>
+				<ChevronLeft className="size-4" />
+			</Button>
+			<span>
+				Branch {index + 1} of {branches.length}
+			</span>
+			<Button
+				variant="ghost"
+				size="icon"
+				className="size-6"
+				disabled={disabled || index >= branches.length - 1}
+				onClick={() => onSwitch(branches[index + 1].id)}>
+				<ChevronRight className="size-4" />
+			</Button>
+		</div>
+	);
+};
@@ -9,6 +9,8 @@ import {
 	CommandList,
 	CommandSeparator,
 } from "@/components/ui/command";
+import { useQuerySearch } from "@/hooks/use-app-utils";
+import { splitSnippet, type SearchResult } from "@/lib/ipc/search";
 import { useNavigate } from "@tanstack/react-router";
 import { Clipboard, GitCompare, MessageSquare, Settings } from "lucide-react";
 import type { ComponentType } from "react";
@@ -34,10 +36,48 @@ export type CommandDefinition = {
 	}) => void;
 };
 
+function Snippet({ snippet }: { snippet: string }) {
+	return (
+		<span className="truncate text-sm text-muted-foreground">
+			{splitSnippet(snippet).map((piece, i) =>
+				piece.match ? (
+					<mark
+						key={i}
+						className="rounded-sm bg-yellow-200/70 text-foreground dark:bg-yellow-500/30">
+						{piece.text}
+					</mark>
+				) : (
+					<React.Fragment key={i}>{piece.text}</React.Fragment>
+				),
+			)}
+		</span>
+	);
+}
+
 export function CmdMenu() {
 	const [open, setOpen] = React.useState(false);
+	const [search, setSearch] = React.useState("");
 	const navigate = useNavigate();
 	const { toggleDialog } = useSettingsDialog();
+	const { data: results = [] } = useQuerySearch(
+		React.useDeferredValue(search),
+	);
+
+	const openResult = (result: SearchResult) => {
+		if (result.kind === "chat") {
+			navigate({
+				to: "/chats/$chatId",
+				params: { chatId: result.id },
+			});
+		} else {
+			navigate({
+				to: "/pastebin/$pasteId",
+				params: { pasteId: result.id },
+			});
+		}
+
+		setOpen(false);
+	};
 
 	React.useEffect(() => {
 		const down = (e: KeyboardEvent) => {
@@ -170,14 +210,58 @@ export function CmdMenu() {
 				</button>
 			</div>
 
-			<CommandDialog open={open} onOpenChange={setOpen}>
+			<CommandDialog
+				open={open}
+				onOpenChange={(v) => {
+					setOpen(v);
+					if (!v) setSearch("");
+				}}>
 				<Command className="rounded-lg border shadow-md md:min-w-[450px]">
-					<CommandInput placeholder="Type a command or search..." />
+					<CommandInput
+						placeholder="Type a command or search..."
+						value={search}
+						onValueChange={setSearch}
+					/>
 					<CommandList>
 						<CommandEmpty>
 							No results found.
 						</CommandEmpty>
 
+						{search.trim() && results.length > 0 && (
+							<>
+								<CommandGroup heading="Chats and pastes">
+									{results.map((result) => (
+										<CommandItem
+											key={`${result.kind}-${result.id}-${result.message_id}`}
+											value={`${result.kind}-${result.id}-${result.message_id}`}
+											// Ranked by the backend, always let them through cmdk's filter.
+											keywords={[search]}
+											onSelect={() =>
+												openResult(result)
+											}
+											className="flex flex-col items-start gap-0.5">
+											<span className="flex items-center gap-2">
+												{result.kind === "chat" ? (
+													<MessageSquare />
+												) : (
+													<Clipboard />
+												)}
+												{result.label ??
+													(result.kind === "chat"
+														? "Chat"
+														: "Paste")}
+											</span>
+											<Snippet
+												snippet={result.snippet}
+											/>
+										</CommandItem>
+									))}
+								</CommandGroup>
+
+								<CommandSeparator />
+							</>
+						)}
+
 						<CommandGroup heading="Suggestions">
 							{suggestions.map((cmd) => {
 								const Icon = cmd.icon;
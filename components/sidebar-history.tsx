@@ -13,6 +13,9 @@ import { useParams } from "@tanstack/react-router";
 type SidebarHistoryProps = {
 	isLoading: boolean;
 	recentChats: ChatsRecord[];
+	hasMore?: boolean;
+	isLoadingMore?: boolean;
+	onLoadMore?: () => void;
 };
 
 export function SidebarHistory(props: SidebarHistoryProps) {
@@ -77,6 +80,18 @@ export function SidebarHistory(props: SidebarHistoryProps) {
 										/>
 									),
 								)}
+
+							{props.hasMore && (
+								<button
+									type="button"
+									className="rounded-md px-2 py-1 text-left text-sidebar-foreground/50 text-xs hover:bg-sidebar-accent disabled:opacity-50"
+									disabled={props.isLoadingMore}
+									onClick={props.onLoadMore}>
+									{props.isLoadingMore
+										? "Loading..."
+										: "Load more"}
+								</button>
+							)}
 						</div>
 					</SidebarMenu>
 				</SidebarGroupContent>
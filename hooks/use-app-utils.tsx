@@ -5,6 +5,9 @@ import {
 	cmd_open_url,
 	cmd_update_app_config,
 } from "@/lib/ipc/utils";
+import { cmd_list_models } from "@/lib/ipc/providers";
+import { cmd_search } from "@/lib/ipc/search";
+import { toModel } from "@/lib/llms";
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 import { isTauri } from "@tauri-apps/api/core";
 import { useEffect } from "react";
@@ -35,6 +38,43 @@ export const useQueryAppConfig = () => {
 	return r;
 };
 
+export const useQueryModels = () => {
+	const r = useQuery({
+		queryKey: ["fetch_models"],
+		queryFn: async () => {
+			if (!isTauri()) return [];
+
+			const models = await cmd_list_models();
+
+			return models.map(toModel);
+		},
+		initialData: [],
+	});
+
+	useEffect(() => {
+		if (!r.error) return;
+
+		console.error("Failed to fetch models:", r.error);
+	}, [r.error]);
+
+	return r;
+};
+
+export const useQuerySearch = (query: string) => {
+	const search = query.trim();
+
+	return useQuery({
+		queryKey: ["search", search],
+		queryFn: async () => {
+			if (!isTauri()) return [];
+
+			return await cmd_search(search);
+		},
+		enabled: search.length > 0,
+		placeholderData: (previous) => previous,
+	});
+};
+
 type ModelProvider = Pick<
 	AppConfig,
 	"app_id" | "selected_model" | "selected_provider"
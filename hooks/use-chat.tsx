@@ -1,15 +1,18 @@
 import {
-	ChatsRecord,
+	ChatWithMessages,
 	cmd_delete_chat_by_id,
 	cmd_find_recent_chats,
 	cmd_get_chat_by_id,
-	cmd_save_initial_chat,
-	update_chat_message,
-	UpdateChat,
+	cmd_switch_chat_branch,
 } from "@/lib/ipc/chats";
-import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { nextPageCursor, PageCursor } from "@/lib/ipc/utils";
+import {
+	useInfiniteQuery,
+	useMutation,
+	useQuery,
+	useQueryClient,
+} from "@tanstack/react-query";
 import { useNavigate } from "@tanstack/react-router";
-import { ChatStatus } from "ai";
 import { useEffect } from "react";
 import { toast } from "sonner";
 
@@ -36,69 +39,59 @@ export const useQueryChatById = (chatId?: string) => {
 	return r;
 };
 
-export const useUpdateChat = () => {
-	const r = useMutation({
-		mutationKey: ["update_chat_messages"],
-		mutationFn: async (data: UpdateChat) => {
-			return {
-				status: await update_chat_message(data),
-				chatId: data.chat_id,
-			};
-		},
-	});
+/** Opens a chat saved by the chat api, refreshing the sidebar first. */
+export const useOpenSavedChat = () => {
+	const navigate = useNavigate();
+	const queryClient = useQueryClient();
 
-	return r;
+	return (chatId: string) => {
+		queryClient.invalidateQueries({
+			queryKey: ["fetch_recent_chats"],
+		});
+
+		navigate({
+			to: "/chats/$chatId",
+			params: {
+				chatId,
+			},
+		});
+	};
 };
 
-export const useSaveInitialChat = ({
-	isFirstChat,
-	status,
-}: {
-	status: ChatStatus;
-	isFirstChat: boolean;
-}) => {
-	const navigate = useNavigate();
+/** Switches the branch of a chat, the loaded chat is replaced with it. */
+export const useSwitchChatBranch = (chatId: string) => {
 	const queryClient = useQueryClient();
 
 	const r = useMutation({
-		mutationKey: ["save_initial_chat"],
-		mutationFn: async (data: ChatsRecord) => {
-			await cmd_save_initial_chat(data);
-
-			return data.id;
+		mutationKey: ["switch_chat_branch", chatId],
+		mutationFn: (branchId: string) => {
+			return cmd_switch_chat_branch(chatId, branchId);
 		},
-		onError(e) {
-			console.log("Error: ", e);
+		onSuccess(chat: ChatWithMessages) {
+			queryClient.setQueryData(["fetch_chat_by_id", chatId], chat);
 		},
-		onSuccess: async (chatId) => {
-			if (status === "ready" && isFirstChat === true) {
-				await new Promise((r) => setTimeout(r, 1500));
-
-				// Refresh sidebar
-				queryClient.invalidateQueries({
-					queryKey: ["fetch_recent_chats"],
-				});
-
-				navigate({
-					to: "/chats/$chatId",
-					params: {
-						chatId,
-					},
-				});
-			}
+		onError(error) {
+			toast.error("Unable to switch branch", {
+				description: "The branch could not be loaded. Please try again.",
+			});
+
+			console.error("Failed to switch branch:", error);
 		},
 	});
 
 	return r;
 };
 
+/** Recent chats, older pages are loaded with `fetchNextPage`. */
 export const useQueryRecentChats = () => {
-	const r = useQuery({
+	const r = useInfiniteQuery({
 		queryKey: ["fetch_recent_chats"],
-		queryFn: async () => {
-			return await cmd_find_recent_chats();
+		queryFn: async ({ pageParam }) => {
+			return await cmd_find_recent_chats({ cursor: pageParam });
 		},
-		initialData: [],
+		initialPageParam: undefined as PageCursor | undefined,
+		getNextPageParam: (lastPage) => nextPageCursor(lastPage),
+		select: (data) => data.pages.flat(),
 	});
 
 	useEffect(() => {
@@ -1,26 +1,42 @@
 import { invoke } from "@tauri-apps/api/core";
 import { Message } from "../types";
+import { PageOptions } from "@/lib/ipc/utils";
 
 export type ChatsRecord = {
 	id: string;
 	label: String;
+};
+
+export type ChatBranchRecord = {
+	chat_id: string;
+	id: string;
+	head_id: string | null;
+	/** Branches forked from the same message are alternatives to each other. */
+	forked_from: string | null;
+	created_at: string;
+};
+
+export type ChatWithMessages = ChatsRecord & {
+	/** Branch `messages` are from. */
+	branch_id: string;
+	branches: ChatBranchRecord[];
 	messages: Message[];
 };
 
-export const cmd_save_initial_chat = async (chat: ChatsRecord) => {
+export const cmd_save_initial_chat = async (chat: ChatWithMessages) => {
 	return await invoke("cmd_save_initial_chat", {
 		chat: {
 			id: chat.id,
 			label: chat.label,
-			messages: JSON.stringify(chat.messages),
 		},
+		messages: chat.messages,
 	});
 };
 
 export const cmd_get_chat_by_id = async (chatId: string) => {
 	return (await invoke("cmd_get_chat_by_id", {
 		chat_id: chatId,
-	})) as Omit<ChatsRecord, "messages"> & { messages: string };
+	})) as ChatWithMessages;
 };
 
 export const cmd_delete_chat_by_id = async (chatId: string) => {
@@ -29,23 +45,49 @@ export const cmd_delete_chat_by_id = async (chatId: string) => {
 	})) as boolean;
 };
 
-export type UpdateChat = {
+export type UpdateChatMessage = {
 	chat_id: string;
-	messages: Message[];
+	message_id: string;
+	parts: Message["parts"];
 };
 
-export const update_chat_message = async (args: UpdateChat) => {
-	return (await invoke("update_chat_message", {
+export const cmd_update_chat_message = async (args: UpdateChatMessage) => {
+	return (await invoke("cmd_update_chat_message", {
 		chat_id: args.chat_id,
-		messages: JSON.stringify(args.messages),
+		message_id: args.message_id,
+		parts: args.parts,
 	})) as boolean;
 };
 
-export const cmd_find_recent_chats = async () => {
-	const results = (await invoke(
-		"cmd_find_recent_chats",
-		{},
-	)) as ChatsRecord[];
+export const cmd_fork_chat_at_message = async (
+	chatId: string,
+	messageId: string,
+) => {
+	return (await invoke("cmd_fork_chat_at_message", {
+		chat_id: chatId,
+		message_id: messageId,
+	})) as ChatWithMessages;
+};
+
+export const cmd_switch_chat_branch = async (
+	chatId: string,
+	branchId: string,
+) => {
+	return (await invoke("cmd_switch_chat_branch", {
+		chat_id: chatId,
+		branch_id: branchId,
+	})) as ChatWithMessages;
+};
+
+export type ChatSummary = ChatsRecord & {
+	created_at: string;
+	updated_at: string;
+};
+
+export const cmd_find_recent_chats = async (options?: PageOptions) => {
+	const results = (await invoke("cmd_find_recent_chats", {
+		options,
+	})) as ChatSummary[];
 
 	if (!results) return [];
 
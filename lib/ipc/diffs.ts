@@ -0,0 +1,552 @@
+import { type Channel, invoke } from "@tauri-apps/api/core";
+import { emit } from "@tauri-apps/api/event";
+
+export type DiffSource =
+	| { type: "text"; old: string; new: string }
+	| { type: "files"; old_path: string; new_path: string };
+
+export type DiffOptions = {
+	context_lines?: number;
+	word_diff?: boolean;
+	detect_moves?: boolean;
+	ignore_line_endings?: boolean;
+	ignore_trailing_whitespace?: boolean;
+	ignore_all_whitespace?: boolean;
+	ignore_case?: boolean;
+	ignore_blank_lines?: boolean;
+	/** Regular expressions, changes made only of matching lines are dropped. */
+	ignore_matching?: string[];
+};
+
+export type ChangeKind = "equal" | "insert" | "delete";
+
+export type InlineSegment = {
+	kind: ChangeKind;
+	value: string;
+};
+
+export type DiffLine = {
+	kind: ChangeKind;
+	old_line: number | null;
+	new_line: number | null;
+	content: string;
+	segments?: InlineSegment[];
+	/** Id of the moved block the line belongs to. */
+	move_id?: number;
+};
+
+export type Hunk = {
+	old_start: number;
+	old_lines: number;
+	new_start: number;
+	new_lines: number;
+	lines: DiffLine[];
+};
+
+export type DiffStats = {
+	insertions: number;
+	deletions: number;
+};
+
+export type MovedBlock = {
+	id: number;
+	old_start: number;
+	old_lines: number;
+	new_start: number;
+	new_lines: number;
+	/** Changes made while moving, absent for a plain move. */
+	edits?: Hunk[];
+};
+
+export type DiffResult = {
+	identical: boolean;
+	stats: DiffStats;
+	hunks: Hunk[];
+	moves: MovedBlock[];
+};
+
+export type DataFormat = "json" | "yaml" | "toml";
+
+export type SyntaxLanguage = "rust" | "typescript" | "tsx" | "python" | "json";
+
+export type DiffMode =
+	| { type: "text" }
+	| { type: "structural"; format?: DataFormat | null }
+	| { type: "syntax"; language?: SyntaxLanguage | null };
+
+export type StructuralChange = {
+	path: string;
+	kind: "added" | "removed" | "changed";
+	old: unknown | null;
+	new: unknown | null;
+	summary: string;
+};
+
+export type StructuralDiff = {
+	format: DataFormat;
+	identical: boolean;
+	changes: StructuralChange[];
+};
+
+export type LineRange = {
+	start: number;
+	end: number;
+};
+
+export type SyntaxChange = {
+	kind: "added" | "removed" | "modified" | "moved" | "renamed" | "reformatted";
+	node_kind: string;
+	name: string | null;
+	old_name: string | null;
+	old_range: LineRange | null;
+	new_range: LineRange | null;
+	diff: DiffResult | null;
+};
+
+export type SyntaxDiff = {
+	language: SyntaxLanguage;
+	identical: boolean;
+	changes: SyntaxChange[];
+};
+
+export type DiffOutput =
+	| { mode: "text"; result: DiffResult }
+	| { mode: "structural"; result: StructuralDiff }
+	| { mode: "syntax"; result: SyntaxDiff };
+
+export const cmd_compute_diff = async (
+	source: DiffSource,
+	options?: DiffOptions,
+	mode?: DiffMode,
+) => {
+	return (await invoke("cmd_compute_diff", {
+		source,
+		options,
+		mode,
+	})) as DiffOutput;
+};
+
+export type SaveDiff = {
+	id?: number | null;
+	label: string;
+	old_file: string;
+	new_file: string;
+};
+
+export type DiffRecord = {
+	id: number;
+	label: string;
+	old_file: string;
+	new_file: string;
+	created_at: string;
+	updated_at: string;
+};
+
+export type DiffSummary = Omit<DiffRecord, "old_file" | "new_file">;
+
+export type HunkSummary = {
+	old_start: number;
+	old_lines: number;
+	new_start: number;
+	new_lines: number;
+	insertions: number;
+	deletions: number;
+};
+
+export type DiffMetrics = {
+	insertions: number;
+	deletions: number;
+	/** Lines edited in place, the deletions paired with an insertion. */
+	changed_lines: number;
+	/** Share of lines in common, from 0 to 1. */
+	similarity: number;
+	hunks: HunkSummary[];
+};
+
+export const cmd_diff_stats = async (
+	source: DiffSource,
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_diff_stats", {
+		source,
+		options,
+	})) as DiffMetrics;
+};
+
+export const cmd_save_diff = async (diff: SaveDiff) => {
+	return (await invoke("cmd_save_diff", { diff })) as number;
+};
+
+export const cmd_get_diff_by_id = async (diffId: number) => {
+	return (await invoke("cmd_get_diff_by_id", {
+		diff_id: diffId,
+	})) as DiffRecord;
+};
+
+export const cmd_find_recent_diffs = async () => {
+	const results = (await invoke("cmd_find_recent_diffs", {})) as DiffSummary[];
+
+	if (!results) return [];
+
+	return results;
+};
+
+export const cmd_delete_diff_by_id = async (diffId: number) => {
+	return (await invoke("cmd_delete_diff_by_id", {
+		diff_id: diffId,
+	})) as boolean;
+};
+
+export type MergeOptions = {
+	ours_label?: string;
+	theirs_label?: string;
+	base_label?: string;
+	show_base?: boolean;
+};
+
+export type MergeRegion =
+	| {
+			type: "resolved";
+			source: "base" | "ours" | "theirs" | "both";
+			text: string;
+	  }
+	| {
+			type: "conflict";
+			id: number;
+			base_start: number;
+			ours_start: number;
+			theirs_start: number;
+			base: string;
+			ours: string;
+			theirs: string;
+	  };
+
+export type MergeResult = {
+	conflicts: number;
+	regions: MergeRegion[];
+	merged: string;
+};
+
+export type ConflictResolution =
+	| { type: "ours" }
+	| { type: "theirs" }
+	| { type: "base" }
+	| { type: "both" }
+	| { type: "custom"; text: string };
+
+export type ResolveConflict = {
+	id: number;
+	resolution: ConflictResolution;
+};
+
+export const cmd_merge_three_way = async (
+	base: string,
+	ours: string,
+	theirs: string,
+	options?: MergeOptions,
+) => {
+	return (await invoke("cmd_merge_three_way", {
+		base,
+		ours,
+		theirs,
+		options,
+	})) as MergeResult;
+};
+
+export const cmd_resolve_merge_conflicts = async (
+	regions: MergeRegion[],
+	resolutions: ResolveConflict[],
+) => {
+	return (await invoke("cmd_resolve_merge_conflicts", {
+		regions,
+		resolutions,
+	})) as string;
+};
+
+export type DirDiffOptions = {
+	exclude?: string[];
+	respect_gitignore?: boolean;
+	include_unchanged?: boolean;
+	diff?: DiffOptions;
+};
+
+export type EntryStatus = "added" | "removed" | "modified" | "unchanged";
+
+export type DirEntryDiff = {
+	path: string;
+	status: EntryStatus;
+	binary: boolean;
+	stats: DiffStats | null;
+	/** Why the file could not be compared, eg it is unreadable. */
+	error: string | null;
+};
+
+export type DirDiffResult = {
+	summary: {
+		added: number;
+		removed: number;
+		modified: number;
+		unchanged: number;
+		/** Files that could not be compared. */
+		errors: number;
+	};
+	entries: DirEntryDiff[];
+	/** Walk errors that name no file. */
+	errors: string[];
+};
+
+export const cmd_diff_directories = async (
+	oldDir: string,
+	newDir: string,
+	options?: DirDiffOptions,
+) => {
+	return (await invoke("cmd_diff_directories", {
+		old_dir: oldDir,
+		new_dir: newDir,
+		options,
+	})) as DirDiffResult;
+};
+
+export type ImageDiffOptions = {
+	/** Largest per channel difference still treated as equal. */
+	tolerance?: number;
+};
+
+export type ImageInfo = {
+	width: number;
+	height: number;
+	format: "png" | "jpeg" | "webp";
+};
+
+export type ImageDiffResult = {
+	old: ImageInfo;
+	new: ImageInfo;
+	same_dimensions: boolean;
+	total_pixels: number;
+	changed_pixels: number;
+	difference_percent: number;
+	/** Highlighted PNG under `Documents/Differ`, replaced by the next diff of the same pair. `null` when nothing changed. */
+	overlay_path: string | null;
+};
+
+export const cmd_diff_images = async (
+	oldPath: string,
+	newPath: string,
+	options?: ImageDiffOptions,
+) => {
+	return (await invoke("cmd_diff_images", {
+		old_path: oldPath,
+		new_path: newPath,
+		options,
+	})) as ImageDiffResult;
+};
+
+export type GitRefs = {
+	head: string | null;
+	branches: string[];
+};
+
+export type GitFileDiff = {
+	path: string;
+	status: EntryStatus;
+	binary: boolean;
+	diff: DiffResult | null;
+};
+
+export type GitDiff = {
+	summary: DirDiffResult["summary"];
+	files: GitFileDiff[];
+};
+
+export type GitCommit = {
+	id: string;
+	short_id: string;
+	summary: string;
+	author: string;
+	/** Seconds since the unix epoch. */
+	time: number;
+};
+
+export type GitFileRevision = {
+	commit: GitCommit;
+	status: EntryStatus;
+	binary: boolean;
+	diff: DiffResult | null;
+};
+
+export const cmd_git_refs = async (repoPath: string) => {
+	return (await invoke("cmd_git_refs", { repo_path: repoPath })) as GitRefs;
+};
+
+export const cmd_git_diff_worktree = async (
+	repoPath: string,
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_git_diff_worktree", {
+		repo_path: repoPath,
+		options,
+	})) as GitDiff;
+};
+
+/** Revisions can be branches, tags, commit ids or expressions like `HEAD~2`. */
+export const cmd_git_diff_revisions = async (
+	repoPath: string,
+	oldRev: string,
+	newRev: string,
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_git_diff_revisions", {
+		repo_path: repoPath,
+		old_rev: oldRev,
+		new_rev: newRev,
+		options,
+	})) as GitDiff;
+};
+
+export const cmd_git_file_history = async (
+	repoPath: string,
+	filePath: string,
+	limit?: number,
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_git_file_history", {
+		repo_path: repoPath,
+		file_path: filePath,
+		limit,
+		options,
+	})) as GitFileRevision[];
+};
+
+export type PatchTarget =
+	| { type: "text"; text: string }
+	| { type: "file"; path: string };
+
+export type HunkOutcome = {
+	hunk: number;
+	applied: boolean;
+	offset: number;
+	fuzz: number;
+};
+
+export type ApplyResult = {
+	text: string;
+	applied: number;
+	failed: number;
+	hunks: HunkOutcome[];
+};
+
+export const cmd_unified_diff = async (
+	source: DiffSource,
+	options?: DiffOptions,
+	fileName?: string,
+) => {
+	return (await invoke("cmd_unified_diff", {
+		source,
+		options,
+		file_name: fileName,
+	})) as string;
+};
+
+/** Resolves to the saved path, or `null` when the save dialog was dismissed. */
+export const cmd_export_patch = async (
+	source: DiffSource,
+	options?: DiffOptions,
+	fileName?: string,
+) => {
+	return (await invoke("cmd_export_patch", {
+		source,
+		options,
+		file_name: fileName,
+	})) as string | null;
+};
+
+export type ReportFormat =
+	| { type: "html"; layout?: "inline" | "side_by_side" }
+	| { type: "markdown" };
+
+/** Resolves to the saved path, or `null` when the save dialog was dismissed. */
+export const cmd_export_report = async (
+	source: DiffSource,
+	format: ReportFormat,
+	options?: DiffOptions,
+	fileName?: string,
+) => {
+	return (await invoke("cmd_export_report", {
+		source,
+		format,
+		options,
+		file_name: fileName,
+	})) as string | null;
+};
+
+export const cmd_apply_patch = async (
+	patch: string,
+	target: PatchTarget,
+	maxFuzz?: number,
+) => {
+	return (await invoke("cmd_apply_patch", {
+		patch,
+		target,
+		max_fuzz: maxFuzz,
+	})) as ApplyResult;
+};
+
+export type DiffStreamEvent =
+	| { event: "started"; data: { old_size: number; new_size: number } }
+	| { event: "hunk"; data: { hunk: Hunk } }
+	| { event: "progress"; data: { bytes_read: number; total: number } }
+	| { event: "finished"; data: { identical: boolean; stats: DiffStats } };
+
+export type DiffStreamChannel = Channel<DiffStreamEvent>;
+
+export const cmd_stream_diff = async (
+	oldPath: string,
+	newPath: string,
+	channel: DiffStreamChannel,
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_stream_diff", {
+		old_path: oldPath,
+		new_path: newPath,
+		options,
+		channel,
+	})) as DiffStats;
+};
+
+export type DiffWatchEvent =
+	| { event: "updated"; data: { watch_id: string; result: DiffResult } }
+	| { event: "failed"; data: { watch_id: string; error: string } };
+
+/** Recomputed diffs are emitted as `diff_watch` events until unlistened. */
+export const cmd_watch_diff = async (
+	watchId: string,
+	oldPath: string,
+	newPath: string,
+	options?: DiffOptions,
+) => {
+	await invoke("cmd_watch_diff", {
+		watch_id: watchId,
+		old_path: oldPath,
+		new_path: newPath,
+		options,
+	});
+
+	// Unlisten
+	return async () => {
+		await emit("cmd_watch_diff_close", watchId);
+	};
+};
+
+/**
+ * Body of `POST ${endpoint}/api/explain-diff`, answered with the same UI
+ * message stream as `/api/chat`. Set `chat_id` to save the explanation as a chat.
+ */
+export type ExplainDiffRequest = {
+	source:
+		| { type: "patch"; patch: string }
+		| { type: "text"; old: string; new: string };
+	style?: "explain" | "review";
+	options?: DiffOptions;
+	chat_id?: string;
+	label?: string;
+};
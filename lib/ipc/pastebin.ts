@@ -1,6 +1,8 @@
 import { DownloadChannel } from "@/hooks/use-download";
 import { invoke } from "@tauri-apps/api/core";
 import { emit } from "@tauri-apps/api/event";
+import { DiffOptions } from "@/lib/ipc/diffs";
+import { PageOptions } from "@/lib/ipc/utils";
 
 export type LocalAttachment = {
 	original_file_name: string;
@@ -24,11 +26,31 @@ export type RemotePastes = {
 	_creationTime: number;
 };
 
-export const cmd_find_recent_local_pastes = async () => {
-	return (await invoke(
-		"cmd_find_recent_local_pastes",
-		{},
-	)) as LocalPastes[];
+export const cmd_find_recent_local_pastes = async (options?: PageOptions) => {
+	return (await invoke("cmd_find_recent_local_pastes", {
+		options,
+	})) as LocalPastes[];
+};
+
+export type PasteSimilarity = {
+	paste_id: string;
+	/** Share of lines in common, from 0 to 1. */
+	similarity: number;
+	insertions: number;
+	deletions: number;
+};
+
+/** Most similar first, compares the recent pastes when no ids are given. */
+export const cmd_rank_pastes_by_similarity = async (
+	filePath: string,
+	pasteIds?: string[],
+	options?: DiffOptions,
+) => {
+	return (await invoke("cmd_rank_pastes_by_similarity", {
+		file_path: filePath,
+		paste_ids: pasteIds,
+		options,
+	})) as PasteSimilarity[];
 };
 
 export type SaveRemotePasteLocally = Pick<RemotePastes, "_id" | "body">;
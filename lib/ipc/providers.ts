@@ -0,0 +1,72 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type SaveCustomProvider = {
+	id?: string | null;
+	name: string;
+	/** Including the version path, eg `http://localhost:11434/v1`. */
+	base_url: string;
+	api_key?: string | null;
+};
+
+export type CustomProviderRecord = {
+	id: string;
+	name: string;
+	base_url: string;
+	api_key: string | null;
+	created_at: string;
+	updated_at: string;
+};
+
+/**
+ * Resolves to the provider id. Select the provider by saving that id as
+ * `selected_provider` in the app config, with any model name it serves as
+ * `selected_model`.
+ */
+export const cmd_save_custom_provider = async (provider: SaveCustomProvider) => {
+	return (await invoke("cmd_save_custom_provider", { provider })) as string;
+};
+
+export const cmd_find_custom_providers = async () => {
+	const results = (await invoke(
+		"cmd_find_custom_providers",
+		{},
+	)) as CustomProviderRecord[];
+
+	if (!results) return [];
+
+	return results;
+};
+
+export const cmd_delete_custom_provider = async (providerId: string) => {
+	return (await invoke("cmd_delete_custom_provider", {
+		provider_id: providerId,
+	})) as boolean;
+};
+
+export type ModelCapability =
+	| "text_input"
+	| "text_output"
+	| "image_input"
+	| "audio_input"
+	| "video_input"
+	| "reasoning"
+	| "structured_output"
+	| "tool_calls";
+
+export type ModelRecord = {
+	provider_id: string;
+	model_id: string;
+	provider_name: string;
+	display_name: string;
+	/** Tokens, prompt and answer combined. */
+	context_window: number;
+	capabilities: ModelCapability[];
+};
+
+export const cmd_list_models = async () => {
+	const results = (await invoke("cmd_list_models", {})) as ModelRecord[];
+
+	if (!results) return [];
+
+	return results;
+};
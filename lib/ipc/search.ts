@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Wraps each match in a search snippet, see `SEARCH_MATCH_START` in constants.rs. */
+export const SEARCH_MATCH_START = "\uE000";
+export const SEARCH_MATCH_END = "\uE001";
+
+export type SearchKind = "chat" | "paste";
+
+export type SearchResult = {
+	kind: SearchKind;
+	/** Id of the chat or paste. */
+	id: string;
+	message_id: string | null;
+	label: string | null;
+	snippet: string;
+	/** Lower is a better match. */
+	rank: number;
+};
+
+export const cmd_search = async (query: string) => {
+	return (await invoke("cmd_search", { query })) as SearchResult[];
+};
+
+/** Splits a snippet into plain and matched text. */
+export const splitSnippet = (snippet: string) => {
+	const pieces: { text: string; match: boolean }[] = [];
+
+	for (const chunk of snippet.split(SEARCH_MATCH_START)) {
+		const [match, rest] = chunk.split(SEARCH_MATCH_END);
+
+		if (rest === undefined) {
+			if (match) pieces.push({ text: match, match: false });
+			continue;
+		}
+
+		if (match) pieces.push({ text: match, match: true });
+		if (rest) pieces.push({ text: rest, match: false });
+	}
+
+	return pieces;
+};
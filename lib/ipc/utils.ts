@@ -14,7 +14,66 @@ export const getEndpoint = async (): Promise<string | null> => {
 	return taskStatus.url;
 };
 
-export const filePicker = async () => {
+export type TextEncoding =
+	| "utf8"
+	| "utf16_le"
+	| "utf16_be"
+	| "latin1"
+	| "shift_jis";
+
+export type LineEnding = "lf" | "crlf" | "cr" | "mixed" | "none";
+
+export type FileContents = {
+	name: string;
+	/** Decoded text, or a hex dump for binary files. */
+	contents: string;
+	encoding: TextEncoding | null;
+	has_bom: boolean;
+	line_ending: LineEnding | null;
+	binary: boolean;
+	size: number;
+};
+
+export type ReadFileOptions = {
+	max_size?: number;
+	binary?: "refuse" | "hex_summary";
+};
+
+/** Rows per page when `PageOptions.limit` is not set, see constants.rs. */
+export const RECENT_PAGE_SIZE = 30;
+
+/** `updated_at` and `id` of the last row of a page. */
+export type PageCursor = {
+	updated_at: string;
+	id: string;
+};
+
+export type PageOptions = {
+	/** Continue after this row, omit for the first page. */
+	cursor?: PageCursor;
+	limit?: number;
+	sort?: "newest" | "oldest";
+	/** Label (chats) or body (pastes) contains this text. */
+	contains?: string;
+	/** `YYYY-MM-DD[ HH:MM:SS]`, inclusive. */
+	updated_after?: string;
+	/** `YYYY-MM-DD[ HH:MM:SS]`, exclusive. */
+	updated_before?: string;
+};
+
+/** Cursor for the page after `page`, undefined when it was the last one. */
+export const nextPageCursor = (
+	page: PageCursor[],
+	limit = RECENT_PAGE_SIZE,
+): PageCursor | undefined => {
+	if (page.length < limit) return undefined;
+
+	const last = page[page.length - 1];
+
+	return { updated_at: last.updated_at, id: last.id };
+};
+
+export const filePicker = async (options?: ReadFileOptions) => {
 	const file_path = await open({
 		multiple: false,
 		directory: false,
@@ -22,10 +81,10 @@ export const filePicker = async () => {
 
 	if (!file_path) return null;
 
-	const data = (await invoke("cmd_read_file", { file_path })) as {
-		name: string;
-		contents: string;
-	};
+	const data = (await invoke("cmd_read_file", {
+		file_path,
+		options,
+	})) as FileContents;
 
 	return data;
 };
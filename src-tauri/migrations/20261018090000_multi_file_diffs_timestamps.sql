@@ -0,0 +1,11 @@
+-- `multi_file_diffs` was never written to, so it is safe to recreate it with timestamps.
+DROP TABLE if EXISTS multi_file_diffs;
+
+CREATE TABLE multi_file_diffs (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  label TEXT NOT NULL,
+  old_file TEXT NOT NULL,
+  new_file TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  updated_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
@@ -0,0 +1,10 @@
+-- User defined providers speaking the OpenAI chat completions format, eg Ollama, LM Studio or vLLM.
+-- `app_config.selected_provider` holds the provider id when one of them is selected.
+CREATE TABLE custom_providers (
+  id TEXT PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL,
+  base_url TEXT NOT NULL, -- including the version path, eg http://localhost:11434/v1
+  api_key TEXT DEFAULT '', -- local servers usually need none
+  created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  updated_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
@@ -0,0 +1,10 @@
+-- Models offered by the built in providers, reseeded from `models.json` on every start.
+CREATE TABLE models (
+  provider_id TEXT NOT NULL, -- eg google, matches `app_config.selected_provider`
+  model_id TEXT NOT NULL, -- matches `app_config.selected_model`
+  provider_name TEXT NOT NULL,
+  display_name TEXT NOT NULL,
+  context_window INTEGER NOT NULL DEFAULT 0, -- tokens
+  capabilities TEXT NOT NULL DEFAULT '[]', -- JSON array, eg ["text_input", "tool_calls"]
+  PRIMARY KEY (provider_id, model_id)
+);
@@ -0,0 +1,45 @@
+-- One row per message instead of the JSON blob in `chats.messages`.
+CREATE TABLE chat_messages (
+  chat_id TEXT NOT NULL REFERENCES chats (id) ON DELETE CASCADE,
+  id TEXT NOT NULL, -- `UIMessage.id`, only unique within its chat
+  parent_id TEXT, -- message this one answers or follows, NULL for the first
+  role TEXT NOT NULL, -- user, assistant or system
+  parts TEXT NOT NULL DEFAULT '[]', -- JSON array of `UIMessage.parts`
+  metadata TEXT, -- JSON
+  model TEXT, -- model that wrote an assistant message
+  provider TEXT,
+  input_tokens INTEGER,
+  output_tokens INTEGER,
+  position INTEGER NOT NULL, -- order within the chat
+  created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  PRIMARY KEY (chat_id, id)
+);
+
+CREATE INDEX idx_chat_messages_position ON chat_messages (chat_id, position);
+
+-- Move the existing blobs over, messages without an id get a random one.
+INSERT OR IGNORE INTO chat_messages (chat_id, id, role, parts, metadata, position, created_at)
+SELECT
+  chats.id,
+  COALESCE(json_extract(message.value, '$.id'), lower(hex(randomblob(16)))),
+  json_extract(message.value, '$.role'),
+  COALESCE(message.value -> '$.parts', '[]'),
+  NULLIF(message.value -> '$.metadata', 'null'),
+  message.key,
+  chats.created_at
+FROM chats, json_each(chats.messages) AS message
+WHERE json_valid(chats.messages)
+  AND json_type(message.value) = 'object'
+  AND json_extract(message.value, '$.role') IS NOT NULL;
+
+UPDATE chat_messages
+SET parent_id = (
+  SELECT previous.id
+  FROM chat_messages AS previous
+  WHERE previous.chat_id = chat_messages.chat_id
+    AND previous.position < chat_messages.position
+  ORDER BY previous.position DESC
+  LIMIT 1
+);
+
+ALTER TABLE chats DROP COLUMN messages;
@@ -0,0 +1,22 @@
+-- Messages of a chat form a tree through `parent_id`, a branch is the path
+-- from the first message to its head.
+CREATE TABLE chat_branches (
+  chat_id TEXT NOT NULL REFERENCES chats (id) ON DELETE CASCADE,
+  id TEXT NOT NULL,
+  head_id TEXT, -- last message of the branch, NULL while the chat is empty
+  forked_from TEXT, -- message the branch continues from, NULL for `main`
+  created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  PRIMARY KEY (chat_id, id)
+);
+
+ALTER TABLE chat_messages ADD COLUMN branch_id TEXT NOT NULL DEFAULT 'main'; -- branch the message was written on
+ALTER TABLE chats ADD COLUMN active_branch_id TEXT NOT NULL DEFAULT 'main';
+
+-- Existing chats are a single thread.
+INSERT INTO chat_branches (chat_id, id, head_id, created_at)
+SELECT
+  chats.id,
+  'main',
+  (SELECT id FROM chat_messages WHERE chat_id = chats.id ORDER BY position DESC LIMIT 1),
+  chats.created_at
+FROM chats;
@@ -0,0 +1,77 @@
+-- Full text indexes for `cmd_search`, kept in sync by the triggers below.
+-- Rows are matched by their ids rather than rowid, a VACUUM renumbers the
+-- rowids of tables without an INTEGER PRIMARY KEY.
+CREATE VIRTUAL TABLE chat_messages_fts USING fts5 (
+  text, -- text parts of the message
+  chat_id UNINDEXED,
+  message_id UNINDEXED,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE paste_bins_fts USING fts5 (
+  body,
+  attachment_names,
+  paste_id UNINDEXED,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER chat_messages_fts_insert AFTER INSERT ON chat_messages BEGIN
+  INSERT INTO chat_messages_fts (text, chat_id, message_id)
+  VALUES (
+    (SELECT group_concat(json_extract(value, '$.text'), ' ') FROM json_each(NEW.parts) WHERE json_extract(value, '$.type') = 'text'),
+    NEW.chat_id,
+    NEW.id
+  );
+END;
+
+CREATE TRIGGER chat_messages_fts_update AFTER UPDATE OF parts ON chat_messages BEGIN
+  DELETE FROM chat_messages_fts WHERE chat_id = OLD.chat_id AND message_id = OLD.id;
+  INSERT INTO chat_messages_fts (text, chat_id, message_id)
+  VALUES (
+    (SELECT group_concat(json_extract(value, '$.text'), ' ') FROM json_each(NEW.parts) WHERE json_extract(value, '$.type') = 'text'),
+    NEW.chat_id,
+    NEW.id
+  );
+END;
+
+CREATE TRIGGER chat_messages_fts_delete AFTER DELETE ON chat_messages BEGIN
+  DELETE FROM chat_messages_fts WHERE chat_id = OLD.chat_id AND message_id = OLD.id;
+END;
+
+CREATE TRIGGER paste_bins_fts_insert AFTER INSERT ON paste_bins BEGIN
+  INSERT INTO paste_bins_fts (body, attachment_names, paste_id)
+  VALUES (
+    NEW.body,
+    (SELECT group_concat(json_extract(value, '$.original_file_name'), ' ') FROM json_each(NEW.attachments)),
+    NEW.id
+  );
+END;
+
+CREATE TRIGGER paste_bins_fts_update AFTER UPDATE OF body, attachments ON paste_bins BEGIN
+  DELETE FROM paste_bins_fts WHERE paste_id = OLD.id;
+  INSERT INTO paste_bins_fts (body, attachment_names, paste_id)
+  VALUES (
+    NEW.body,
+    (SELECT group_concat(json_extract(value, '$.original_file_name'), ' ') FROM json_each(NEW.attachments)),
+    NEW.id
+  );
+END;
+
+CREATE TRIGGER paste_bins_fts_delete AFTER DELETE ON paste_bins BEGIN
+  DELETE FROM paste_bins_fts WHERE paste_id = OLD.id;
+END;
+
+-- Index what is already there.
+INSERT INTO chat_messages_fts (text, chat_id, message_id)
+SELECT
+  (SELECT group_concat(json_extract(value, '$.text'), ' ') FROM json_each(parts) WHERE json_extract(value, '$.type') = 'text'),
+  chat_id,
+  id
+FROM chat_messages;
+
+INSERT INTO paste_bins_fts (body, attachment_names, paste_id)
+SELECT
+  body,
+  (SELECT group_concat(json_extract(value, '$.original_file_name'), ' ') FROM json_each(attachments)),
+  id
+FROM paste_bins;
@@ -2,12 +2,23 @@ use crate::constants::{
     ANTHROPIC_BASE_URL, ANTHROPIC_PROVIDER_NAME, GOOGLE_BASE_URL, GOOGLE_PROVIDER_NAME,
     GROQ_BASE_URL, GROQ_PROVIDER_NAME, OPENAI_BASE_URL, OPENAI_PROVIDER_NAME,
 };
+use crate::db_chats::{
+    append_message, find_thread_to, findone_message, fork_with_message, save_chat_messages,
+    ChatsRecord, SaveChatMessage,
+};
 use crate::db_config::get_app_config;
+use crate::db_models;
+use crate::db_providers;
+use crate::diff_explain::ExplainDiffRequest;
+use crate::openai_compatible::OpenAICompatible;
 use crate::prelude::*;
+use crate::ui_message::{chat_label, to_model_messages, ChatRequest, RegenerateRequest, UIMessage};
 use crate::utils::Utils;
+use aisdk::core::capabilities::ModelName;
+use aisdk::core::language_model::{LanguageModelStream, Usage};
 use aisdk::core::LanguageModelRequest;
-use aisdk::core::{Message, StreamTextResponse};
-use aisdk::integrations::{axum::AxumSseResponse, vercel_aisdk_ui::VercelUIRequest};
+use aisdk::core::{LanguageModel, LanguageModelStreamChunkType, Message, StreamTextResponse};
+use aisdk::integrations::axum::AxumSseResponse;
 use aisdk::providers::anthropic::{
     Anthropic, ClaudeHaiku45, ClaudeOpus41, ClaudeOpus45, ClaudeSonnet45,
 };
@@ -17,10 +28,14 @@ use aisdk::providers::google::{
 };
 use aisdk::providers::groq::{Groq, Llama318bInstant};
 use aisdk::providers::openai::{Gpt51Codex, Gpt52, Gpt52ChatLatest, Gpt52Pro, OpenAI};
+use async_trait::async_trait;
 use axum::extract::State;
 use axum::routing::post;
 use axum::{Json, Router};
+use futures::StreamExt;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tauri::AppHandle;
 use tauri::Listener;
 use tauri::Manager;
@@ -29,18 +44,222 @@ use tower_http::cors::CorsLayer;
 #[axum::debug_handler]
 async fn chat_handler(
     State(db): State<Db>,
-    Json(request): Json<VercelUIRequest>,
+    Json(request): Json<ChatRequest>,
+) -> Result<AxumSseResponse, AppError> {
+    let chat = ChatsRecord {
+        label: chat_label(&request.messages),
+        id: request.id,
+    };
+
+    // Saved before the model is even looked up so the prompt is kept when
+    // there is no api key or the model call fails.
+    let branch_id = save_chat_messages(&db, &chat, &request.messages)
+        .await
+        .map_err(to_app_err)?;
+
+    let model = get_selected_model(db.clone()).await?;
+
+    let mut response = model
+        .stream_messages(to_model_messages(&request.messages))
+        .await?;
+
+    persist_response(&mut response, move |answer| async move {
+        // Partial answers are kept, eg when the model fails mid stream.
+        if answer.text.is_empty() {
+            return;
+        }
+
+        if let Err(err) = append_message(&db, &chat.id, &branch_id, answer.into()).await {
+            println!("Failed to save chat: {}", err);
+        }
+    });
+
+    Ok(response.response.into())
+}
+
+/// Answers again from `message_id` on a new branch, the earlier answers stay
+/// on theirs. An assistant message is answered again from its prompt. The
+/// branch is only created once there is an answer to put on it.
+#[axum::debug_handler]
+async fn regenerate_handler(
+    State(db): State<Db>,
+    Json(request): Json<RegenerateRequest>,
+) -> Result<AxumSseResponse, AppError> {
+    let message = findone_message(&db, &request.chat_id, &request.message_id)
+        .await
+        .map_err(to_app_err)?;
+
+    let fork_at = match message.role.as_str() {
+        "assistant" => message.parent_id.ok_or(AppError::Request(
+            "The message has no prompt to answer".to_string(),
+        ))?,
+        _ => message.id,
+    };
+
+    let thread: Vec<UIMessage> = find_thread_to(&db, &request.chat_id, &fork_at)
+        .await
+        .map_err(to_app_err)?
+        .into_iter()
+        .map(UIMessage::from)
+        .collect();
+
+    let model = get_selected_model(db.clone()).await?;
+
+    let mut response = model.stream_messages(to_model_messages(&thread)).await?;
+
+    let chat_id = request.chat_id;
+
+    persist_response(&mut response, move |answer| async move {
+        if answer.text.is_empty() {
+            return;
+        }
+
+        if let Err(err) = fork_with_message(&db, &chat_id, &fork_at, answer.into()).await {
+            println!("Failed to save chat: {}", err);
+        }
+    });
+
+    Ok(response.response.into())
+}
+
+#[axum::debug_handler]
+async fn explain_diff_handler(
+    State(db): State<Db>,
+    Json(request): Json<ExplainDiffRequest>,
 ) -> Result<AxumSseResponse, AppError> {
-    let config = get_app_config(db).await.map_err(to_app_err)?;
+    let model = get_selected_model(db.clone()).await?;
+
+    let request = Arc::new(request);
+    let prompt = {
+        let request = request.clone();
+
+        tauri::async_runtime::spawn_blocking(move || request.prompt())
+            .await
+            .map_err(|e| AppError::Runtime(e.to_string()))??
+    };
+
+    let messages = vec![
+        Message::System(prompt.system.into()),
+        Message::User(prompt.user.clone().into()),
+    ];
+
+    let mut response = model.stream_messages(messages).await?;
+
+    if let Some(chat_id) = request.chat_id.clone() {
+        let chat = ChatsRecord {
+            id: chat_id,
+            label: request.chat_label(),
+        };
+
+        persist_response(&mut response, move |answer| async move {
+            if answer.failed || answer.text.is_empty() {
+                return;
+            }
+
+            let prompt = [UIMessage::user(&prompt.user)];
+
+            let saved = match save_chat_messages(&db, &chat, &prompt).await {
+                Ok(branch_id) => append_message(&db, &chat.id, &branch_id, answer.into()).await,
+                Err(err) => Err(err),
+            };
+
+            if let Err(err) = saved {
+                println!("Failed to save chat: {}", err);
+            }
+        });
+    }
+
+    Ok(response.response.into())
+}
+
+/// What the model streamed back, collected by [`persist_response`].
+struct StreamedAnswer {
+    text: String,
+    reasoning: String,
+    /// The model failed or stopped before finishing its answer.
+    failed: bool,
+    provider: String,
+    model: String,
+    usage: Usage,
+}
+
+impl From<StreamedAnswer> for SaveChatMessage {
+    fn from(answer: StreamedAnswer) -> Self {
+        SaveChatMessage {
+            message: UIMessage::assistant(&answer.text, &answer.reasoning),
+            model: Some(answer.model),
+            provider: Some(answer.provider),
+            input_tokens: answer.usage.input_tokens.map(|t| t as i64),
+            output_tokens: answer.usage.output_tokens.map(|t| t as i64),
+        }
+    }
+}
+
+/// Forwards the model's stream through a new one, collecting the answer on
+/// the way, and hands it to `save` once the model is done. The stream is
+/// drained even if the client disconnects so the answer is still saved, and
+/// it only closes for the client after `save` so a reload sees the answer.
+fn persist_response<F, Fut>(response: &mut ChatResponse, save: F)
+where
+    F: FnOnce(StreamedAnswer) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let (tx, stream) = LanguageModelStream::new();
+    let mut upstream = std::mem::replace(&mut response.response.stream, stream);
+
+    let mut answer = StreamedAnswer {
+        text: String::new(),
+        reasoning: String::new(),
+        failed: false,
+        provider: response.provider.clone(),
+        model: response.model.clone(),
+        usage: Usage::default(),
+    };
+    let usage = response.usage.clone();
+
+    tauri::async_runtime::spawn(async move {
+        // The stream closes once the model is done, errors arrive as chunks.
+        while let Some(chunk) = upstream.next().await {
+            match &chunk {
+                LanguageModelStreamChunkType::Text(delta) => answer.text.push_str(delta),
+                LanguageModelStreamChunkType::Reasoning(delta) => answer.reasoning.push_str(delta),
+                LanguageModelStreamChunkType::Failed(_)
+                | LanguageModelStreamChunkType::Incomplete(_) => answer.failed = true,
+                _ => (),
+            }
+
+            tx.send(chunk).ok();
+        }
+
+        if let Ok(usage) = usage.lock() {
+            answer.usage = usage.clone();
+        }
+
+        save(answer).await;
+        drop(tx);
+    });
+}
+
+/// Builds the model picked in the app config with its provider's api key.
+/// Any provider other than the built in ones is looked up in `custom_providers`.
+async fn get_selected_model(db: Db) -> anyhow::Result<SelectedModel, AppError> {
+    let config = get_app_config(db.clone()).await.map_err(to_app_err)?;
 
     let provider = config.selected_provider.trim();
+    let model = config.selected_model.trim();
 
     let api_key = match provider {
         "google" => config.google_key.as_deref(),
         "anthropic" => config.anthropic_key.as_deref(),
         "groq" => config.groq_key.as_deref(),
         "openai" => config.openai_key.as_deref(),
-        _ => None,
+        _ => {
+            return Ok(SelectedModel {
+                chat_model: get_custom_model(&db, provider, model).await?,
+                provider: provider.to_string(),
+                model: model.to_string(),
+            })
+        }
     }
     .ok_or(AppError::UnsupportedProvider(provider.to_string()))?
     .trim();
@@ -49,15 +268,39 @@ async fn chat_handler(
         return Err(AppError::MissingApiKey(provider.to_string()));
     }
 
-    let model = config.selected_model.trim();
-
-    let messages: Vec<Message> = request.into();
-
-    let model = get_provider_model(provider, model, api_key)?;
-
-    let response = get_model_messages(messages, model).await?;
+    // Only models in the registry can be picked, whatever the config says.
+    db_models::findone(&db, provider, model)
+        .await
+        .map_err(|err| match to_app_err(err) {
+            AppError::NotFound => {
+                AppError::UnsupportedModel(format!("`{}` with provider `{}`", model, provider))
+            }
+            err => err,
+        })?;
+
+    Ok(SelectedModel {
+        chat_model: get_provider_model(provider, model, api_key)?,
+        provider: provider.to_string(),
+        model: model.to_string(),
+    })
+}
 
-    Ok(response.into())
+async fn get_custom_model(
+    db: &Db,
+    provider_id: &str,
+    model: &str,
+) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
+    let provider = db_providers::findone_by_id(db, provider_id)
+        .await
+        .map_err(|err| match to_app_err(err) {
+            AppError::NotFound => AppError::UnsupportedProvider(provider_id.to_string()),
+            err => err,
+        })?;
+
+    let m = OpenAICompatible::new(&provider.base_url, provider.api_key.as_deref(), model)
+        .map_err(model_setup_error)?;
+
+    Ok(Box::new(m))
 }
 
 async fn not_found_handler() -> &'static str {
@@ -88,6 +331,8 @@ pub fn init_chat_api(app: &AppHandle, db: Db) -> AppResult<()> {
 
         let app = Router::new()
             .route("/api/chat", post(chat_handler))
+            .route("/api/chat/regenerate", post(regenerate_handler))
+            .route("/api/explain-diff", post(explain_diff_handler))
             .with_state(db.clone())
             .fallback(not_found_handler)
             .layer(CorsLayer::permissive());
@@ -131,356 +376,204 @@ pub fn init_chat_api(app: &AppHandle, db: Db) -> AppResult<()> {
     Ok(())
 }
 
+/// aisdk models are each their own type, this erases them so the selected
+/// one can be picked at runtime.
+#[async_trait]
+trait ChatModel: Send {
+    async fn stream_messages(
+        self: Box<Self>,
+        messages: Vec<Message>,
+    ) -> anyhow::Result<ChatResponse, AppError>;
+}
+
+#[async_trait]
+impl<M: LanguageModel> ChatModel for M {
+    async fn stream_messages(
+        self: Box<Self>,
+        messages: Vec<Message>,
+    ) -> anyhow::Result<ChatResponse, AppError> {
+        let usage = Arc::new(std::sync::Mutex::new(Usage::default()));
+        let step_usage = usage.clone();
+
+        let response = LanguageModelRequest::builder()
+            .model(*self)
+            .messages(messages)
+            .on_step_finish(move |options| {
+                if let Ok(mut usage) = step_usage.lock() {
+                    *usage = options.usage();
+                }
+            })
+            .build()
+            .stream_text()
+            .await?;
+
+        Ok(ChatResponse {
+            response,
+            usage,
+            provider: String::new(),
+            model: String::new(),
+        })
+    }
+}
+
+/// The model picked in the app config.
+struct SelectedModel {
+    provider: String,
+    model: String,
+    chat_model: Box<dyn ChatModel>,
+}
+
+impl SelectedModel {
+    async fn stream_messages(
+        self,
+        messages: Vec<Message>,
+    ) -> anyhow::Result<ChatResponse, AppError> {
+        let response = self.chat_model.stream_messages(messages).await?;
+
+        Ok(ChatResponse {
+            provider: self.provider,
+            model: self.model,
+            ..response
+        })
+    }
+}
+
+/// A streaming answer, `usage` is filled in once the model is done.
+struct ChatResponse {
+    response: StreamTextResponse,
+    usage: Arc<std::sync::Mutex<Usage>>,
+    provider: String,
+    model: String,
+}
+
+/// Builds a model from `models.json`. aisdk knows each model by its type, so
+/// a model added to the list also needs its line here.
 fn get_provider_model(
     provider: &str,
     model: &str,
     api_key: &str,
-) -> anyhow::Result<Models, AppError> {
+) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
     match (provider, model) {
         // GOOGLE — GEMINI 3
-        ("google", "gemini-3-pro-preview") => {
-            let m = Google::<Gemini3ProPreview>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini3ProPreview(m))
-        }
+        ("google", "gemini-3-pro-preview") => google::<Gemini3ProPreview>(api_key),
 
         // GOOGLE — GEMINI 2.5
-        ("google", "gemini-2.5-pro") => {
-            let m = Google::<Gemini25Pro>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini25Pro(m))
-        }
-
-        ("google", "gemini-2.5-flash") => {
-            let m = Google::<Gemini25Flash>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini25Flash(m))
-        }
-
-        ("google", "gemini-2.5-flash-lite") => {
-            let m = Google::<Gemini25FlashLite>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini25FlashLite(m))
-        }
-
+        ("google", "gemini-2.5-pro") => google::<Gemini25Pro>(api_key),
+        ("google", "gemini-2.5-flash") => google::<Gemini25Flash>(api_key),
+        ("google", "gemini-2.5-flash-lite") => google::<Gemini25FlashLite>(api_key),
         ("google", "gemini-2.5-flash-lite-preview-06-17") => {
-            let m = Google::<Gemini25FlashLitePreview0617>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini25FlashLitePreview0617(m))
+            google::<Gemini25FlashLitePreview0617>(api_key)
         }
 
         // GOOGLE — GEMINI 2.0
-        ("google", "gemini-2.0-flash") => {
-            let m = Google::<Gemini20Flash>::builder()
-                .api_key(api_key)
-                .base_url(GOOGLE_BASE_URL)
-                .provider_name(GOOGLE_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::Gemini20Flash(m))
-        }
+        ("google", "gemini-2.0-flash") => google::<Gemini20Flash>(api_key),
 
         // Groq
-        ("groq", "llama-3.1-8b-instant") => {
-            let m = Groq::<Llama318bInstant>::builder()
-                .api_key(api_key)
-                .base_url(GROQ_BASE_URL)
-                .provider_name(GROQ_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::GroqLlama318bInstant(m))
-        }
+        ("groq", "llama-3.1-8b-instant") => groq::<Llama318bInstant>(api_key),
 
         // Anthropic
-        ("anthropic", "claude-opus-4-5") => {
-            let m = Anthropic::<ClaudeOpus45>::builder()
-                .api_key(api_key)
-                .base_url(ANTHROPIC_BASE_URL)
-                .provider_name(ANTHROPIC_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::AnthropicClaudeOpus45(m))
-        }
-        ("anthropic", "claude-haiku-4-5") => {
-            let m = Anthropic::<ClaudeHaiku45>::builder()
-                .api_key(api_key)
-                .base_url(ANTHROPIC_BASE_URL)
-                .provider_name(ANTHROPIC_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::AnthropicClaudeHaiku45(m))
-        }
-        ("anthropic", "claude-sonnet-4-5") => {
-            let m = Anthropic::<ClaudeSonnet45>::builder()
-                .api_key(api_key)
-                .base_url(ANTHROPIC_BASE_URL)
-                .provider_name(ANTHROPIC_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::AnthropicClaudeSonnet45(m))
-        }
-        ("anthropic", "claude-opus-4-1") => {
-            let m = Anthropic::<ClaudeOpus41>::builder()
-                .api_key(api_key)
-                .base_url(ANTHROPIC_BASE_URL)
-                .provider_name(ANTHROPIC_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::AnthropicClaudeOpus41(m))
-        }
+        ("anthropic", "claude-opus-4-5") => anthropic::<ClaudeOpus45>(api_key),
+        ("anthropic", "claude-haiku-4-5") => anthropic::<ClaudeHaiku45>(api_key),
+        ("anthropic", "claude-sonnet-4-5") => anthropic::<ClaudeSonnet45>(api_key),
+        ("anthropic", "claude-opus-4-1") => anthropic::<ClaudeOpus41>(api_key),
 
         // OpenAI
-        ("openai", "gpt-5.2-pro") => {
-            let m = OpenAI::<Gpt52Pro>::builder()
-                .api_key(api_key)
-                .base_url(OPENAI_BASE_URL)
-                .provider_name(OPENAI_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::OpenaiGpt52Pro(m))
-        }
-        ("openai", "gpt-5.2-chat-latest") => {
-            let m = OpenAI::<Gpt52ChatLatest>::builder()
-                .api_key(api_key)
-                .base_url(OPENAI_BASE_URL)
-                .provider_name(OPENAI_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::OpenaiGpt52ChatLatest(m))
-        }
-        ("openai", "gpt-5.2") => {
-            let m = OpenAI::<Gpt52>::builder()
-                .api_key(api_key)
-                .base_url(OPENAI_BASE_URL)
-                .provider_name(OPENAI_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::OpenaiGpt52(m))
-        }
-        ("openai", "gpt-5.1-codex") => {
-            let m = OpenAI::<Gpt51Codex>::builder()
-                .api_key(api_key)
-                .base_url(OPENAI_BASE_URL)
-                .provider_name(OPENAI_PROVIDER_NAME)
-                .build()?;
-
-            Ok(Models::OpenaiGpt51Codex(m))
-        }
+        ("openai", "gpt-5.2-pro") => openai::<Gpt52Pro>(api_key),
+        ("openai", "gpt-5.2-chat-latest") => openai::<Gpt52ChatLatest>(api_key),
+        ("openai", "gpt-5.2") => openai::<Gpt52>(api_key),
+        ("openai", "gpt-5.1-codex") => openai::<Gpt51Codex>(api_key),
 
-        _ => Ok(Models::Unsupported(format!(
-            "Model `{}` with provider `{}` is not supported",
+        _ => Err(AppError::UnsupportedModel(format!(
+            "`{}` with provider `{}`",
             model, provider
         ))),
     }
 }
 
-async fn get_model_messages(
-    messages: Vec<Message>,
-    model: Models,
-) -> anyhow::Result<StreamTextResponse, AppError> {
-    match model {
-        Models::Gemini3ProPreview(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::Gemini25Pro(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::Gemini25Flash(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::Gemini25FlashLite(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::Gemini25FlashLitePreview0617(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::Gemini20Flash(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::GroqLlama318bInstant(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::AnthropicClaudeOpus45(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::AnthropicClaudeHaiku45(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::AnthropicClaudeSonnet45(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::AnthropicClaudeOpus41(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
+/// Building a model never reaches the provider, so what fails here is the
+/// stored configuration and not the upstream, unlike the other aisdk errors.
+fn model_setup_error(err: aisdk::Error) -> AppError {
+    match err {
+        aisdk::Error::MissingField(field) if field == "model" => {
+            AppError::UnsupportedModel("No model name given".to_string())
         }
-        Models::OpenaiGpt52Pro(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::OpenaiGpt52ChatLatest(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::OpenaiGpt52(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-        Models::OpenaiGpt51Codex(model) => {
-            let request = LanguageModelRequest::builder()
-                .model(model)
-                .messages(messages)
-                .build()
-                .stream_text()
-                .await?;
-
-            Ok(request)
-        }
-
-        Models::Unsupported(msg) => panic!("{msg}"),
+        err => AppError::Request(err.to_string()),
     }
 }
 
-enum Models {
-    // GOOGLE — GEMINI 3
-    Gemini3ProPreview(Google<Gemini3ProPreview>),
+fn google<M: ModelName>(api_key: &str) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
+    let m = Google::<M>::builder()
+        .api_key(api_key)
+        .base_url(GOOGLE_BASE_URL)
+        .provider_name(GOOGLE_PROVIDER_NAME)
+        .build()
+        .map_err(model_setup_error)?;
+
+    Ok(Box::new(m))
+}
+
+fn groq<M: ModelName>(api_key: &str) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
+    let m = Groq::<M>::builder()
+        .api_key(api_key)
+        .base_url(GROQ_BASE_URL)
+        .provider_name(GROQ_PROVIDER_NAME)
+        .build()
+        .map_err(model_setup_error)?;
 
-    // GOOGLE — GEMINI 2.5
-    Gemini25Pro(Google<Gemini25Pro>),
-    Gemini25Flash(Google<Gemini25Flash>),
-    Gemini25FlashLite(Google<Gemini25FlashLite>),
-    Gemini25FlashLitePreview0617(Google<Gemini25FlashLitePreview0617>),
+    Ok(Box::new(m))
+}
+
+fn anthropic<M: ModelName>(api_key: &str) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
+    let m = Anthropic::<M>::builder()
+        .api_key(api_key)
+        .base_url(ANTHROPIC_BASE_URL)
+        .provider_name(ANTHROPIC_PROVIDER_NAME)
+        .build()
+        .map_err(model_setup_error)?;
 
-    // GOOGLE — GEMINI 2.0
-    Gemini20Flash(Google<Gemini20Flash>),
+    Ok(Box::new(m))
+}
 
-    // GROQ
-    GroqLlama318bInstant(Groq<Llama318bInstant>),
+fn openai<M: ModelName>(api_key: &str) -> anyhow::Result<Box<dyn ChatModel>, AppError> {
+    let m = OpenAI::<M>::builder()
+        .api_key(api_key)
+        .base_url(OPENAI_BASE_URL)
+        .provider_name(OPENAI_PROVIDER_NAME)
+        .build()
+        .map_err(model_setup_error)?;
 
-    // Anthropic
-    AnthropicClaudeOpus45(Anthropic<ClaudeOpus45>),
-    AnthropicClaudeHaiku45(Anthropic<ClaudeHaiku45>),
-    AnthropicClaudeSonnet45(Anthropic<ClaudeSonnet45>),
-    AnthropicClaudeOpus41(Anthropic<ClaudeOpus41>),
+    Ok(Box::new(m))
+}
 
-    // OpenAI
-    OpenaiGpt52Pro(OpenAI<Gpt52Pro>),
-    OpenaiGpt52ChatLatest(OpenAI<Gpt52ChatLatest>),
-    OpenaiGpt52(OpenAI<Gpt52>),
-    OpenaiGpt51Codex(OpenAI<Gpt51Codex>),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_every_bundled_model_builds() {
+        let models = db_models::bundled_models().expect("failed to read bundled models");
+
+        for model in models {
+            if let Err(err) = get_provider_model(&model.provider_id, &model.model_id, "key") {
+                panic!(
+                    "`{}` from `{}` is in models.json but can't be built: {}",
+                    model.model_id, model.provider_id, err
+                );
+            }
+        }
+    }
 
-    Unsupported(String),
+    #[test]
+    fn test_model_setup_errors_are_not_upstream_errors() {
+        let bad_url = OpenAICompatible::new("ftp://localhost", None, "llama3")
+            .expect_err("ftp base url should be rejected");
+        let err = model_setup_error(bad_url);
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.code(), "invalid_request");
+
+        let no_model = OpenAICompatible::new("http://localhost:11434/v1", None, " ")
+            .expect_err("empty model should be rejected");
+        assert_eq!(model_setup_error(no_model).code(), "unsupported_model");
+    }
 }
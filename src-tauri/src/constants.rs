@@ -1,6 +1,7 @@
 pub const APP_ID: &str = "Differ";
 pub const DB_ID: &str = "differ.db";
 pub const ATTACHMENTS: &str = "attachments";
+pub const IMAGE_DIFFS: &str = "image_diffs";
 
 pub const GOOGLE_PROVIDER_NAME: &str = "Google";
 pub const GOOGLE_BASE_URL: &str = "https://generativelanguage.googleapis.com";
@@ -18,3 +19,30 @@ pub const DEFAULT_AI_PROVIDER: &str = "google";
 pub const DEFAULT_PROVIDER_MODEL: &str = "gemini-2.5-flash";
 
 pub const APP_ID_PREFIX: &str = "appId_";
+
+pub const DEFAULT_DIFF_CONTEXT_LINES: usize = 3;
+pub const DIFF_TIMEOUT_SECS: u64 = 5;
+pub const WORD_DIFF_MIN_RATIO: f32 = 0.4;
+pub const DEFAULT_PATCH_FUZZ: usize = 2;
+pub const STREAM_DIFF_WINDOW_LINES: usize = 20_000;
+pub const DIFF_WATCH_DEBOUNCE_MS: u64 = 300;
+pub const MOVED_BLOCK_MIN_LINES: usize = 3;
+pub const MOVED_BLOCK_MIN_CHARS: usize = 20;
+pub const MOVED_BLOCK_MAX_GAP: usize = 2;
+pub const MOVED_BLOCK_MAX_CANDIDATES: usize = 32;
+pub const GIT_HISTORY_LIMIT: usize = 50;
+pub const GIT_SHORT_ID_LEN: usize = 7;
+pub const IMAGE_DIFF_TOLERANCE: u8 = 8;
+pub const EXPLAIN_DIFF_MAX_LINES: usize = 2_000;
+pub const SEARCH_RESULTS_LIMIT: usize = 50;
+pub const SEARCH_SNIPPET_TOKENS: usize = 16;
+/// Wrap matches in search snippets, private use characters so they can't
+/// clash with the searched text.
+pub const SEARCH_MATCH_START: &str = "\u{E000}";
+pub const SEARCH_MATCH_END: &str = "\u{E001}";
+pub const RECENT_PAGE_SIZE: usize = 30;
+pub const RECENT_PAGE_SIZE_MAX: usize = 100;
+
+pub const MAX_READ_FILE_BYTES: u64 = 50 * 1024 * 1024;
+pub const BINARY_SNIFF_BYTES: usize = 8000;
+pub const HEX_PREVIEW_BYTES: usize = 256;
@@ -1,10 +1,17 @@
+use crate::db_page::PageOptions;
 use crate::prelude::*;
+use crate::ui_message::UIMessage;
 use crate::utils::Utils;
 use crate::Db;
 
 use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::sqlite::SqliteQueryResult;
+use sqlx::{QueryBuilder, Sqlite, SqliteExecutor, Transaction};
+
+/// Branch every chat starts on.
+pub const MAIN_BRANCH_ID: &str = "main";
 
 pub async fn findone_by_id(db: &Db, chat_id: &str) -> AppResult<ChatsRecord> {
     let chat = sqlx::query_as::<_, ChatsRecord>("SELECT * FROM chats WHERE id = ?1 LIMIT 1")
@@ -16,43 +23,301 @@ pub async fn findone_by_id(db: &Db, chat_id: &str) -> AppResult<ChatsRecord> {
 }
 
 pub async fn create_chat(db: &Db, chat: ChatsRecord) -> AppResult<SqliteQueryResult> {
+    let mut tx = db.begin().await?;
     let created_at = Utils::get_timestamp();
     let updated_at = Utils::get_timestamp();
 
-    let result = sqlx::query("INSERT INTO chats (id, label, messages, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)")
-        .bind(chat.id)
-        .bind(chat.label)
-        .bind(chat.messages)
-        .bind(created_at)
-        .bind(updated_at)
-        .execute(db)
-        .await?;
+    let result = sqlx::query(
+        "INSERT INTO chats (id, label, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(&chat.id)
+    .bind(chat.label)
+    .bind(&created_at)
+    .bind(updated_at)
+    .execute(&mut *tx)
+    .await?;
+
+    create_main_branch(&mut tx, &chat.id, &created_at).await?;
+
+    tx.commit().await?;
 
     Ok(result)
 }
 
-pub async fn find_many(db: &Db) -> AppResult<Vec<ChatsRecord>> {
-    let latest_workspaces =
-        sqlx::query_as::<_, ChatsRecord>("SELECT * FROM chats ORDER BY updated_at DESC LIMIT 30;")
-            .fetch(db)
-            .try_collect()
-            .await?;
+/// A page of chats, see [`PageOptions`].
+pub async fn find_page(db: &Db, options: &PageOptions) -> AppResult<Vec<ChatSummary>> {
+    let mut query =
+        QueryBuilder::new("SELECT id, label, created_at, updated_at FROM chats WHERE 1 = 1");
+    options.push_to(&mut query, "label");
+
+    let chats = query
+        .build_query_as::<ChatSummary>()
+        .fetch(db)
+        .try_collect()
+        .await?;
+
+    Ok(chats)
+}
+
+/// Messages of the chat's active branch in the order they were sent.
+pub async fn find_messages(db: &Db, chat_id: &str) -> AppResult<Vec<ChatMessageRecord>> {
+    let branch_id = find_active_branch_id(db, chat_id).await?;
+
+    find_thread(db, chat_id, &branch_id).await
+}
+
+pub async fn findone_message(
+    db: &Db,
+    chat_id: &str,
+    message_id: &str,
+) -> AppResult<ChatMessageRecord> {
+    let message = sqlx::query_as::<_, ChatMessageRecord>(
+        "SELECT * FROM chat_messages WHERE chat_id = ?1 AND id = ?2 LIMIT 1",
+    )
+    .bind(chat_id)
+    .bind(message_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(message)
+}
+
+pub async fn findone_with_messages(db: &Db, chat_id: &str) -> AppResult<ChatWithMessages> {
+    let chat = findone_by_id(db, chat_id).await?;
+    let branch_id = find_active_branch_id(db, chat_id).await?;
+    let messages = find_messages(db, chat_id).await?;
+    let branches = find_branches(db, chat_id).await?;
+
+    Ok(ChatWithMessages {
+        chat,
+        branch_id,
+        branches,
+        messages: messages.into_iter().map(UIMessage::from).collect(),
+    })
+}
+
+/// Messages from the first one to the head of `branch_id`.
+pub async fn find_thread<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    chat_id: &str,
+    branch_id: &str,
+) -> AppResult<Vec<ChatMessageRecord>> {
+    let messages = sqlx::query_as::<_, ChatMessageRecord>(
+        "WITH RECURSIVE thread (id) AS (
+           SELECT head_id FROM chat_branches WHERE chat_id = ?1 AND id = ?2 AND head_id IS NOT NULL
+           UNION ALL
+           SELECT m.parent_id FROM chat_messages m JOIN thread ON m.chat_id = ?1 AND m.id = thread.id
+           WHERE m.parent_id IS NOT NULL
+         )
+         SELECT m.* FROM chat_messages m JOIN thread ON m.chat_id = ?1 AND m.id = thread.id
+         ORDER BY m.position ASC",
+    )
+    .bind(chat_id)
+    .bind(branch_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(messages)
+}
+
+/// Messages from the first one to `message_id`, whichever branch it is on.
+pub async fn find_thread_to<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    chat_id: &str,
+    message_id: &str,
+) -> AppResult<Vec<ChatMessageRecord>> {
+    let messages = sqlx::query_as::<_, ChatMessageRecord>(
+        "WITH RECURSIVE thread (id) AS (
+           SELECT ?2
+           UNION ALL
+           SELECT m.parent_id FROM chat_messages m JOIN thread ON m.chat_id = ?1 AND m.id = thread.id
+           WHERE m.parent_id IS NOT NULL
+         )
+         SELECT m.* FROM chat_messages m JOIN thread ON m.chat_id = ?1 AND m.id = thread.id
+         ORDER BY m.position ASC",
+    )
+    .bind(chat_id)
+    .bind(message_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(messages)
+}
+
+pub async fn find_branches(db: &Db, chat_id: &str) -> AppResult<Vec<ChatBranchRecord>> {
+    let branches = sqlx::query_as::<_, ChatBranchRecord>(
+        "SELECT * FROM chat_branches WHERE chat_id = ?1 ORDER BY created_at ASC, rowid ASC",
+    )
+    .bind(chat_id)
+    .fetch(db)
+    .try_collect()
+    .await?;
+
+    Ok(branches)
+}
+
+/// Saves the thread of the active branch, creating the chat on its first
+/// message; later calls keep the label it was created with. When `messages`
+/// leave the stored thread, eg after a regenerate, they continue on a new
+/// branch so the old one stays as it was. Returns the id of that branch.
+pub async fn save_chat_messages(
+    db: &Db,
+    chat: &ChatsRecord,
+    messages: &[UIMessage],
+) -> AppResult<String> {
+    let mut tx = db.begin().await?;
+    let timestamp = Utils::get_timestamp();
+
+    sqlx::query(
+        "INSERT INTO chats (id, label, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT(id) DO UPDATE SET updated_at = excluded.updated_at",
+    )
+    .bind(&chat.id)
+    .bind(&chat.label)
+    .bind(&timestamp)
+    .execute(&mut *tx)
+    .await?;
+
+    create_main_branch(&mut tx, &chat.id, &timestamp).await?;
+
+    let mut branch_id = find_active_branch_id(&mut *tx, &chat.id).await?;
+    let thread = find_thread(&mut *tx, &chat.id, &branch_id).await?;
+
+    let kept = thread
+        .iter()
+        .zip(messages)
+        .take_while(|(saved, message)| saved.id == message.id)
+        .count();
+
+    if kept < thread.len() {
+        let forked_from = kept.checked_sub(1).map(|i| messages[i].id.as_str());
+        branch_id = create_branch(&mut tx, &chat.id, forked_from, &timestamp).await?;
+    }
+
+    let mut parent_id: Option<&str> = None;
+
+    for (position, message) in messages.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO chat_messages (chat_id, id, parent_id, branch_id, role, parts, metadata, position, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(chat_id, id) DO UPDATE SET parts = excluded.parts, metadata = excluded.metadata",
+        )
+        .bind(&chat.id)
+        .bind(&message.id)
+        .bind(parent_id)
+        .bind(&branch_id)
+        .bind(&message.role)
+        .bind(serde_json::to_string(&message.parts)?)
+        .bind(metadata_json(&message.metadata)?)
+        .bind(position as i64)
+        .bind(&timestamp)
+        .execute(&mut *tx)
+        .await?;
+
+        parent_id = Some(&message.id);
+    }
+
+    set_branch_head(&mut tx, &chat.id, &branch_id, parent_id).await?;
+
+    tx.commit().await?;
+
+    Ok(branch_id)
+}
+
+/// Adds a message after the head of `branch_id`.
+pub async fn append_message(
+    db: &Db,
+    chat_id: &str,
+    branch_id: &str,
+    message: SaveChatMessage,
+) -> AppResult<()> {
+    let mut tx = db.begin().await?;
+    let timestamp = Utils::get_timestamp();
+
+    insert_after_head(&mut tx, chat_id, branch_id, message, &timestamp).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Starts a branch that continues from `message_id` and makes it the active
+/// one. Returns the id of the new branch.
+pub async fn fork_at_message(db: &Db, chat_id: &str, message_id: &str) -> AppResult<String> {
+    let mut tx = db.begin().await?;
+    let timestamp = Utils::get_timestamp();
+
+    sqlx::query("SELECT id FROM chat_messages WHERE chat_id = ?1 AND id = ?2")
+        .bind(chat_id)
+        .bind(message_id)
+        .fetch_one(&mut *tx)
+        .await?;
 
-    Ok(latest_workspaces)
+    let branch_id = create_branch(&mut tx, chat_id, Some(message_id), &timestamp).await?;
+
+    tx.commit().await?;
+
+    Ok(branch_id)
+}
+
+/// Starts a branch that continues from `message_id` with `message` and makes
+/// it the active one, all at once so a failed regenerate leaves no empty
+/// branch behind. Returns the id of the new branch.
+pub async fn fork_with_message(
+    db: &Db,
+    chat_id: &str,
+    message_id: &str,
+    message: SaveChatMessage,
+) -> AppResult<String> {
+    let mut tx = db.begin().await?;
+    let timestamp = Utils::get_timestamp();
+
+    sqlx::query("SELECT id FROM chat_messages WHERE chat_id = ?1 AND id = ?2")
+        .bind(chat_id)
+        .bind(message_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let branch_id = create_branch(&mut tx, chat_id, Some(message_id), &timestamp).await?;
+    insert_after_head(&mut tx, chat_id, &branch_id, message, &timestamp).await?;
+
+    tx.commit().await?;
+
+    Ok(branch_id)
+}
+
+pub async fn switch_branch(db: &Db, chat_id: &str, branch_id: &str) -> AppResult<()> {
+    let result = sqlx::query(
+        "UPDATE chats SET active_branch_id = ?1
+         WHERE id = ?2 AND EXISTS (SELECT 1 FROM chat_branches WHERE chat_id = ?2 AND id = ?1)",
+    )
+    .bind(branch_id)
+    .bind(chat_id)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    Ok(())
 }
 
-pub async fn update_chat_message(
+/// Replaces the parts of a single message, eg when it is edited.
+pub async fn update_message_parts(
     db: &Db,
     chat_id: &str,
-    messages: &str,
+    message_id: &str,
+    parts: &[Value],
 ) -> AppResult<SqliteQueryResult> {
-    let update_chat = sqlx::query("UPDATE chats SET messages = ?1 WHERE id = ?2")
-        .bind(messages)
+    let result = sqlx::query("UPDATE chat_messages SET parts = ?1 WHERE chat_id = ?2 AND id = ?3")
+        .bind(serde_json::to_string(parts)?)
         .bind(chat_id)
+        .bind(message_id)
         .execute(db)
         .await?;
 
-    Ok(update_chat)
+    Ok(result)
 }
 
 pub async fn delete_chat_by_id(db: &Db, chat_id: &str) -> AppResult<SqliteQueryResult> {
@@ -64,16 +329,245 @@ pub async fn delete_chat_by_id(db: &Db, chat_id: &str) -> AppResult<SqliteQueryR
     Ok(update_chat)
 }
 
+async fn find_active_branch_id<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    chat_id: &str,
+) -> AppResult<String> {
+    let (branch_id,): (String,) =
+        sqlx::query_as("SELECT active_branch_id FROM chats WHERE id = ?1")
+            .bind(chat_id)
+            .fetch_one(executor)
+            .await?;
+
+    Ok(branch_id)
+}
+
+async fn create_main_branch(
+    tx: &mut Transaction<'_, Sqlite>,
+    chat_id: &str,
+    timestamp: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO chat_branches (chat_id, id, created_at) VALUES (?1, ?2, ?3)",
+    )
+    .bind(chat_id)
+    .bind(MAIN_BRANCH_ID)
+    .bind(timestamp)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Adds a branch whose head is `forked_from` and makes it the active one.
+async fn create_branch(
+    tx: &mut Transaction<'_, Sqlite>,
+    chat_id: &str,
+    forked_from: Option<&str>,
+    timestamp: &str,
+) -> AppResult<String> {
+    let branch_id = Utils::get_random_id();
+
+    sqlx::query(
+        "INSERT INTO chat_branches (chat_id, id, head_id, forked_from, created_at) VALUES (?1, ?2, ?3, ?3, ?4)",
+    )
+    .bind(chat_id)
+    .bind(&branch_id)
+    .bind(forked_from)
+    .bind(timestamp)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query("UPDATE chats SET active_branch_id = ?1 WHERE id = ?2")
+        .bind(&branch_id)
+        .bind(chat_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(branch_id)
+}
+
+async fn insert_after_head(
+    tx: &mut Transaction<'_, Sqlite>,
+    chat_id: &str,
+    branch_id: &str,
+    message: SaveChatMessage,
+    timestamp: &str,
+) -> AppResult<()> {
+    let head: Option<(String, i64)> = sqlx::query_as(
+        "SELECT m.id, m.position FROM chat_branches b
+         JOIN chat_messages m ON m.chat_id = b.chat_id AND m.id = b.head_id
+         WHERE b.chat_id = ?1 AND b.id = ?2",
+    )
+    .bind(chat_id)
+    .bind(branch_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let (parent_id, position) = match head {
+        Some((id, position)) => (Some(id), position + 1),
+        None => (None, 0),
+    };
+
+    sqlx::query(
+        "INSERT INTO chat_messages (chat_id, id, parent_id, branch_id, role, parts, metadata, model, provider, input_tokens, output_tokens, position, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+    )
+    .bind(chat_id)
+    .bind(&message.message.id)
+    .bind(parent_id)
+    .bind(branch_id)
+    .bind(&message.message.role)
+    .bind(serde_json::to_string(&message.message.parts)?)
+    .bind(metadata_json(&message.message.metadata)?)
+    .bind(message.model)
+    .bind(message.provider)
+    .bind(message.input_tokens)
+    .bind(message.output_tokens)
+    .bind(position)
+    .bind(timestamp)
+    .execute(&mut **tx)
+    .await?;
+
+    set_branch_head(tx, chat_id, branch_id, Some(&message.message.id)).await?;
+    touch_chat(tx, chat_id, timestamp).await?;
+
+    Ok(())
+}
+
+async fn set_branch_head(
+    tx: &mut Transaction<'_, Sqlite>,
+    chat_id: &str,
+    branch_id: &str,
+    head_id: Option<&str>,
+) -> AppResult<()> {
+    sqlx::query("UPDATE chat_branches SET head_id = ?1 WHERE chat_id = ?2 AND id = ?3")
+        .bind(head_id)
+        .bind(chat_id)
+        .bind(branch_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+async fn touch_chat(
+    tx: &mut Transaction<'_, Sqlite>,
+    chat_id: &str,
+    timestamp: &str,
+) -> AppResult<()> {
+    sqlx::query("UPDATE chats SET updated_at = ?1 WHERE id = ?2")
+        .bind(timestamp)
+        .bind(chat_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+fn metadata_json(metadata: &Option<Value>) -> AppResult<Option<String>> {
+    let json = match metadata {
+        Some(value) => Some(serde_json::to_string(value)?),
+        None => None,
+    };
+
+    Ok(json)
+}
+
 #[derive(sqlx::FromRow, Default, Serialize, Deserialize, Clone)]
 pub struct ChatsRecord {
     pub id: String,
     pub label: String,
-    pub messages: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct ChatSummary {
+    pub id: String,
+    pub label: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A chat along with the messages of its active branch, in the shape
+/// `useChat` takes them.
+#[derive(Serialize, Clone)]
+pub struct ChatWithMessages {
+    #[serde(flatten)]
+    pub chat: ChatsRecord,
+    pub branch_id: String,
+    pub branches: Vec<ChatBranchRecord>,
+    pub messages: Vec<UIMessage>,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct ChatBranchRecord {
+    pub chat_id: String,
+    pub id: String,
+    pub head_id: Option<String>,
+    /// Branches forked from the same message are alternatives to each other.
+    pub forked_from: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct ChatMessageRecord {
+    pub chat_id: String,
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub branch_id: String,
+    pub role: String,
+
+    #[sqlx(json)]
+    pub parts: Vec<Value>,
+
+    #[sqlx(json(nullable))]
+    pub metadata: Option<Value>,
+
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub position: i64,
+    pub created_at: String,
+}
+
+impl From<ChatMessageRecord> for UIMessage {
+    fn from(record: ChatMessageRecord) -> Self {
+        UIMessage {
+            id: record.id,
+            role: record.role,
+            parts: record.parts,
+            metadata: record.metadata,
+        }
+    }
+}
+
+/// A message to append, with the model that wrote it when it is an answer.
+#[derive(Debug, Clone)]
+pub struct SaveChatMessage {
+    pub message: UIMessage,
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+}
+
+impl From<UIMessage> for SaveChatMessage {
+    fn from(message: UIMessage) -> Self {
+        SaveChatMessage {
+            message,
+            model: None,
+            provider: None,
+            input_tokens: None,
+            output_tokens: None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db_page::PageCursor;
     use sqlx::SqlitePool;
 
     async fn setup_db() -> SqlitePool {
@@ -93,7 +587,15 @@ mod tests {
         ChatsRecord {
             id: "chat_1".to_string(),
             label: "Test Chat".to_string(),
-            messages: "[]".to_string(),
+        }
+    }
+
+    fn mock_message(id: &str, role: &str, text: &str) -> UIMessage {
+        UIMessage {
+            id: id.to_string(),
+            role: role.to_string(),
+            parts: vec![serde_json::json!({ "type": "text", "text": text })],
+            metadata: None,
         }
     }
 
@@ -124,6 +626,315 @@ mod tests {
 
         assert_eq!(fetched.id, chat.id);
         assert_eq!(fetched.label, chat.label);
-        assert_eq!(fetched.messages, chat.messages);
+    }
+
+    #[tokio::test]
+    async fn test_find_page() {
+        let db = setup_db().await;
+
+        for (i, label) in ["Rebase help", "Lifetimes", "rebase onto main"]
+            .iter()
+            .enumerate()
+        {
+            let chat = ChatsRecord {
+                id: format!("chat_{}", i),
+                label: label.to_string(),
+            };
+            create_chat(&db, chat).await.expect("failed to create chat");
+
+            sqlx::query("UPDATE chats SET updated_at = ?1 WHERE id = ?2")
+                .bind(format!("2026-03-0{} 09:00:00", i + 1))
+                .bind(format!("chat_{}", i))
+                .execute(&db)
+                .await
+                .expect("failed to set updated_at");
+        }
+
+        let first = find_page(
+            &db,
+            &PageOptions {
+                limit: Some(1),
+                contains: Some("REBASE".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("failed to fetch chat page");
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].id, "chat_2");
+
+        let second = find_page(
+            &db,
+            &PageOptions {
+                cursor: Some(PageCursor {
+                    updated_at: first[0].updated_at.clone(),
+                    id: first[0].id.clone(),
+                }),
+                limit: Some(1),
+                contains: Some("REBASE".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("failed to fetch chat page");
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].id, "chat_0");
+        assert_eq!(second[0].updated_at, "2026-03-01 09:00:00");
+    }
+
+    #[tokio::test]
+    async fn test_save_chat_messages() {
+        let db = setup_db().await;
+        let chat = mock_chat();
+
+        let messages = [
+            mock_message("m1", "user", "hi"),
+            mock_message("m2", "assistant", "hello"),
+        ];
+        let branch_id = save_chat_messages(&db, &chat, &messages)
+            .await
+            .expect("failed to create chat");
+        assert_eq!(branch_id, MAIN_BRANCH_ID);
+
+        // Sending the same thread again keeps the branch.
+        let branch_id = save_chat_messages(&db, &chat, &messages)
+            .await
+            .expect("failed to update chat");
+        assert_eq!(branch_id, MAIN_BRANCH_ID);
+
+        // A regenerate drops the answer, the question is sent again.
+        let relabeled = ChatsRecord {
+            label: "Other".to_string(),
+            ..mock_chat()
+        };
+        let branch_id = save_chat_messages(&db, &relabeled, &messages[..1])
+            .await
+            .expect("failed to update chat");
+        assert_ne!(branch_id, MAIN_BRANCH_ID);
+
+        let answer = SaveChatMessage {
+            model: Some("gemini-2.5-flash".to_string()),
+            provider: Some("google".to_string()),
+            input_tokens: Some(12),
+            output_tokens: Some(30),
+            ..mock_message("m3", "assistant", "hey").into()
+        };
+        append_message(&db, &chat.id, &branch_id, answer)
+            .await
+            .expect("failed to append message");
+
+        let fetched = findone_by_id(&db, &chat.id)
+            .await
+            .expect("failed to fetch chat by id");
+        assert_eq!(fetched.label, "Test Chat");
+
+        let messages = find_messages(&db, &chat.id)
+            .await
+            .expect("failed to fetch messages");
+        let ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["m1", "m3"]);
+        assert_eq!(messages[1].parent_id.as_deref(), Some("m1"));
+        assert_eq!(messages[1].model.as_deref(), Some("gemini-2.5-flash"));
+        assert_eq!(messages[1].output_tokens, Some(30));
+
+        // The first answer is still on `main`.
+        let main = find_thread(&db, &chat.id, MAIN_BRANCH_ID)
+            .await
+            .expect("failed to fetch thread");
+        let ids: Vec<&str> = main.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["m1", "m2"]);
+
+        let branches = find_branches(&db, &chat.id)
+            .await
+            .expect("failed to fetch branches");
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[1].forked_from.as_deref(), Some("m1"));
+        assert_eq!(branches[1].head_id.as_deref(), Some("m3"));
+
+        let edited = [serde_json::json!({ "type": "text", "text": "hi there" })];
+        let result = update_message_parts(&db, &chat.id, "m1", &edited)
+            .await
+            .expect("failed to edit message");
+        assert_eq!(result.rows_affected(), 1);
+
+        let message: UIMessage = find_messages(&db, &chat.id)
+            .await
+            .expect("failed to fetch messages")
+            .remove(0)
+            .into();
+        assert_eq!(message.text(), "hi there");
+
+        delete_chat_by_id(&db, &chat.id)
+            .await
+            .expect("failed to delete chat");
+        let messages = find_thread(&db, &chat.id, MAIN_BRANCH_ID)
+            .await
+            .expect("failed to fetch messages");
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fork_and_switch_branch() {
+        let db = setup_db().await;
+        let chat = mock_chat();
+
+        let messages = [
+            mock_message("m1", "user", "hi"),
+            mock_message("m2", "assistant", "hello"),
+            mock_message("m3", "user", "how are you?"),
+        ];
+        save_chat_messages(&db, &chat, &messages)
+            .await
+            .expect("failed to create chat");
+
+        let branch_id = fork_at_message(&db, &chat.id, "m2")
+            .await
+            .expect("failed to fork chat");
+        assert!(fork_at_message(&db, &chat.id, "missing").await.is_err());
+
+        // An edited prompt continues the fork.
+        let edited = [
+            messages[0].clone(),
+            messages[1].clone(),
+            mock_message("m4", "user", "what is new?"),
+        ];
+        let saved_on = save_chat_messages(&db, &chat, &edited)
+            .await
+            .expect("failed to save edit");
+        assert_eq!(saved_on, branch_id);
+
+        let chat_with_messages = findone_with_messages(&db, &chat.id)
+            .await
+            .expect("failed to fetch chat");
+        assert_eq!(chat_with_messages.branch_id, branch_id);
+        assert_eq!(chat_with_messages.messages[2].text(), "what is new?");
+
+        switch_branch(&db, &chat.id, MAIN_BRANCH_ID)
+            .await
+            .expect("failed to switch branch");
+        assert!(switch_branch(&db, &chat.id, "missing").await.is_err());
+
+        let messages = find_messages(&db, &chat.id)
+            .await
+            .expect("failed to fetch messages");
+        assert_eq!(messages[2].id, "m3");
+        assert_eq!(messages[2].branch_id, MAIN_BRANCH_ID);
+    }
+
+    #[tokio::test]
+    async fn test_fork_with_message() {
+        let db = setup_db().await;
+        let chat = mock_chat();
+
+        let messages = [
+            mock_message("m1", "user", "hi"),
+            mock_message("m2", "assistant", "hello"),
+            mock_message("m3", "user", "how are you?"),
+        ];
+        save_chat_messages(&db, &chat, &messages)
+            .await
+            .expect("failed to create chat");
+
+        let thread = find_thread_to(&db, &chat.id, "m1")
+            .await
+            .expect("failed to fetch thread");
+        assert_eq!(thread.len(), 1);
+        assert_eq!(thread[0].id, "m1");
+
+        // Nothing changes until there is an answer.
+        assert!(fork_with_message(
+            &db,
+            &chat.id,
+            "missing",
+            mock_message("m4", "assistant", "hey").into()
+        )
+        .await
+        .is_err());
+        let branches = find_branches(&db, &chat.id)
+            .await
+            .expect("failed to fetch branches");
+        assert_eq!(branches.len(), 1);
+
+        let branch_id = fork_with_message(
+            &db,
+            &chat.id,
+            "m1",
+            mock_message("m4", "assistant", "hey").into(),
+        )
+        .await
+        .expect("failed to fork chat");
+
+        let chat_with_messages = findone_with_messages(&db, &chat.id)
+            .await
+            .expect("failed to fetch chat");
+        assert_eq!(chat_with_messages.branch_id, branch_id);
+        assert_eq!(chat_with_messages.branches.len(), 2);
+
+        let ids: Vec<&str> = chat_with_messages
+            .messages
+            .iter()
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(ids, ["m1", "m4"]);
+
+        let main = find_thread(&db, &chat.id, MAIN_BRANCH_ID)
+            .await
+            .expect("failed to fetch messages");
+        assert_eq!(main.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_messages_blob_migration() {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("failed to create sqlite pool");
+
+        // The blobs are moved by the migration, so it runs on the old schema.
+        sqlx::raw_sql(include_str!("../migrations/20260117133735_init.sql"))
+            .execute(&pool)
+            .await
+            .expect("failed to create old schema");
+
+        let blob = r#"[
+            {"id": "m1", "role": "user", "parts": [{"type": "text", "text": "hi"}]},
+            {"id": "m2", "role": "assistant", "parts": [{"type": "text", "text": "hello"}], "metadata": {"pinned": true}}
+        ]"#;
+        sqlx::query("INSERT INTO chats (id, label, messages) VALUES ('chat_1', 'Old', ?1), ('chat_2', 'Broken', 'not json')")
+            .bind(blob)
+            .execute(&pool)
+            .await
+            .expect("failed to insert chats");
+
+        sqlx::raw_sql(include_str!(
+            "../migrations/20261018120000_chat_messages.sql"
+        ))
+        .execute(&pool)
+        .await
+        .expect("failed to migrate messages");
+        sqlx::raw_sql(include_str!(
+            "../migrations/20261018130000_chat_branches.sql"
+        ))
+        .execute(&pool)
+        .await
+        .expect("failed to migrate branches");
+
+        let messages = find_messages(&pool, "chat_1")
+            .await
+            .expect("failed to fetch messages");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].parent_id, None);
+        assert_eq!(messages[1].parent_id.as_deref(), Some("m1"));
+        assert_eq!(
+            messages[1].metadata,
+            Some(serde_json::json!({"pinned": true}))
+        );
+
+        let message: UIMessage = messages[0].clone().into();
+        assert_eq!(message.text(), "hi");
+
+        let broken = find_messages(&pool, "chat_2")
+            .await
+            .expect("failed to fetch messages");
+        assert!(broken.is_empty());
     }
 }
@@ -0,0 +1,203 @@
+use crate::prelude::*;
+use crate::utils::Utils;
+use crate::Db;
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteQueryResult;
+
+pub async fn findone_by_id(db: &Db, diff_id: i64) -> AppResult<DiffRecord> {
+    let diff =
+        sqlx::query_as::<_, DiffRecord>("SELECT * FROM multi_file_diffs WHERE id = ?1 LIMIT 1")
+            .bind(diff_id)
+            .fetch_one(db)
+            .await?;
+
+    Ok(diff)
+}
+
+/// Inserts a new diff session, or updates it when `diff.id` is set.
+/// Returns the id of the saved record.
+pub async fn save_diff(db: &Db, diff: SaveDiff) -> AppResult<i64> {
+    let updated_at = Utils::get_timestamp();
+
+    if let Some(diff_id) = diff.id {
+        let result = sqlx::query(
+            "UPDATE multi_file_diffs SET label = ?1, old_file = ?2, new_file = ?3, updated_at = ?4 WHERE id = ?5",
+        )
+        .bind(diff.label)
+        .bind(diff.old_file)
+        .bind(diff.new_file)
+        .bind(updated_at)
+        .bind(diff_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+
+        return Ok(diff_id);
+    }
+
+    let created_at = Utils::get_timestamp();
+
+    let result = sqlx::query("INSERT INTO multi_file_diffs (label, old_file, new_file, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+        .bind(diff.label)
+        .bind(diff.old_file)
+        .bind(diff.new_file)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(db)
+        .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Lists recent sessions without the file contents, which can be large.
+pub async fn find_many(db: &Db) -> AppResult<Vec<DiffSummary>> {
+    let recent_diffs = sqlx::query_as::<_, DiffSummary>(
+        "SELECT id, label, created_at, updated_at FROM multi_file_diffs ORDER BY updated_at DESC, id DESC LIMIT 30;",
+    )
+    .fetch(db)
+    .try_collect()
+    .await?;
+
+    Ok(recent_diffs)
+}
+
+pub async fn delete_diff_by_id(db: &Db, diff_id: i64) -> AppResult<SqliteQueryResult> {
+    let operation = sqlx::query("DELETE FROM multi_file_diffs WHERE id = ?1")
+        .bind(diff_id)
+        .execute(db)
+        .await?;
+
+    Ok(operation)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveDiff {
+    pub id: Option<i64>,
+    pub label: String,
+    pub old_file: String,
+    pub new_file: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct DiffRecord {
+    pub id: i64,
+    pub label: String,
+    pub old_file: String,
+    pub new_file: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct DiffSummary {
+    pub id: i64,
+    pub label: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("failed to create in-memory sqlite pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run database migrations");
+
+        pool
+    }
+
+    fn mock_diff(label: &str) -> SaveDiff {
+        SaveDiff {
+            id: None,
+            label: label.to_string(),
+            old_file: "fn main() {}\n".to_string(),
+            new_file: "fn main() {\n    println!(\"hi\");\n}\n".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_and_findone_by_id() {
+        let db = setup_db().await;
+
+        let diff_id = save_diff(&db, mock_diff("main.rs"))
+            .await
+            .expect("failed to save diff");
+
+        let fetched = findone_by_id(&db, diff_id)
+            .await
+            .expect("failed to fetch diff by id");
+
+        assert_eq!(fetched.label, "main.rs");
+        assert_eq!(fetched.old_file, "fn main() {}\n");
+        assert!(!fetched.created_at.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_existing_diff_updates_it() {
+        let db = setup_db().await;
+
+        let diff_id = save_diff(&db, mock_diff("before"))
+            .await
+            .expect("failed to save diff");
+
+        let mut diff = mock_diff("after");
+        diff.id = Some(diff_id);
+
+        let updated_id = save_diff(&db, diff).await.expect("failed to update diff");
+        assert_eq!(updated_id, diff_id);
+
+        let fetched = findone_by_id(&db, diff_id)
+            .await
+            .expect("failed to fetch diff by id");
+        assert_eq!(fetched.label, "after");
+
+        let mut missing = mock_diff("missing");
+        missing.id = Some(diff_id + 100);
+        assert!(save_diff(&db, missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_many() {
+        let db = setup_db().await;
+
+        for i in 0..3 {
+            save_diff(&db, mock_diff(&format!("diff_{}", i)))
+                .await
+                .expect("failed to save diff for find_many test");
+        }
+
+        let list = find_many(&db).await.expect("failed to fetch diff list");
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].label, "diff_2");
+    }
+
+    #[tokio::test]
+    async fn test_delete_diff_by_id() {
+        let db = setup_db().await;
+
+        let diff_id = save_diff(&db, mock_diff("to_delete"))
+            .await
+            .expect("failed to save diff for delete test");
+
+        let res = delete_diff_by_id(&db, diff_id)
+            .await
+            .expect("failed to delete diff by id");
+
+        assert_eq!(res.rows_affected(), 1);
+        assert!(findone_by_id(&db, diff_id).await.is_err());
+    }
+}
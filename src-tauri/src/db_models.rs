@@ -0,0 +1,131 @@
+use crate::prelude::*;
+use crate::Db;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+/// Models of the built in providers, shipped with the app.
+const BUNDLED_MODELS: &str = include_str!("../models.json");
+
+/// Replaces the registry with the bundled list, so models dropped from an
+/// app update disappear too. Returns the number of models seeded.
+pub async fn seed_models(db: &Db) -> AppResult<usize> {
+    let models = bundled_models()?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM models").execute(&mut *tx).await?;
+
+    for model in &models {
+        let capabilities = serde_json::to_string(&model.capabilities)?;
+
+        sqlx::query("INSERT INTO models (provider_id, model_id, provider_name, display_name, context_window, capabilities) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+            .bind(&model.provider_id)
+            .bind(&model.model_id)
+            .bind(&model.provider_name)
+            .bind(&model.display_name)
+            .bind(model.context_window)
+            .bind(capabilities)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(models.len())
+}
+
+pub fn bundled_models() -> AppResult<Vec<ModelRecord>> {
+    let models = serde_json::from_str(BUNDLED_MODELS).context("Invalid bundled models.json")?;
+
+    Ok(models)
+}
+
+pub async fn findone(db: &Db, provider_id: &str, model_id: &str) -> AppResult<ModelRecord> {
+    let model = sqlx::query_as::<_, ModelRecord>(
+        "SELECT * FROM models WHERE provider_id = ?1 AND model_id = ?2 LIMIT 1",
+    )
+    .bind(provider_id)
+    .bind(model_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(model)
+}
+
+/// Lists models grouped by provider, in the order of the bundled list.
+pub async fn find_many(db: &Db) -> AppResult<Vec<ModelRecord>> {
+    let models = sqlx::query_as::<_, ModelRecord>(
+        "SELECT * FROM models ORDER BY provider_name ASC, rowid ASC;",
+    )
+    .fetch(db)
+    .try_collect()
+    .await?;
+
+    Ok(models)
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct ModelRecord {
+    pub provider_id: String,
+    pub model_id: String,
+    pub provider_name: String,
+    pub display_name: String,
+
+    /// Tokens, prompt and answer combined.
+    pub context_window: i64,
+
+    /// eg `text_input`, `image_input`, `reasoning` or `tool_calls`.
+    #[sqlx(json)]
+    pub capabilities: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("failed to create in-memory sqlite pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run database migrations");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_seed_models() {
+        let db = setup_db().await;
+
+        let seeded = seed_models(&db).await.expect("failed to seed models");
+        assert!(seeded > 0);
+
+        // Seeding again replaces the rows instead of failing on the keys.
+        let reseeded = seed_models(&db).await.expect("failed to reseed models");
+        assert_eq!(seeded, reseeded);
+
+        let models = find_many(&db).await.expect("failed to fetch models");
+        assert_eq!(models.len(), seeded);
+    }
+
+    #[tokio::test]
+    async fn test_findone() {
+        let db = setup_db().await;
+        seed_models(&db).await.expect("failed to seed models");
+
+        let model = findone(&db, "google", "gemini-2.5-flash")
+            .await
+            .expect("failed to fetch model");
+
+        assert_eq!(model.display_name, "Gemini 2.5 Flash");
+        assert!(model.context_window > 0);
+        assert!(model.capabilities.iter().any(|c| c == "text_input"));
+
+        assert!(findone(&db, "google", "gpt-5.2").await.is_err());
+    }
+}
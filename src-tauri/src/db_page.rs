@@ -0,0 +1,104 @@
+use crate::constants::{RECENT_PAGE_SIZE, RECENT_PAGE_SIZE_MAX};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite};
+
+/// Position after the last row of a page, the `updated_at` and `id` of that
+/// row.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PageCursor {
+    pub updated_at: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Most recently updated first.
+    #[default]
+    Newest,
+    Oldest,
+}
+
+/// Which page of recent chats or pastes to list. The defaults give the first
+/// page of the most recently updated ones.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PageOptions {
+    /// Continue after this row, `None` for the first page.
+    pub cursor: Option<PageCursor>,
+
+    /// Rows per page, capped at [`RECENT_PAGE_SIZE_MAX`].
+    pub limit: Option<usize>,
+
+    pub sort: SortOrder,
+
+    /// Only rows whose label (chats) or body (pastes) contains this text,
+    /// case insensitive.
+    pub contains: Option<String>,
+
+    /// Only rows updated at or after this time, `YYYY-MM-DD[ HH:MM:SS]`.
+    pub updated_after: Option<String>,
+
+    /// Only rows updated before this time, `YYYY-MM-DD[ HH:MM:SS]`.
+    pub updated_before: Option<String>,
+}
+
+impl PageOptions {
+    pub fn page_size(&self) -> usize {
+        self.limit
+            .unwrap_or(RECENT_PAGE_SIZE)
+            .clamp(1, RECENT_PAGE_SIZE_MAX)
+    }
+
+    /// Appends the filters, cursor, order and limit to `query`, which has to
+    /// end in a `WHERE` clause of a table with `updated_at` and `id` columns.
+    /// `text_column` is the column `contains` searches.
+    pub fn push_to(&self, query: &mut QueryBuilder<'_, Sqlite>, text_column: &str) {
+        if let Some(text) = self.contains.as_deref().filter(|t| !t.is_empty()) {
+            query
+                .push(format!(" AND {} LIKE ", text_column))
+                .push_bind(like_pattern(text))
+                .push(" ESCAPE '\\'");
+        }
+
+        if let Some(after) = &self.updated_after {
+            query.push(" AND updated_at >= ").push_bind(after.clone());
+        }
+
+        if let Some(before) = &self.updated_before {
+            query.push(" AND updated_at < ").push_bind(before.clone());
+        }
+
+        let (op, direction) = match self.sort {
+            SortOrder::Newest => ("<", "DESC"),
+            SortOrder::Oldest => (">", "ASC"),
+        };
+
+        if let Some(cursor) = &self.cursor {
+            query
+                .push(format!(" AND (updated_at, id) {} (", op))
+                .push_bind(cursor.updated_at.clone())
+                .push(", ")
+                .push_bind(cursor.id.clone())
+                .push(")");
+        }
+
+        query
+            .push(format!(
+                " ORDER BY updated_at {0}, id {0} LIMIT ",
+                direction
+            ))
+            .push_bind(self.page_size() as i64);
+    }
+}
+
+/// `LIKE` pattern matching `text` anywhere, with its wildcards escaped.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{}%", escaped)
+}
@@ -1,8 +1,9 @@
+use crate::db_page::PageOptions;
 use crate::prelude::*;
 use crate::Db;
 use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, sqlite::SqliteQueryResult};
+use sqlx::{prelude::FromRow, sqlite::SqliteQueryResult, QueryBuilder};
 
 pub async fn findone_by_id(db: &Db, paste_id: &str) -> AppResult<PasteRecord> {
     let paste = sqlx::query_as::<_, PasteRecord>("SELECT * FROM paste_bins WHERE id = ?1 LIMIT 1")
@@ -14,14 +15,21 @@ pub async fn findone_by_id(db: &Db, paste_id: &str) -> AppResult<PasteRecord> {
 }
 
 pub async fn find_many(db: &Db) -> AppResult<Vec<PasteRecord>> {
-    let list_recent_pastes = sqlx::query_as::<_, PasteRecord>(
-        "SELECT * FROM paste_bins ORDER BY updated_at DESC LIMIT 30;",
-    )
-    .fetch(db)
-    .try_collect()
-    .await?;
-
-    Ok(list_recent_pastes)
+    find_page(db, &PageOptions::default()).await
+}
+
+/// A page of pastes, see [`PageOptions`].
+pub async fn find_page(db: &Db, options: &PageOptions) -> AppResult<Vec<PasteRecord>> {
+    let mut query = QueryBuilder::new("SELECT * FROM paste_bins WHERE 1 = 1");
+    options.push_to(&mut query, "body");
+
+    let pastes = query
+        .build_query_as::<PasteRecord>()
+        .fetch(db)
+        .try_collect()
+        .await?;
+
+    Ok(pastes)
 }
 
 pub async fn delete_paste_by_id(db: &Db, paste_id: &str) -> AppResult<SqliteQueryResult> {
@@ -68,6 +76,7 @@ pub struct AttachmentRecord {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db_page::{PageCursor, SortOrder};
     use sqlx::SqlitePool;
 
     async fn setup_db() -> SqlitePool {
@@ -144,6 +153,76 @@ mod tests {
         assert_eq!(list.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_find_page() {
+        let db = setup_db().await;
+
+        // paste_2 and paste_3 share a timestamp, the id breaks the tie.
+        let days = ["01", "02", "03", "03", "04"];
+        for (i, day) in days.iter().enumerate() {
+            let mut paste = mock_paste(&format!("paste_{}", i));
+            paste.updated_at = format!("2026-02-{} 12:00:00", day);
+            if i == 4 {
+                paste.body = "100% done_".to_string();
+            }
+
+            create_paste(&db, paste)
+                .await
+                .expect("failed to create paste record for find_page test");
+        }
+
+        let mut options = PageOptions {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let mut ids = Vec::new();
+
+        loop {
+            let page = find_page(&db, &options)
+                .await
+                .expect("failed to fetch paste page");
+            let Some(last) = page.last() else {
+                break;
+            };
+
+            options.cursor = Some(PageCursor {
+                updated_at: last.updated_at.clone(),
+                id: last.id.clone(),
+            });
+            ids.extend(page.into_iter().map(|p| p.id));
+        }
+
+        assert_eq!(ids, ["paste_4", "paste_3", "paste_2", "paste_1", "paste_0"]);
+
+        let oldest = find_page(
+            &db,
+            &PageOptions {
+                sort: SortOrder::Oldest,
+                updated_after: Some("2026-02-02".to_string()),
+                updated_before: Some("2026-02-04".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("failed to fetch paste page");
+        let ids: Vec<&str> = oldest.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["paste_1", "paste_2", "paste_3"]);
+
+        // LIKE wildcards in the filter are matched literally.
+        for (contains, expected) in [("0% d", 1), ("E_", 1), ("d_n", 0), ("%", 1)] {
+            let page = find_page(
+                &db,
+                &PageOptions {
+                    contains: Some(contains.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("failed to fetch paste page");
+            assert_eq!(page.len(), expected, "contains {:?}", contains);
+        }
+    }
+
     #[tokio::test]
     async fn test_delete_paste_by_id() {
         let db = setup_db().await;
@@ -0,0 +1,193 @@
+use crate::prelude::*;
+use crate::utils::Utils;
+use crate::Db;
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteQueryResult;
+
+pub async fn findone_by_id(db: &Db, provider_id: &str) -> AppResult<CustomProviderRecord> {
+    let provider = sqlx::query_as::<_, CustomProviderRecord>(
+        "SELECT * FROM custom_providers WHERE id = ?1 LIMIT 1",
+    )
+    .bind(provider_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(provider)
+}
+
+/// Inserts a new provider, or updates it when `provider.id` is set.
+/// Returns the id of the saved record.
+pub async fn save_provider(db: &Db, provider: SaveCustomProvider) -> AppResult<String> {
+    let updated_at = Utils::get_timestamp();
+    let api_key = provider.api_key.unwrap_or_default();
+
+    if let Some(provider_id) = provider.id {
+        let result = sqlx::query(
+            "UPDATE custom_providers SET name = ?1, base_url = ?2, api_key = ?3, updated_at = ?4 WHERE id = ?5",
+        )
+        .bind(provider.name)
+        .bind(provider.base_url)
+        .bind(api_key)
+        .bind(updated_at)
+        .bind(&provider_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+
+        return Ok(provider_id);
+    }
+
+    let provider_id = Utils::get_random_id();
+    let created_at = Utils::get_timestamp();
+
+    sqlx::query("INSERT INTO custom_providers (id, name, base_url, api_key, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+        .bind(&provider_id)
+        .bind(provider.name)
+        .bind(provider.base_url)
+        .bind(api_key)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(db)
+        .await?;
+
+    Ok(provider_id)
+}
+
+pub async fn find_many(db: &Db) -> AppResult<Vec<CustomProviderRecord>> {
+    let providers = sqlx::query_as::<_, CustomProviderRecord>(
+        "SELECT * FROM custom_providers ORDER BY name COLLATE NOCASE ASC;",
+    )
+    .fetch(db)
+    .try_collect()
+    .await?;
+
+    Ok(providers)
+}
+
+pub async fn delete_provider_by_id(db: &Db, provider_id: &str) -> AppResult<SqliteQueryResult> {
+    let operation = sqlx::query("DELETE FROM custom_providers WHERE id = ?1")
+        .bind(provider_id)
+        .execute(db)
+        .await?;
+
+    Ok(operation)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveCustomProvider {
+    pub id: Option<String>,
+    pub name: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone)]
+pub struct CustomProviderRecord {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("failed to create in-memory sqlite pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run database migrations");
+
+        pool
+    }
+
+    fn mock_provider(name: &str) -> SaveCustomProvider {
+        SaveCustomProvider {
+            id: None,
+            name: name.to_string(),
+            base_url: "http://localhost:11434/v1".to_string(),
+            api_key: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_and_findone_by_id() {
+        let db = setup_db().await;
+
+        let provider_id = save_provider(&db, mock_provider("Ollama"))
+            .await
+            .expect("failed to save provider");
+
+        let fetched = findone_by_id(&db, &provider_id)
+            .await
+            .expect("failed to fetch provider by id");
+
+        assert_eq!(fetched.name, "Ollama");
+        assert_eq!(fetched.base_url, "http://localhost:11434/v1");
+        assert_eq!(fetched.api_key.as_deref(), Some(""));
+    }
+
+    #[tokio::test]
+    async fn test_save_existing_provider_updates_it() {
+        let db = setup_db().await;
+
+        let provider_id = save_provider(&db, mock_provider("Gateway"))
+            .await
+            .expect("failed to save provider");
+
+        let mut provider = mock_provider("Internal gateway");
+        provider.id = Some(provider_id.clone());
+        provider.api_key = Some("secret".to_string());
+
+        let updated_id = save_provider(&db, provider)
+            .await
+            .expect("failed to update provider");
+        assert_eq!(updated_id, provider_id);
+
+        let fetched = findone_by_id(&db, &provider_id)
+            .await
+            .expect("failed to fetch provider by id");
+        assert_eq!(fetched.name, "Internal gateway");
+        assert_eq!(fetched.api_key.as_deref(), Some("secret"));
+
+        let mut missing = mock_provider("missing");
+        missing.id = Some("missing".to_string());
+        assert!(save_provider(&db, missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_many_and_delete() {
+        let db = setup_db().await;
+
+        for name in ["vLLM", "LM Studio", "ollama"] {
+            save_provider(&db, mock_provider(name))
+                .await
+                .expect("failed to save provider");
+        }
+
+        let list = find_many(&db).await.expect("failed to fetch providers");
+        let names: Vec<&str> = list.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["LM Studio", "ollama", "vLLM"]);
+
+        let res = delete_provider_by_id(&db, &list[0].id)
+            .await
+            .expect("failed to delete provider");
+        assert_eq!(res.rows_affected(), 1);
+
+        let list = find_many(&db).await.expect("failed to fetch providers");
+        assert_eq!(list.len(), 2);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::constants::{SEARCH_MATCH_END, SEARCH_MATCH_START, SEARCH_SNIPPET_TOKENS};
+use crate::prelude::*;
+use crate::Db;
+
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+/// Searches chat messages and pastes, best matches first. Every word of
+/// `query` has to match, the last one as a prefix so results show up while
+/// typing.
+pub async fn search(db: &Db, query: &str, limit: usize) -> AppResult<Vec<SearchResult>> {
+    let Some(query) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let results = sqlx::query_as::<_, SearchResult>(
+        "SELECT * FROM (
+           SELECT
+             'chat' AS kind,
+             chat_messages_fts.chat_id AS id,
+             chat_messages_fts.message_id AS message_id,
+             chats.label AS label,
+             snippet(chat_messages_fts, 0, ?2, ?3, '…', ?4) AS snippet,
+             bm25(chat_messages_fts) AS rank
+           FROM chat_messages_fts
+           JOIN chats ON chats.id = chat_messages_fts.chat_id
+           WHERE chat_messages_fts MATCH ?1
+           UNION ALL
+           SELECT
+             'paste',
+             paste_bins_fts.paste_id,
+             NULL,
+             NULL,
+             snippet(paste_bins_fts, -1, ?2, ?3, '…', ?4),
+             bm25(paste_bins_fts)
+           FROM paste_bins_fts
+           WHERE paste_bins_fts MATCH ?1
+         )
+         ORDER BY rank ASC
+         LIMIT ?5",
+    )
+    .bind(query)
+    .bind(SEARCH_MATCH_START)
+    .bind(SEARCH_MATCH_END)
+    .bind(SEARCH_SNIPPET_TOKENS as i64)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await?;
+
+    Ok(results)
+}
+
+/// Quotes each word so FTS5 operators typed by the user are searched for as
+/// text. Returns `None` when there is nothing to search for.
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}*", words.join(" ")))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type, Serialize)]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Chat,
+    Paste,
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+
+    /// Id of the chat or paste.
+    pub id: String,
+
+    /// Matched message, for chats.
+    pub message_id: Option<String>,
+
+    /// Chat label, for chats.
+    pub label: Option<String>,
+
+    /// Matches are wrapped in [`SEARCH_MATCH_START`] and [`SEARCH_MATCH_END`].
+    pub snippet: String,
+
+    /// bm25 score, lower is a better match.
+    pub rank: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_chats::{save_chat_messages, update_message_parts, ChatsRecord};
+    use crate::db_pastebin::{create_paste, delete_paste_by_id, AttachmentRecord, PasteRecord};
+    use crate::ui_message::UIMessage;
+    use sqlx::SqlitePool;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("failed to create in-memory sqlite pool");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run database migrations");
+
+        pool
+    }
+
+    fn mock_paste(id: &str, body: &str, file_name: &str) -> PasteRecord {
+        PasteRecord {
+            id: id.to_string(),
+            body: body.to_string(),
+            attachments: vec![AttachmentRecord {
+                original_file_name: file_name.to_string(),
+                original_file_size: "12".to_string(),
+                path_on_disk: format!("/attachments/{}", file_name),
+            }],
+            created_at: "2026-10-18 10:00:00".to_string(),
+            updated_at: "2026-10-18 10:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(
+            fts_query(r#"merge "conflict NEAR"#).as_deref(),
+            Some(r#""merge" """conflict" "NEAR"*"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_chats_and_pastes() {
+        let db = setup_db().await;
+
+        let chat = ChatsRecord {
+            id: "chat_1".to_string(),
+            label: "Rebase help".to_string(),
+        };
+        let messages = [
+            UIMessage::user("How do I resolve a merge conflict?"),
+            UIMessage::assistant("Open the conflicted file and pick a side.", ""),
+        ];
+        save_chat_messages(&db, &chat, &messages)
+            .await
+            .expect("failed to save chat");
+
+        create_paste(&db, mock_paste("paste_1", "git merge --abort", "notes.txt"))
+            .await
+            .expect("failed to create paste");
+        create_paste(&db, mock_paste("paste_2", "unrelated", "conflicts.log"))
+            .await
+            .expect("failed to create paste");
+
+        let results = search(&db, "merge", 10).await.expect("failed to search");
+        assert_eq!(results.len(), 2);
+
+        let chat_hit = results
+            .iter()
+            .find(|r| r.kind == SearchKind::Chat)
+            .expect("missing chat result");
+        assert_eq!(chat_hit.id, "chat_1");
+        assert_eq!(
+            chat_hit.message_id.as_deref(),
+            Some(messages[0].id.as_str())
+        );
+        assert_eq!(chat_hit.label.as_deref(), Some("Rebase help"));
+        assert!(chat_hit
+            .snippet
+            .contains(&format!("{}merge{}", SEARCH_MATCH_START, SEARCH_MATCH_END)));
+
+        // Prefix match on the last word, attachment names are indexed too.
+        let results = search(&db, "conflict", 10).await.expect("failed to search");
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert!(ids.contains(&"chat_1"));
+        assert!(ids.contains(&"paste_2"));
+
+        // Operators are searched for as text instead of failing.
+        let results = search(&db, "merge AND (", 10)
+            .await
+            .expect("failed to search");
+        assert!(results.is_empty());
+
+        // The index follows edits and deletes.
+        let edited = [serde_json::json!({ "type": "text", "text": "Rebasing onto main" })];
+        update_message_parts(&db, "chat_1", &messages[0].id, &edited)
+            .await
+            .expect("failed to edit message");
+        delete_paste_by_id(&db, "paste_1")
+            .await
+            .expect("failed to delete paste");
+
+        let results = search(&db, "merge", 10).await.expect("failed to search");
+        assert!(results.is_empty());
+
+        let results = search(&db, "rebasing", 10).await.expect("failed to search");
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_survives_renumbered_rowids() {
+        let db = setup_db().await;
+
+        create_paste(&db, mock_paste("paste_1", "first", "notes.txt"))
+            .await
+            .expect("failed to create paste");
+        create_paste(&db, mock_paste("paste_2", "second", "notes.txt"))
+            .await
+            .expect("failed to create paste");
+
+        // Swaps the rowids, what a VACUUM or a table rebuild may do to tables
+        // without an INTEGER PRIMARY KEY.
+        for sql in [
+            "UPDATE paste_bins SET rowid = -rowid",
+            "UPDATE paste_bins SET rowid = 3 + rowid",
+        ] {
+            sqlx::query(sql)
+                .execute(&db)
+                .await
+                .expect("failed to renumber rowids");
+        }
+
+        delete_paste_by_id(&db, "paste_2")
+            .await
+            .expect("failed to delete paste");
+
+        let results = search(&db, "second", 10).await.expect("failed to search");
+        assert!(results.is_empty());
+
+        let results = search(&db, "first", 10).await.expect("failed to search");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "paste_1");
+    }
+}
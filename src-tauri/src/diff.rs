@@ -0,0 +1,840 @@
+use crate::constants::{DEFAULT_DIFF_CONTEXT_LINES, DIFF_TIMEOUT_SECS, WORD_DIFF_MIN_RATIO};
+use crate::diff_moves::{detect_moves, mark_moved_lines, MovedBlock};
+use crate::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use similar::{
+    capture_diff_slices_deadline, group_diff_ops, Algorithm, ChangeTag, DiffOp, DiffTag, TextDiff,
+};
+use std::borrow::Cow;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Unchanged lines kept around every hunk.
+    pub context_lines: usize,
+
+    /// Compute word level segments for replaced lines.
+    pub word_diff: bool,
+
+    /// Report blocks that were deleted in one place and inserted in another.
+    pub detect_moves: bool,
+
+    /// Treat `\r\n` and `\n` as the same, and ignore a missing final newline.
+    pub ignore_line_endings: bool,
+
+    pub ignore_trailing_whitespace: bool,
+
+    /// Compare lines with every whitespace character removed.
+    pub ignore_all_whitespace: bool,
+
+    pub ignore_case: bool,
+
+    /// Drop changes that only add or remove blank lines.
+    pub ignore_blank_lines: bool,
+
+    /// Regular expressions, changes where every line matches one of them are dropped.
+    pub ignore_matching: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: DEFAULT_DIFF_CONTEXT_LINES,
+            word_diff: true,
+            detect_moves: true,
+            ignore_line_endings: false,
+            ignore_trailing_whitespace: false,
+            ignore_all_whitespace: false,
+            ignore_case: false,
+            ignore_blank_lines: false,
+            ignore_matching: vec![],
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineSegment {
+    pub kind: ChangeKind,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: ChangeKind,
+
+    /// 1-based line number in the old text, `None` for insertions.
+    pub old_line: Option<usize>,
+
+    /// 1-based line number in the new text, `None` for deletions.
+    pub new_line: Option<usize>,
+
+    /// Line contents without the trailing line ending.
+    pub content: String,
+
+    /// Word level changes, only set for lines that were replaced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<InlineSegment>,
+
+    /// Id of the [`MovedBlock`] the line belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_id: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Hunk position and line counts, without the lines themselves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkSummary {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffMetrics {
+    pub insertions: usize,
+    pub deletions: usize,
+
+    /// Lines edited in place, the deletions paired with an insertion.
+    pub changed_lines: usize,
+
+    /// Share of lines both sides have in common, from 0.0 to 1.0.
+    pub similarity: f64,
+
+    pub hunks: Vec<HunkSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffResult {
+    pub identical: bool,
+    pub stats: DiffStats,
+    pub hunks: Vec<Hunk>,
+
+    #[serde(default)]
+    pub moves: Vec<MovedBlock>,
+}
+
+/// Computes a line diff between `old` and `new`, grouped into hunks.
+pub fn compute_diff(
+    old: &str,
+    new: &str,
+    options: &DiffOptions,
+) -> anyhow::Result<DiffResult, AppError> {
+    let normalizer = Normalizer::new(options)?;
+
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    // Move detection shares the time budget of the line diff.
+    let deadline = diff_deadline();
+    let ops = normalizer.diff(&old_lines, &new_lines);
+
+    let stats = normalizer.count_changes(&old_lines, &new_lines, &ops);
+    let mut hunks = build_hunks(&old_lines, &new_lines, &ops, &normalizer, 0, 0);
+
+    let moves = if options.detect_moves {
+        detect_moves(&old_lines, &new_lines, &ops, &normalizer, deadline)
+    } else {
+        vec![]
+    };
+
+    mark_moved_lines(&mut hunks, &moves);
+
+    Ok(DiffResult {
+        identical: hunks.is_empty(),
+        stats,
+        hunks,
+        moves,
+    })
+}
+
+/// Line counts only, for callers that do not need the hunks.
+pub fn diff_stats(
+    old: &str,
+    new: &str,
+    options: &DiffOptions,
+) -> anyhow::Result<DiffStats, AppError> {
+    let normalizer = Normalizer::new(options)?;
+
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    let ops = normalizer.diff(&old_lines, &new_lines);
+
+    Ok(normalizer.count_changes(&old_lines, &new_lines, &ops))
+}
+
+/// Line counts, similarity and hunk positions, cheaper than [`compute_diff`]
+/// since no hunk lines or word segments are built.
+pub fn diff_metrics(
+    old: &str,
+    new: &str,
+    options: &DiffOptions,
+) -> anyhow::Result<DiffMetrics, AppError> {
+    let normalizer = Normalizer::new(options)?;
+
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    let ops = normalizer.diff(&old_lines, &new_lines);
+    let stats = normalizer.count_changes(&old_lines, &new_lines, &ops);
+
+    let changed_lines = ops
+        .iter()
+        .filter(|op| {
+            op.tag() == DiffTag::Replace && !normalizer.is_ignored(&old_lines, &new_lines, op)
+        })
+        .map(|op| op.old_range().len().min(op.new_range().len()))
+        .sum();
+
+    let hunks = group_diff_ops(ops, options.context_lines)
+        .iter()
+        .filter_map(|group| {
+            let stats = normalizer.count_changes(&old_lines, &new_lines, group);
+
+            // Same rule as `build_hunks`, groups of ignored changes are dropped.
+            if stats == DiffStats::default() {
+                return None;
+            }
+
+            let old_range = group_range(group, |op| op.old_range());
+            let new_range = group_range(group, |op| op.new_range());
+
+            Some(HunkSummary {
+                old_start: hunk_start(old_range.0, old_range.1),
+                old_lines: old_range.1,
+                new_start: hunk_start(new_range.0, new_range.1),
+                new_lines: new_range.1,
+                insertions: stats.insertions,
+                deletions: stats.deletions,
+            })
+        })
+        .collect();
+
+    // Ignored changes count as lines in common.
+    let total = old_lines.len() + new_lines.len();
+    let similarity = if total == 0 {
+        1.0
+    } else {
+        (total - stats.insertions - stats.deletions) as f64 / total as f64
+    };
+
+    Ok(DiffMetrics {
+        insertions: stats.insertions,
+        deletions: stats.deletions,
+        changed_lines,
+        similarity,
+        hunks,
+    })
+}
+
+/// Orders `candidates` from the most to the least similar to `target`, ties
+/// keep their original order.
+pub fn rank_by_similarity<K>(
+    target: &str,
+    candidates: Vec<(K, String)>,
+    options: &DiffOptions,
+) -> anyhow::Result<Vec<(K, DiffMetrics)>, AppError> {
+    let mut ranked = candidates
+        .into_iter()
+        .map(|(key, text)| Ok((key, diff_metrics(&text, target, options)?)))
+        .collect::<anyhow::Result<Vec<_>, AppError>>()?;
+
+    ranked.sort_by(|(_, a), (_, b)| b.similarity.total_cmp(&a.similarity));
+
+    Ok(ranked)
+}
+
+/// Splits text into lines, keeping the line endings so that `\r\n` vs `\n`
+/// and a missing final newline are reported as changes.
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+pub(crate) fn diff_lines<T: Hash + Eq + Ord>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    capture_diff_slices_deadline(Algorithm::Myers, old, new, Some(diff_deadline()))
+}
+
+/// When a diff started now gives up and settles for a coarser result.
+pub(crate) fn diff_deadline() -> Instant {
+    Instant::now() + Duration::from_secs(DIFF_TIMEOUT_SECS)
+}
+
+/// Applies the ignore options of [`DiffOptions`]. Lines are compared by a
+/// normalised key but always displayed as written.
+pub(crate) struct Normalizer<'a> {
+    options: &'a DiffOptions,
+    ignore_matching: Vec<Regex>,
+}
+
+impl<'a> Normalizer<'a> {
+    pub fn new(options: &'a DiffOptions) -> anyhow::Result<Self, AppError> {
+        let ignore_matching = options
+            .ignore_matching
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| AppError::Diff(format!("Invalid ignore pattern: {}", e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            options,
+            ignore_matching,
+        })
+    }
+
+    pub fn options(&self) -> &DiffOptions {
+        self.options
+    }
+
+    pub fn diff(&self, old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+        let options = self.options;
+        let normalizes = options.ignore_line_endings
+            || options.ignore_trailing_whitespace
+            || options.ignore_all_whitespace
+            || options.ignore_case;
+
+        if !normalizes {
+            return diff_lines(old, new);
+        }
+
+        let old_keys: Vec<Cow<str>> = old.iter().map(|line| self.key(line)).collect();
+        let new_keys: Vec<Cow<str>> = new.iter().map(|line| self.key(line)).collect();
+
+        diff_lines(&old_keys, &new_keys)
+    }
+
+    pub fn key<'l>(&self, line: &'l str) -> Cow<'l, str> {
+        let options = self.options;
+
+        let mut key = Cow::Borrowed(line);
+
+        if options.ignore_all_whitespace {
+            key = Cow::Owned(key.chars().filter(|c| !c.is_whitespace()).collect());
+        } else if options.ignore_trailing_whitespace {
+            key = Cow::Borrowed(line.trim_end());
+        } else if options.ignore_line_endings {
+            key = Cow::Borrowed(trim_line_ending(line));
+        }
+
+        if options.ignore_case {
+            key = Cow::Owned(key.to_lowercase());
+        }
+
+        key
+    }
+
+    fn is_ignorable(&self, line: &str) -> bool {
+        let line = trim_line_ending(line);
+
+        (self.options.ignore_blank_lines && line.trim().is_empty())
+            || self.ignore_matching.iter().any(|re| re.is_match(line))
+    }
+
+    /// A change made only of ignorable lines does not count as a change.
+    pub fn is_ignored(&self, old: &[&str], new: &[&str], op: &DiffOp) -> bool {
+        if op.tag() == DiffTag::Equal {
+            return false;
+        }
+
+        if !self.options.ignore_blank_lines && self.ignore_matching.is_empty() {
+            return false;
+        }
+
+        old[op.old_range()]
+            .iter()
+            .chain(new[op.new_range()].iter())
+            .all(|line| self.is_ignorable(line))
+    }
+
+    pub fn count_changes(&self, old: &[&str], new: &[&str], ops: &[DiffOp]) -> DiffStats {
+        let mut stats = DiffStats::default();
+
+        for op in ops {
+            if self.is_ignored(old, new, op) {
+                continue;
+            }
+
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+
+            match tag {
+                DiffTag::Equal => (),
+                DiffTag::Delete => stats.deletions += old_range.len(),
+                DiffTag::Insert => stats.insertions += new_range.len(),
+                DiffTag::Replace => {
+                    stats.deletions += old_range.len();
+                    stats.insertions += new_range.len();
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+/// Groups `ops` into hunks, leaving out groups with ignored changes only. The
+/// offsets are added to every line number, which lets callers diff a window of
+/// a larger file.
+pub(crate) fn build_hunks(
+    old: &[&str],
+    new: &[&str],
+    ops: &[DiffOp],
+    normalizer: &Normalizer,
+    old_offset: usize,
+    new_offset: usize,
+) -> Vec<Hunk> {
+    let options = normalizer.options();
+
+    group_diff_ops(ops.to_vec(), options.context_lines)
+        .iter()
+        .filter(|group| {
+            group
+                .iter()
+                .any(|op| op.tag() != DiffTag::Equal && !normalizer.is_ignored(old, new, op))
+        })
+        .map(|group| build_hunk(old, new, group, options, old_offset, new_offset))
+        .collect()
+}
+
+fn build_hunk(
+    old: &[&str],
+    new: &[&str],
+    group: &[DiffOp],
+    options: &DiffOptions,
+    old_offset: usize,
+    new_offset: usize,
+) -> Hunk {
+    let mut lines = vec![];
+
+    for op in group {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+
+        match tag {
+            DiffTag::Equal => {
+                for (i, j) in old_range.zip(new_range) {
+                    lines.push(DiffLine {
+                        kind: ChangeKind::Equal,
+                        old_line: Some(old_offset + i + 1),
+                        new_line: Some(new_offset + j + 1),
+                        content: trim_line_ending(old[i]).to_string(),
+                        segments: vec![],
+                        move_id: None,
+                    });
+                }
+            }
+            DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                let mut deleted: Vec<DiffLine> = old_range
+                    .map(|i| DiffLine {
+                        kind: ChangeKind::Delete,
+                        old_line: Some(old_offset + i + 1),
+                        new_line: None,
+                        content: trim_line_ending(old[i]).to_string(),
+                        segments: vec![],
+                        move_id: None,
+                    })
+                    .collect();
+
+                let mut inserted: Vec<DiffLine> = new_range
+                    .map(|j| DiffLine {
+                        kind: ChangeKind::Insert,
+                        old_line: None,
+                        new_line: Some(new_offset + j + 1),
+                        content: trim_line_ending(new[j]).to_string(),
+                        segments: vec![],
+                        move_id: None,
+                    })
+                    .collect();
+
+                // Pair replaced lines in order, extra lines stay whole-line changes.
+                if options.word_diff && tag == DiffTag::Replace {
+                    for (d, i) in deleted.iter_mut().zip(inserted.iter_mut()) {
+                        let (old_segments, new_segments) = diff_words(&d.content, &i.content);
+
+                        d.segments = old_segments;
+                        i.segments = new_segments;
+                    }
+                }
+
+                lines.extend(deleted);
+                lines.extend(inserted);
+            }
+        }
+    }
+
+    let old_range = group_range(group, |op| op.old_range());
+    let new_range = group_range(group, |op| op.new_range());
+
+    Hunk {
+        old_start: hunk_start(old_offset + old_range.0, old_range.1),
+        old_lines: old_range.1,
+        new_start: hunk_start(new_offset + new_range.0, new_range.1),
+        new_lines: new_range.1,
+        lines,
+    }
+}
+
+/// Start index and length covered by a group of ops.
+pub(crate) fn group_range<F>(group: &[DiffOp], range: F) -> (usize, usize)
+where
+    F: Fn(&DiffOp) -> std::ops::Range<usize>,
+{
+    let start = group.first().map(|op| range(op).start).unwrap_or(0);
+    let end = group.last().map(|op| range(op).end).unwrap_or(start);
+
+    (start, end - start)
+}
+
+/// Unified diff convention: an empty range points at the line before it.
+pub(crate) fn hunk_start(index: usize, len: usize) -> usize {
+    if len == 0 {
+        index
+    } else {
+        index + 1
+    }
+}
+
+/// Word level diff of a replaced line. Returns the segments for the old and
+/// new side, or nothing when the lines share too little to be useful.
+fn diff_words(old: &str, new: &str) -> (Vec<InlineSegment>, Vec<InlineSegment>) {
+    let diff = TextDiff::configure()
+        .timeout(Duration::from_secs(DIFF_TIMEOUT_SECS))
+        .diff_words(old, new);
+
+    if diff.ratio() < WORD_DIFF_MIN_RATIO {
+        return (vec![], vec![]);
+    }
+
+    let mut old_segments: Vec<InlineSegment> = vec![];
+    let mut new_segments: Vec<InlineSegment> = vec![];
+
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+
+        match change.tag() {
+            ChangeTag::Equal => {
+                push_segment(&mut old_segments, ChangeKind::Equal, value);
+                push_segment(&mut new_segments, ChangeKind::Equal, value);
+            }
+            ChangeTag::Delete => push_segment(&mut old_segments, ChangeKind::Delete, value),
+            ChangeTag::Insert => push_segment(&mut new_segments, ChangeKind::Insert, value),
+        }
+    }
+
+    (old_segments, new_segments)
+}
+
+/// Appends to the last segment when it has the same kind.
+fn push_segment(segments: &mut Vec<InlineSegment>, kind: ChangeKind, value: &str) {
+    match segments.last_mut() {
+        Some(last) if last.kind == kind => last.value.push_str(value),
+        _ => segments.push(InlineSegment {
+            kind,
+            value: value.to_string(),
+        }),
+    }
+}
+
+pub(crate) fn trim_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed_lines(result: &DiffResult) -> Vec<(ChangeKind, String)> {
+        result
+            .hunks
+            .iter()
+            .flat_map(|h| h.lines.iter())
+            .filter(|l| l.kind != ChangeKind::Equal)
+            .map(|l| (l.kind, l.content.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_texts_have_no_hunks() {
+        let result =
+            compute_diff("a\nb\n", "a\nb\n", &DiffOptions::default()).expect("failed to diff");
+
+        assert!(result.identical);
+        assert!(result.hunks.is_empty());
+        assert_eq!(result.stats.insertions, 0);
+        assert_eq!(result.stats.deletions, 0);
+    }
+
+    #[test]
+    fn test_single_line_change() {
+        let old = "one\ntwo\nthree\n";
+        let new = "one\n2\nthree\n";
+
+        let result = compute_diff(old, new, &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(result.hunks.len(), 1);
+        assert_eq!(result.stats.insertions, 1);
+        assert_eq!(result.stats.deletions, 1);
+        assert_eq!(
+            changed_lines(&result),
+            vec![
+                (ChangeKind::Delete, "two".to_string()),
+                (ChangeKind::Insert, "2".to_string()),
+            ]
+        );
+
+        let hunk = &result.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines), (1, 3));
+        assert_eq!((hunk.new_start, hunk.new_lines), (1, 3));
+    }
+
+    #[test]
+    fn test_hunks_are_split_by_context() {
+        let old: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+
+        let result = compute_diff(&old, &new, &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(result.hunks.len(), 2);
+        assert_eq!(result.stats.insertions, 1);
+        assert_eq!(result.stats.deletions, 2);
+
+        let last = &result.hunks[1];
+        assert_eq!((last.old_start, last.old_lines), (15, 6));
+        assert_eq!((last.new_start, last.new_lines), (15, 5));
+    }
+
+    #[test]
+    fn test_insertion_into_empty_text() {
+        let result = compute_diff("", "hello\n", &DiffOptions::default()).expect("failed to diff");
+
+        let hunk = &result.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines), (0, 0));
+        assert_eq!((hunk.new_start, hunk.new_lines), (1, 1));
+        assert_eq!(hunk.lines[0].new_line, Some(1));
+    }
+
+    #[test]
+    fn test_word_segments_for_replaced_lines() {
+        let result = compute_diff(
+            "let value = 1;\n",
+            "let value = 2;\n",
+            &DiffOptions::default(),
+        )
+        .expect("failed to diff");
+
+        let lines = &result.hunks[0].lines;
+        let deleted: Vec<_> = lines[0]
+            .segments
+            .iter()
+            .filter(|s| s.kind == ChangeKind::Delete)
+            .map(|s| s.value.as_str())
+            .collect();
+        let inserted: Vec<_> = lines[1]
+            .segments
+            .iter()
+            .filter(|s| s.kind == ChangeKind::Insert)
+            .map(|s| s.value.as_str())
+            .collect();
+
+        assert_eq!(deleted, vec!["1;"]);
+        assert_eq!(inserted, vec!["2;"]);
+    }
+
+    #[test]
+    fn test_line_endings_are_significant_by_default() {
+        let result =
+            compute_diff("a\nb\n", "a\r\nb\r\n", &DiffOptions::default()).expect("failed to diff");
+
+        assert!(!result.identical);
+        assert_eq!(result.stats.deletions, 2);
+        assert_eq!(result.hunks[0].lines[0].content, "a");
+    }
+
+    #[test]
+    fn test_ignore_line_endings() {
+        let options = DiffOptions {
+            ignore_line_endings: true,
+            ..DiffOptions::default()
+        };
+
+        let result = compute_diff("a\nb\nc", "a\r\nb\r\nc\r\n", &options).expect("failed to diff");
+
+        assert!(result.identical);
+        assert_eq!(result.stats, DiffStats::default());
+    }
+
+    #[test]
+    fn test_ignore_whitespace_and_case() {
+        let old = "fn main() {\n    let a = 1;\n}\n";
+        let new = "fn main() {  \n  let  a=1;\n}\n";
+
+        let trailing = DiffOptions {
+            ignore_trailing_whitespace: true,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(old, new, &trailing).expect("failed to diff");
+        assert_eq!(
+            changed_lines(&result),
+            vec![
+                (ChangeKind::Delete, "    let a = 1;".to_string()),
+                (ChangeKind::Insert, "  let  a=1;".to_string()),
+            ]
+        );
+
+        let all = DiffOptions {
+            ignore_all_whitespace: true,
+            ..DiffOptions::default()
+        };
+        assert!(
+            compute_diff(old, new, &all)
+                .expect("failed to diff")
+                .identical
+        );
+
+        let case = DiffOptions {
+            ignore_case: true,
+            ..DiffOptions::default()
+        };
+        assert!(
+            compute_diff("SELECT 1\n", "select 1\n", &case)
+                .expect("failed to diff")
+                .identical
+        );
+    }
+
+    #[test]
+    fn test_ignore_blank_and_matching_lines() {
+        let old = "a\nb\n// generated 2024\nc\n";
+        let new = "a\n\nb\n// generated 2025\nc\nd\n";
+
+        let options = DiffOptions {
+            ignore_blank_lines: true,
+            ignore_matching: vec!["^// generated".to_string()],
+            context_lines: 0,
+            ..DiffOptions::default()
+        };
+
+        let result = compute_diff(old, new, &options).expect("failed to diff");
+
+        assert_eq!(result.hunks.len(), 1);
+        assert_eq!(result.stats.insertions, 1);
+        assert_eq!(result.stats.deletions, 0);
+        assert_eq!(
+            changed_lines(&result),
+            vec![(ChangeKind::Insert, "d".to_string())]
+        );
+
+        let stats = diff_stats(old, new, &options).expect("failed to diff");
+        assert_eq!(stats, result.stats);
+    }
+
+    #[test]
+    fn test_invalid_ignore_pattern_is_an_error() {
+        let options = DiffOptions {
+            ignore_matching: vec!["(".to_string()],
+            ..DiffOptions::default()
+        };
+
+        assert!(compute_diff("a\n", "b\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_metrics_match_the_full_diff() {
+        let old: String = (1..=30).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 20\n", "line 20\nextra\n");
+
+        let result = compute_diff(&old, &new, &DiffOptions::default()).expect("failed to diff");
+        let metrics = diff_metrics(&old, &new, &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!((metrics.insertions, metrics.deletions), (2, 1));
+        assert_eq!(metrics.changed_lines, 1);
+        assert_eq!(metrics.similarity, 58.0 / 61.0);
+
+        let headers: Vec<_> = result
+            .hunks
+            .iter()
+            .map(|h| (h.old_start, h.old_lines, h.new_start, h.new_lines))
+            .collect();
+        let summaries: Vec<_> = metrics
+            .hunks
+            .iter()
+            .map(|h| (h.old_start, h.old_lines, h.new_start, h.new_lines))
+            .collect();
+        assert_eq!(summaries, headers);
+        assert_eq!(
+            (metrics.hunks[1].insertions, metrics.hunks[1].deletions),
+            (1, 0)
+        );
+    }
+
+    #[test]
+    fn test_similarity_bounds_and_ignored_changes() {
+        let options = DiffOptions::default();
+
+        assert_eq!(diff_metrics("", "", &options).unwrap().similarity, 1.0);
+        assert_eq!(
+            diff_metrics("a\n", "a\n", &options).unwrap().similarity,
+            1.0
+        );
+        assert_eq!(
+            diff_metrics("a\nb\n", "c\n", &options).unwrap().similarity,
+            0.0
+        );
+
+        let options = DiffOptions {
+            ignore_case: true,
+            ..DiffOptions::default()
+        };
+        let metrics = diff_metrics("Hello\n", "hello\n", &options).unwrap();
+        assert_eq!(metrics.similarity, 1.0);
+        assert!(metrics.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_rank_by_similarity() {
+        let target = "alpha\nbeta\ngamma\ndelta\n";
+        let candidates = vec![
+            ("far", "one\ntwo\n".to_string()),
+            ("close", "alpha\nbeta\ngamma\n".to_string()),
+            ("exact", target.to_string()),
+        ];
+
+        let ranked = rank_by_similarity(target, candidates, &DiffOptions::default())
+            .expect("failed to rank");
+        let order: Vec<_> = ranked.iter().map(|(key, _)| *key).collect();
+
+        assert_eq!(order, vec!["exact", "close", "far"]);
+    }
+}
@@ -0,0 +1,591 @@
+use crate::diff::{diff_stats, DiffOptions, DiffStats, Normalizer};
+use crate::file_text::decode_text;
+use crate::prelude::*;
+use crate::utils::Utils;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirDiffOptions {
+    /// `.gitignore` style patterns, matched relative to each root.
+    pub exclude: Vec<String>,
+
+    /// Honour `.gitignore` and `.ignore` files found inside the trees.
+    pub respect_gitignore: bool,
+
+    /// Report files that are identical on both sides.
+    pub include_unchanged: bool,
+
+    /// Line comparison options, files whose only differences are ignored
+    /// count as unchanged.
+    pub diff: DiffOptions,
+}
+
+impl Default for DirDiffOptions {
+    fn default() -> Self {
+        Self {
+            exclude: vec![],
+            respect_gitignore: true,
+            include_unchanged: true,
+            diff: DiffOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntryDiff {
+    /// Path relative to both roots, always `/` separated.
+    pub path: String,
+    pub status: EntryStatus,
+    pub binary: bool,
+
+    /// Line stats, `None` for binary files.
+    pub stats: Option<DiffStats>,
+
+    /// Why the file could not be compared, eg it is unreadable. `stats` is
+    /// `None` then.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirDiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+
+    /// Files that could not be compared, not counted in the other totals.
+    #[serde(default)]
+    pub errors: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirDiffResult {
+    pub summary: DirDiffSummary,
+    pub entries: Vec<DirEntryDiff>,
+
+    /// Walk errors that name no file, reported once for the whole diff.
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// Walks both trees, pairs files by relative path and diffs every pair.
+pub fn diff_directories(
+    old_dir: &str,
+    new_dir: &str,
+    options: &DirDiffOptions,
+) -> anyhow::Result<DirDiffResult, AppError> {
+    let old_root = PathBuf::from(Utils::normalise_path(old_dir));
+    let new_root = PathBuf::from(Utils::normalise_path(new_dir));
+
+    // Bad patterns fail the diff once instead of every file that uses them.
+    Normalizer::new(&options.diff)?;
+    let old_excludes = build_excludes(&old_root, &options.exclude)?;
+    let new_excludes = build_excludes(&new_root, &options.exclude)?;
+
+    let old_tree = collect_files(&old_root, old_excludes, options)?;
+    let new_tree = collect_files(&new_root, new_excludes, options)?;
+
+    Ok(compare_trees(&old_tree, &new_tree, options))
+}
+
+/// Pairs the files of both trees by relative path and diffs every pair.
+fn compare_trees(
+    old_tree: &CollectedFiles,
+    new_tree: &CollectedFiles,
+    options: &DirDiffOptions,
+) -> DirDiffResult {
+    let paths: BTreeSet<&String> = old_tree
+        .files
+        .keys()
+        .chain(old_tree.errors.keys())
+        .chain(new_tree.files.keys())
+        .chain(new_tree.errors.keys())
+        .collect();
+
+    let mut summary = DirDiffSummary::default();
+    let mut entries = vec![];
+
+    for path in paths {
+        let in_old = old_tree.contains(path);
+        let in_new = new_tree.contains(path);
+
+        let status = match (in_old, in_new) {
+            (true, true) => EntryStatus::Modified,
+            (true, false) => EntryStatus::Removed,
+            _ => EntryStatus::Added,
+        };
+
+        let walk_error = old_tree.errors.get(path).or(new_tree.errors.get(path));
+
+        let compared = match walk_error {
+            Some(error) => Err(AppError::File(error.clone())),
+            None => match (old_tree.files.get(path), new_tree.files.get(path)) {
+                (Some(old), Some(new)) => compare_files(path, old, new, &options.diff),
+                (Some(old), None) => single_file(path, old, status, &options.diff),
+                (None, Some(new)) => single_file(path, new, status, &options.diff),
+                (None, None) => continue,
+            },
+        };
+
+        // One unreadable file should not fail the whole comparison.
+        let entry = match compared {
+            Ok(entry) => entry,
+            Err(err) => {
+                summary.errors += 1;
+                entries.push(DirEntryDiff {
+                    path: path.to_string(),
+                    status,
+                    binary: false,
+                    stats: None,
+                    error: Some(err.to_string()),
+                });
+                continue;
+            }
+        };
+
+        match entry.status {
+            EntryStatus::Added => summary.added += 1,
+            EntryStatus::Removed => summary.removed += 1,
+            EntryStatus::Modified => summary.modified += 1,
+            EntryStatus::Unchanged => summary.unchanged += 1,
+        }
+
+        if entry.status == EntryStatus::Unchanged && !options.include_unchanged {
+            continue;
+        }
+
+        entries.push(entry);
+    }
+
+    let errors = old_tree
+        .walk_errors
+        .iter()
+        .chain(&new_tree.walk_errors)
+        .cloned()
+        .collect();
+
+    DirDiffResult {
+        summary,
+        entries,
+        errors,
+    }
+}
+
+#[derive(Default)]
+struct CollectedFiles {
+    /// Relative path to absolute path.
+    files: BTreeMap<String, PathBuf>,
+
+    /// Relative path to the error hit while walking it.
+    errors: BTreeMap<String, String>,
+
+    /// Errors the walker could not tie to a path.
+    walk_errors: Vec<String>,
+}
+
+impl CollectedFiles {
+    fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(path) || self.errors.contains_key(path)
+    }
+
+    fn add_error(&mut self, path: String, error: String) {
+        self.errors
+            .entry(path)
+            .and_modify(|existing| {
+                existing.push_str("; ");
+                existing.push_str(&error);
+            })
+            .or_insert(error);
+    }
+}
+
+/// Every file under `root` that is not excluded. Entries the walker fails on
+/// are collected as errors instead of failing the walk.
+fn collect_files(
+    root: &Path,
+    excludes: Gitignore,
+    options: &DirDiffOptions,
+) -> anyhow::Result<CollectedFiles, AppError> {
+    if !root.is_dir() {
+        return Err(AppError::File(format!(
+            "Not a directory: {}",
+            root.display()
+        )));
+    }
+
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .parents(false)
+        .git_global(false)
+        .require_git(false)
+        .git_ignore(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+
+            if is_dir && entry.file_name() == ".git" {
+                return false;
+            }
+
+            !excludes.matched(entry.path(), is_dir).is_ignore()
+        })
+        .build();
+
+    let mut collected = CollectedFiles::default();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                match error_path(&err) {
+                    Some(path) => collected.add_error(relative_path(root, path), err.to_string()),
+                    None => collected.walk_errors.push(err.to_string()),
+                }
+                continue;
+            }
+        };
+
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        collected
+            .files
+            .insert(relative_path(root, entry.path()), entry.into_path());
+    }
+
+    Ok(collected)
+}
+
+/// `path` relative to `root`, `/` separated.
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Loop { child, .. } => Some(child),
+        _ => None,
+    }
+}
+
+fn build_excludes(root: &Path, patterns: &[String]) -> anyhow::Result<Gitignore, AppError> {
+    let mut builder = GitignoreBuilder::new(root);
+
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| AppError::File(format!("Invalid exclude pattern: {}", e)))?;
+    }
+
+    builder
+        .build()
+        .map_err(|e| AppError::File(format!("Invalid exclude pattern: {}", e)))
+}
+
+fn compare_files(
+    path: &str,
+    old: &Path,
+    new: &Path,
+    options: &DiffOptions,
+) -> anyhow::Result<DirEntryDiff, AppError> {
+    let old_bytes = read_bytes(old)?;
+    let new_bytes = read_bytes(new)?;
+
+    if old_bytes == new_bytes {
+        return Ok(DirEntryDiff {
+            path: path.to_string(),
+            status: EntryStatus::Unchanged,
+            binary: false,
+            stats: Some(DiffStats::default()),
+            error: None,
+        });
+    }
+
+    let stats = match (decode_text(&old_bytes), decode_text(&new_bytes)) {
+        (Some(old), Some(new)) => Some(diff_stats(&old.text, &new.text, options)?),
+        _ => None,
+    };
+
+    let status = match &stats {
+        Some(stats) if *stats == DiffStats::default() => EntryStatus::Unchanged,
+        _ => EntryStatus::Modified,
+    };
+
+    Ok(DirEntryDiff {
+        path: path.to_string(),
+        status,
+        binary: stats.is_none(),
+        stats,
+        error: None,
+    })
+}
+
+/// Entry for a file present on one side only, counted as all added or all removed.
+fn single_file(
+    path: &str,
+    file: &Path,
+    status: EntryStatus,
+    options: &DiffOptions,
+) -> anyhow::Result<DirEntryDiff, AppError> {
+    let stats = match decode_text(&read_bytes(file)?) {
+        Some(decoded) => Some(match status {
+            EntryStatus::Added => diff_stats("", &decoded.text, options)?,
+            _ => diff_stats(&decoded.text, "", options)?,
+        }),
+        None => None,
+    };
+
+    Ok(DirEntryDiff {
+        path: path.to_string(),
+        status,
+        binary: stats.is_none(),
+        stats,
+        error: None,
+    })
+}
+
+fn read_bytes(path: &Path) -> anyhow::Result<Vec<u8>, AppError> {
+    fs::read(path)
+        .context(format!("Could not read: {}", path.display()))
+        .map_err(|e| AppError::File(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempTree(PathBuf);
+
+    impl TempTree {
+        fn new(files: &[(&str, &str)]) -> Self {
+            let root = std::env::temp_dir().join(format!("differ_{}", Utils::get_random_id()));
+
+            for (path, contents) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().expect("file should have a parent"))
+                    .expect("failed to create directory");
+                fs::write(path, contents).expect("failed to write file");
+            }
+
+            Self(root)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().expect("temp path should be valid utf-8")
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    fn status_of(result: &DirDiffResult, path: &str) -> Option<EntryStatus> {
+        result
+            .entries
+            .iter()
+            .find(|e| e.path == path)
+            .map(|e| e.status)
+    }
+
+    #[test]
+    fn test_pairs_files_by_relative_path() {
+        let old = TempTree::new(&[
+            ("same.txt", "a\n"),
+            ("src/lib.rs", "fn a() {}\n"),
+            ("removed.txt", "gone\n"),
+        ]);
+        let new = TempTree::new(&[
+            ("same.txt", "a\n"),
+            ("src/lib.rs", "fn a() {}\nfn b() {}\n"),
+            ("added.txt", "one\ntwo\n"),
+        ]);
+
+        let result = diff_directories(old.path(), new.path(), &DirDiffOptions::default())
+            .expect("failed to diff directories");
+
+        assert_eq!(status_of(&result, "same.txt"), Some(EntryStatus::Unchanged));
+        assert_eq!(
+            status_of(&result, "src/lib.rs"),
+            Some(EntryStatus::Modified)
+        );
+        assert_eq!(
+            status_of(&result, "removed.txt"),
+            Some(EntryStatus::Removed)
+        );
+        assert_eq!(status_of(&result, "added.txt"), Some(EntryStatus::Added));
+
+        let added = result
+            .entries
+            .iter()
+            .find(|e| e.path == "added.txt")
+            .and_then(|e| e.stats.clone())
+            .expect("added file should have stats");
+        assert_eq!(added.insertions, 2);
+
+        assert_eq!(result.summary.modified, 1);
+        assert_eq!(result.summary.unchanged, 1);
+    }
+
+    #[test]
+    fn test_exclude_patterns_and_gitignore() {
+        let old = TempTree::new(&[
+            (".gitignore", "*.log\n"),
+            ("debug.log", "old\n"),
+            ("target/out.bin", "old\n"),
+            ("keep.txt", "old\n"),
+        ]);
+        let new = TempTree::new(&[
+            (".gitignore", "*.log\n"),
+            ("debug.log", "new\n"),
+            ("target/out.bin", "new\n"),
+            ("keep.txt", "new\n"),
+        ]);
+
+        let options = DirDiffOptions {
+            exclude: vec!["target/".to_string()],
+            include_unchanged: false,
+            ..DirDiffOptions::default()
+        };
+
+        let result =
+            diff_directories(old.path(), new.path(), &options).expect("failed to diff directories");
+
+        let paths: Vec<_> = result.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["keep.txt"]);
+    }
+
+    #[test]
+    fn test_line_ending_only_changes_can_be_ignored() {
+        let old = TempTree::new(&[("a.txt", "one\ntwo\n")]);
+        let new = TempTree::new(&[("a.txt", "one\r\ntwo\r\n")]);
+
+        let options = DirDiffOptions {
+            diff: DiffOptions {
+                ignore_line_endings: true,
+                ..DiffOptions::default()
+            },
+            ..DirDiffOptions::default()
+        };
+
+        let result =
+            diff_directories(old.path(), new.path(), &options).expect("failed to diff directories");
+
+        assert_eq!(status_of(&result, "a.txt"), Some(EntryStatus::Unchanged));
+    }
+
+    #[test]
+    fn test_unreadable_files_are_reported_per_entry() {
+        let old = TempTree::new(&[("a.txt", "a\n"), ("b.txt", "b\n"), ("c.txt", "c\n")]);
+        let new = TempTree::new(&[("a.txt", "a\nb\n"), ("b.txt", "b\n"), ("c.txt", "c\n")]);
+
+        let options = DirDiffOptions {
+            include_unchanged: false,
+            ..DirDiffOptions::default()
+        };
+
+        let walk = |tree: &TempTree| {
+            let excludes = build_excludes(&tree.0, &options.exclude).expect("failed to build");
+            collect_files(&tree.0, excludes, &options).expect("failed to walk tree")
+        };
+
+        let old_tree = walk(&old);
+        let mut new_tree = walk(&new);
+
+        // c.txt goes away between the walk and the read, a walk error is
+        // reported for d.txt and one that names no file.
+        fs::remove_file(new.0.join("c.txt")).expect("failed to remove file");
+        new_tree.add_error("d.txt".to_string(), "Permission denied".to_string());
+        new_tree.walk_errors.push("Too many open files".to_string());
+
+        let result = compare_trees(&old_tree, &new_tree, &options);
+
+        assert_eq!(status_of(&result, "a.txt"), Some(EntryStatus::Modified));
+
+        let unreadable = result
+            .entries
+            .iter()
+            .find(|e| e.path == "c.txt")
+            .expect("unreadable file should be listed");
+        assert!(unreadable.error.is_some());
+        assert!(unreadable.stats.is_none());
+
+        let walk_error = result
+            .entries
+            .iter()
+            .find(|e| e.path == "d.txt")
+            .expect("walk error should be listed");
+        assert_eq!(walk_error.status, EntryStatus::Added);
+        assert!(walk_error
+            .error
+            .as_deref()
+            .is_some_and(|e| e.contains("Permission denied")));
+
+        assert_eq!(result.summary.errors, 2);
+        assert_eq!(result.summary.unchanged, 1);
+
+        assert!(result.entries.iter().all(|e| !e.path.is_empty()));
+        assert_eq!(result.errors, vec!["Too many open files".to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_patterns_fail_once() {
+        let old = TempTree::new(&[("a.txt", "a\n"), ("b.txt", "b\n")]);
+        let new = TempTree::new(&[("a.txt", "A\n"), ("b.txt", "B\n")]);
+
+        let invalid_regex = DirDiffOptions {
+            diff: DiffOptions {
+                ignore_matching: vec!["(".to_string()],
+                ..DiffOptions::default()
+            },
+            ..DirDiffOptions::default()
+        };
+        assert!(diff_directories(old.path(), new.path(), &invalid_regex).is_err());
+
+        let invalid_glob = DirDiffOptions {
+            exclude: vec!["{a".to_string()],
+            ..DirDiffOptions::default()
+        };
+        assert!(diff_directories(old.path(), new.path(), &invalid_glob).is_err());
+    }
+
+    #[test]
+    fn test_missing_directory_is_an_error() {
+        let old = TempTree::new(&[("a.txt", "a\n")]);
+
+        let result = diff_directories(
+            old.path(),
+            "/definitely/not/a/real/dir",
+            &DirDiffOptions::default(),
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::constants::EXPLAIN_DIFF_MAX_LINES;
+use crate::diff::{compute_diff, trim_line_ending, ChangeKind, DiffOptions};
+use crate::diff_export::code_fence;
+use crate::diff_patch::parse_patch;
+use crate::prelude::*;
+use serde::Deserialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum ExplainSource {
+    /// A unified diff, possibly touching several files.
+    Patch {
+        patch: String,
+    },
+    Text {
+        old: String,
+        new: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplainStyle {
+    /// Describe what changed and why.
+    #[default]
+    Explain,
+    /// Look for bugs and risky edits.
+    Review,
+}
+
+/// Body of `POST /api/explain-diff`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplainDiffRequest {
+    pub source: ExplainSource,
+
+    #[serde(default)]
+    pub style: ExplainStyle,
+
+    /// Only used for [`ExplainSource::Text`].
+    #[serde(default)]
+    pub options: DiffOptions,
+
+    /// When set, the prompt and the model's answer are saved as a chat with
+    /// this id once the stream finishes.
+    pub chat_id: Option<String>,
+    pub label: Option<String>,
+}
+
+pub struct ExplainPrompt {
+    pub system: String,
+    pub user: String,
+}
+
+impl ExplainDiffRequest {
+    /// Builds the prompt from the hunks of the diff. Fails when there is
+    /// nothing to explain.
+    pub fn prompt(&self) -> anyhow::Result<ExplainPrompt, AppError> {
+        let mut writer = HunkWriter::default();
+
+        match &self.source {
+            ExplainSource::Patch { patch } => {
+                for file in parse_patch(patch)? {
+                    writer.file(file.old_path.as_deref(), file.new_path.as_deref());
+
+                    for hunk in &file.hunks {
+                        writer.hunk(
+                            hunk.old_start,
+                            hunk.old_lines,
+                            hunk.new_start,
+                            hunk.new_lines,
+                        );
+
+                        for line in &hunk.lines {
+                            writer.line(line.kind, &line.content);
+                        }
+                    }
+                }
+            }
+            ExplainSource::Text { old, new } => {
+                let result = compute_diff(old, new, &self.options)?;
+
+                if result.identical {
+                    return Err(AppError::Diff(
+                        "Nothing to explain, both sides are identical".to_string(),
+                    ));
+                }
+
+                writer.file(Some("old"), Some("new"));
+
+                for hunk in &result.hunks {
+                    writer.hunk(
+                        hunk.old_start,
+                        hunk.old_lines,
+                        hunk.new_start,
+                        hunk.new_lines,
+                    );
+
+                    for line in &hunk.lines {
+                        writer.line(line.kind, &line.content);
+                    }
+                }
+            }
+        }
+
+        let diff = writer.finish();
+        let fence = code_fence(&diff);
+
+        let instructions = match self.style {
+            ExplainStyle::Explain => {
+                "Explain what the following changes do and, where it can be inferred, why they were made. \
+                 Group related hunks together and refer to files and line numbers from the hunk headers."
+            }
+            ExplainStyle::Review => {
+                "Review the following changes. Point out bugs, risky edits and missed cases, referring to \
+                 files and line numbers from the hunk headers. Say so plainly when the changes look correct."
+            }
+        };
+
+        Ok(ExplainPrompt {
+            system: format!(
+                "You are a code reviewer inside Differ, a desktop diff tool. {} Answer in Markdown.",
+                instructions
+            ),
+            user: format!("{}diff\n{}{}\n", fence, diff, fence),
+        })
+    }
+
+    pub fn chat_label(&self) -> String {
+        match (&self.label, self.style) {
+            (Some(label), _) if !label.trim().is_empty() => label.trim().to_string(),
+            (_, ExplainStyle::Explain) => "Diff explanation".to_string(),
+            (_, ExplainStyle::Review) => "Diff review".to_string(),
+        }
+    }
+}
+
+/// Writes hunks in unified diff form, dropping lines past
+/// [`EXPLAIN_DIFF_MAX_LINES`] so large diffs still fit the model's context.
+#[derive(Default)]
+struct HunkWriter {
+    out: String,
+    lines: usize,
+    omitted: usize,
+}
+
+impl HunkWriter {
+    fn file(&mut self, old_path: Option<&str>, new_path: Option<&str>) {
+        if self.lines >= EXPLAIN_DIFF_MAX_LINES {
+            return;
+        }
+
+        writeln!(self.out, "--- {}", old_path.unwrap_or("/dev/null")).ok();
+        writeln!(self.out, "+++ {}", new_path.unwrap_or("/dev/null")).ok();
+    }
+
+    fn hunk(&mut self, old_start: usize, old_lines: usize, new_start: usize, new_lines: usize) {
+        if self.lines >= EXPLAIN_DIFF_MAX_LINES {
+            return;
+        }
+
+        writeln!(
+            self.out,
+            "@@ -{},{} +{},{} @@",
+            old_start, old_lines, new_start, new_lines
+        )
+        .ok();
+    }
+
+    fn line(&mut self, kind: ChangeKind, content: &str) {
+        if self.lines >= EXPLAIN_DIFF_MAX_LINES {
+            self.omitted += 1;
+            return;
+        }
+
+        let marker = match kind {
+            ChangeKind::Equal => ' ',
+            ChangeKind::Insert => '+',
+            ChangeKind::Delete => '-',
+        };
+
+        writeln!(self.out, "{}{}", marker, trim_line_ending(content)).ok();
+        self.lines += 1;
+    }
+
+    fn finish(mut self) -> String {
+        if self.omitted > 0 {
+            writeln!(self.out, "... {} more lines omitted", self.omitted).ok();
+        }
+
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(source: ExplainSource, style: ExplainStyle) -> ExplainDiffRequest {
+        ExplainDiffRequest {
+            source,
+            style,
+            options: DiffOptions::default(),
+            chat_id: None,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_prompt_from_texts() {
+        let source = ExplainSource::Text {
+            old: "a\nb\nc\n".to_string(),
+            new: "a\nB\nc\n".to_string(),
+        };
+
+        let prompt = request(source, ExplainStyle::Review)
+            .prompt()
+            .expect("failed to build prompt");
+
+        assert!(prompt.system.contains("Review the following changes"));
+        assert!(prompt
+            .user
+            .starts_with("```diff\n--- old\n+++ new\n@@ -1,3 +1,3 @@\n"));
+        assert!(prompt.user.contains("\n-b\n+B\n"));
+        assert!(prompt.user.ends_with("```\n"));
+
+        let identical = ExplainSource::Text {
+            old: "a\n".to_string(),
+            new: "a\n".to_string(),
+        };
+        assert!(request(identical, ExplainStyle::Explain).prompt().is_err());
+    }
+
+    #[test]
+    fn test_prompt_from_patch() {
+        let patch = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n fn main() {\n-    run();\n+    run_all();\n--- /dev/null\n+++ b/notes.md\n@@ -0,0 +1 @@\n+```\n";
+
+        let prompt = request(
+            ExplainSource::Patch {
+                patch: patch.to_string(),
+            },
+            ExplainStyle::Explain,
+        )
+        .prompt()
+        .expect("failed to build prompt");
+
+        assert!(prompt
+            .system
+            .contains("Explain what the following changes do"));
+        assert!(prompt
+            .user
+            .starts_with("````diff\n--- src/lib.rs\n+++ src/lib.rs\n"));
+        assert!(prompt.user.contains("-    run();\n+    run_all();\n"));
+        assert!(prompt
+            .user
+            .contains("--- /dev/null\n+++ notes.md\n@@ -0,0 +1,1 @@\n+```\n"));
+    }
+
+    #[test]
+    fn test_large_diffs_are_truncated() {
+        let new: String = (0..EXPLAIN_DIFF_MAX_LINES + 10)
+            .map(|i| format!("line {}\n", i))
+            .collect();
+
+        let source = ExplainSource::Text {
+            old: String::new(),
+            new,
+        };
+
+        let prompt = request(source, ExplainStyle::Explain)
+            .prompt()
+            .expect("failed to build prompt");
+
+        assert!(prompt.user.contains("\n... 10 more lines omitted\n"));
+    }
+}
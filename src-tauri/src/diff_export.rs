@@ -0,0 +1,513 @@
+use crate::diff::{ChangeKind, DiffLine, DiffResult, Hunk};
+use crate::diff_patch::hunks_to_unified;
+use crate::diff_syntax::SyntaxLanguage;
+use serde::Deserialize;
+use std::fmt::Write;
+use tree_sitter_highlight::{HighlightConfiguration, Highlighter, HtmlRenderer};
+
+/// Capture names given a colour in the report stylesheet, each becomes a
+/// `hl-<name>` class with dots replaced by dashes.
+const HIGHLIGHT_NAMES: &[&str] = &[
+    "attribute",
+    "comment",
+    "constant",
+    "constant.builtin",
+    "constructor",
+    "escape",
+    "function",
+    "function.builtin",
+    "function.method",
+    "keyword",
+    "label",
+    "number",
+    "operator",
+    "property",
+    "punctuation",
+    "string",
+    "string.special",
+    "tag",
+    "type",
+    "type.builtin",
+    "variable.builtin",
+    "variable.parameter",
+];
+
+const REPORT_STYLE: &str = r#"
+body { margin: 0; padding: 24px; background: #fff; color: #1f2328; font: 14px/1.5 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; }
+header { margin-bottom: 16px; }
+h1 { margin: 0 0 4px; font-size: 20px; }
+.files { margin: 0; color: #59636e; font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 12px; }
+.stats .ins { color: #1a7f37; font-weight: 600; }
+.stats .del { color: #d1242f; font-weight: 600; }
+table.diff { width: 100%; border: 1px solid #d1d9e0; border-radius: 6px; border-collapse: separate; border-spacing: 0; table-layout: fixed; font: 12px/20px ui-monospace, Menlo, Consolas, monospace; }
+table.diff td { padding: 0 8px; vertical-align: top; white-space: pre-wrap; word-break: break-all; }
+table.diff td.num { width: 48px; color: #59636e; text-align: right; user-select: none; }
+table.diff tr.hunk td { background: #ddf4ff; color: #59636e; }
+td.insert, tr.insert td { background: #dafbe1; }
+td.delete, tr.delete td { background: #ffebe9; }
+td.empty { background: #f6f8fa; }
+td.moved, tr.moved td { box-shadow: inset 3px 0 #8250df; }
+.marker { display: inline-block; width: 1ch; margin-right: 8px; color: #59636e; user-select: none; }
+ins { background: #aceebb; text-decoration: none; }
+del { background: #ffcecb; text-decoration: none; }
+.hl-attribute, .hl-tag { color: #116329; }
+.hl-comment { color: #59636e; font-style: italic; }
+.hl-constant, .hl-constant-builtin, .hl-number, .hl-escape { color: #0550ae; }
+.hl-constructor, .hl-type, .hl-type-builtin { color: #953800; }
+.hl-function, .hl-function-builtin, .hl-function-method { color: #6639ba; }
+.hl-keyword, .hl-operator { color: #cf222e; }
+.hl-label, .hl-property, .hl-variable-builtin, .hl-variable-parameter { color: #0550ae; }
+.hl-string, .hl-string-special { color: #0a3069; }
+"#;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HtmlLayout {
+    #[default]
+    Inline,
+    SideBySide,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum ReportFormat {
+    /// Single file page with its own stylesheet, syntax highlighted when the
+    /// language is known.
+    Html {
+        #[serde(default)]
+        layout: HtmlLayout,
+    },
+    /// Unified diff inside a ```diff fence, for tickets and pull requests.
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html { .. } => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn filter_name(&self) -> &'static str {
+        match self {
+            ReportFormat::Html { .. } => "HTML",
+            ReportFormat::Markdown => "Markdown",
+        }
+    }
+}
+
+/// Everything a report shows about one diff.
+pub struct DiffReport<'a> {
+    pub title: &'a str,
+    pub old_label: &'a str,
+    pub new_label: &'a str,
+    pub old: &'a str,
+    pub new: &'a str,
+    pub result: &'a DiffResult,
+    pub language: Option<SyntaxLanguage>,
+}
+
+impl DiffReport<'_> {
+    pub fn render_html(&self, layout: HtmlLayout) -> String {
+        let old_lines = highlight_lines(self.old, self.language);
+        let new_lines = highlight_lines(self.new, self.language);
+
+        let code = |line: &DiffLine| {
+            let (highlighted, number) = match line.kind {
+                ChangeKind::Insert => (&new_lines, line.new_line),
+                _ => (&old_lines, line.old_line),
+            };
+
+            line_html(line, highlighted.as_deref(), number)
+        };
+
+        let mut html = String::new();
+
+        writeln!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>",
+            escape(self.title),
+            REPORT_STYLE
+        )
+        .ok();
+
+        writeln!(
+            html,
+            "<header>\n<h1>{}</h1>\n<p class=\"files\">--- {}<br>+++ {}</p>\n<p class=\"stats\"><span class=\"ins\">+{}</span> <span class=\"del\">-{}</span></p>\n</header>",
+            escape(self.title),
+            escape(self.old_label),
+            escape(self.new_label),
+            self.result.stats.insertions,
+            self.result.stats.deletions
+        )
+        .ok();
+
+        let columns = match layout {
+            HtmlLayout::Inline => 3,
+            HtmlLayout::SideBySide => 4,
+        };
+
+        writeln!(
+            html,
+            "<table class=\"diff {}\">",
+            match layout {
+                HtmlLayout::Inline => "inline",
+                HtmlLayout::SideBySide => "side-by-side",
+            }
+        )
+        .ok();
+
+        for hunk in &self.result.hunks {
+            writeln!(
+                html,
+                "<tbody>\n<tr class=\"hunk\"><td colspan=\"{}\">{}</td></tr>",
+                columns,
+                hunk_header(hunk)
+            )
+            .ok();
+
+            match layout {
+                HtmlLayout::Inline => {
+                    for line in &hunk.lines {
+                        writeln!(
+                            html,
+                            "<tr class=\"{}\"><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"code\"><span class=\"marker\">{}</span>{}</td></tr>",
+                            line_class(line),
+                            number(line.old_line),
+                            number(line.new_line),
+                            marker(line.kind),
+                            code(line)
+                        )
+                        .ok();
+                    }
+                }
+                HtmlLayout::SideBySide => {
+                    for (left, right) in side_by_side_rows(&hunk.lines) {
+                        let (left_num, left_class, left_code) = match left {
+                            Some(line) => (number(line.old_line), line_class(line), code(line)),
+                            None => (String::new(), "empty", String::new()),
+                        };
+
+                        let (right_num, right_class, right_code) = match right {
+                            Some(line) if line.kind == ChangeKind::Equal => (
+                                number(line.new_line),
+                                line_class(line),
+                                line_html(line, new_lines.as_deref(), line.new_line),
+                            ),
+                            Some(line) => (number(line.new_line), line_class(line), code(line)),
+                            None => (String::new(), "empty", String::new()),
+                        };
+
+                        writeln!(
+                            html,
+                            "<tr><td class=\"num\">{}</td><td class=\"code {}\">{}</td><td class=\"num\">{}</td><td class=\"code {}\">{}</td></tr>",
+                            left_num, left_class, left_code, right_num, right_class, right_code
+                        )
+                        .ok();
+                    }
+                }
+            }
+
+            writeln!(html, "</tbody>").ok();
+        }
+
+        writeln!(html, "</table>\n</body>\n</html>").ok();
+
+        html
+    }
+
+    /// Stats and the unified diff of the hunks, so the patch agrees with the
+    /// stats and the HTML report.
+    pub fn render_markdown(&self) -> String {
+        let patch = &hunks_to_unified(
+            self.old,
+            self.new,
+            &self.result.hunks,
+            &format!("a/{}", self.title),
+            &format!("b/{}", self.title),
+        );
+        let fence = code_fence(patch);
+
+        format!(
+            "## {}\n\n`{}` → `{}`: **+{}** / **-{}**\n\n{}diff\n{}{}{}\n",
+            self.title,
+            self.old_label,
+            self.new_label,
+            self.result.stats.insertions,
+            self.result.stats.deletions,
+            fence,
+            patch,
+            if patch.ends_with('\n') { "" } else { "\n" },
+            fence
+        )
+    }
+}
+
+/// Markdown fence for `text`, longer than any backtick run inside it.
+pub(crate) fn code_fence(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+/// Pairs the lines of a hunk into left and right columns. Deletions are lined
+/// up with the insertions that follow them, unchanged lines sit on both sides.
+fn side_by_side_rows(lines: &[DiffLine]) -> Vec<(Option<&DiffLine>, Option<&DiffLine>)> {
+    let mut rows = vec![];
+    let mut deleted: Vec<&DiffLine> = vec![];
+    let mut inserted: Vec<&DiffLine> = vec![];
+
+    for line in lines {
+        match line.kind {
+            ChangeKind::Delete => {
+                if !inserted.is_empty() {
+                    pair_changes(&mut rows, &mut deleted, &mut inserted);
+                }
+
+                deleted.push(line);
+            }
+            ChangeKind::Insert => inserted.push(line),
+            ChangeKind::Equal => {
+                pair_changes(&mut rows, &mut deleted, &mut inserted);
+                rows.push((Some(line), Some(line)));
+            }
+        }
+    }
+
+    pair_changes(&mut rows, &mut deleted, &mut inserted);
+
+    rows
+}
+
+fn pair_changes<'a>(
+    rows: &mut Vec<(Option<&'a DiffLine>, Option<&'a DiffLine>)>,
+    deleted: &mut Vec<&'a DiffLine>,
+    inserted: &mut Vec<&'a DiffLine>,
+) {
+    for i in 0..deleted.len().max(inserted.len()) {
+        rows.push((deleted.get(i).copied(), inserted.get(i).copied()));
+    }
+
+    deleted.clear();
+    inserted.clear();
+}
+
+/// Highlights a whole text and splits the markup per line, spans that cross a
+/// line break are closed and reopened. `None` when the language is unknown or
+/// the grammar fails.
+fn highlight_lines(text: &str, language: Option<SyntaxLanguage>) -> Option<Vec<String>> {
+    let language = language?;
+
+    let mut config = HighlightConfiguration::new(
+        language.grammar(),
+        format!("{:?}", language),
+        &language.highlights_query(),
+        "",
+        "",
+    )
+    .ok()?;
+    config.configure(HIGHLIGHT_NAMES);
+
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(&config, text.as_bytes(), None, |_| None)
+        .ok()?;
+
+    let mut renderer = HtmlRenderer::new();
+    renderer
+        .render(events, text.as_bytes(), &|highlight, html| {
+            html.extend_from_slice(b"class=\"hl-");
+            html.extend_from_slice(HIGHLIGHT_NAMES[highlight.0].replace('.', "-").as_bytes());
+            html.extend_from_slice(b"\"");
+        })
+        .ok()?;
+
+    Some(
+        renderer
+            .lines()
+            .map(|line| line.trim_end_matches('\n').to_string())
+            .collect(),
+    )
+}
+
+/// Word level changes win over syntax colours, they are what the reader is
+/// looking for on a replaced line.
+fn line_html(line: &DiffLine, highlighted: Option<&[String]>, number: Option<usize>) -> String {
+    if !line.segments.is_empty() {
+        return line
+            .segments
+            .iter()
+            .map(|segment| match segment.kind {
+                ChangeKind::Equal => escape(&segment.value),
+                ChangeKind::Insert => format!("<ins>{}</ins>", escape(&segment.value)),
+                ChangeKind::Delete => format!("<del>{}</del>", escape(&segment.value)),
+            })
+            .collect();
+    }
+
+    number
+        .and_then(|n| highlighted?.get(n - 1))
+        .cloned()
+        .unwrap_or_else(|| escape(&line.content))
+}
+
+fn line_class(line: &DiffLine) -> &'static str {
+    match (line.kind, line.move_id.is_some()) {
+        (ChangeKind::Equal, _) => "equal",
+        (ChangeKind::Insert, false) => "insert",
+        (ChangeKind::Insert, true) => "insert moved",
+        (ChangeKind::Delete, false) => "delete",
+        (ChangeKind::Delete, true) => "delete moved",
+    }
+}
+
+fn marker(kind: ChangeKind) -> char {
+    match kind {
+        ChangeKind::Equal => ' ',
+        ChangeKind::Insert => '+',
+        ChangeKind::Delete => '-',
+    }
+}
+
+fn number(line: Option<usize>) -> String {
+    line.map(|n| n.to_string()).unwrap_or_default()
+}
+
+fn hunk_header(hunk: &Hunk) -> String {
+    format!(
+        "@@ -{},{} +{},{} @@",
+        hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{compute_diff, DiffOptions};
+
+    fn report<'a>(old: &'a str, new: &'a str, result: &'a DiffResult) -> DiffReport<'a> {
+        DiffReport {
+            title: "main.rs",
+            old_label: "a/main.rs",
+            new_label: "b/main.rs",
+            old,
+            new,
+            result,
+            language: Some(SyntaxLanguage::Rust),
+        }
+    }
+
+    #[test]
+    fn test_html_inline_is_highlighted_and_escaped() {
+        let old = "fn main() {\n    let a = 1;\n}\n";
+        let new = "fn main() {\n    let a = 1;\n    println!(\"<done>\");\n}\n";
+        let result = compute_diff(old, new, &DiffOptions::default()).expect("failed to diff");
+
+        let html = report(old, new, &result).render_html(HtmlLayout::Inline);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<span class=\"hl-keyword\">fn</span>"));
+        assert!(html.contains("&lt;done&gt;"));
+        assert!(!html.contains("<done>"));
+        assert!(html.contains("@@ -1,3 +1,4 @@"));
+        assert_eq!(html.matches("<tr class=\"insert\">").count(), 1);
+    }
+
+    #[test]
+    fn test_side_by_side_pairs_replaced_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+        let result = compute_diff(old, new, &DiffOptions::default()).expect("failed to diff");
+
+        let rows = side_by_side_rows(&result.hunks[0].lines);
+        let kinds: Vec<_> = rows
+            .iter()
+            .map(|(l, r)| (l.map(|l| l.kind), r.map(|r| r.kind)))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(ChangeKind::Equal), Some(ChangeKind::Equal)),
+                (Some(ChangeKind::Delete), Some(ChangeKind::Insert)),
+                (Some(ChangeKind::Equal), Some(ChangeKind::Equal)),
+                (Some(ChangeKind::Equal), Some(ChangeKind::Equal)),
+                (None, Some(ChangeKind::Insert)),
+            ]
+        );
+
+        let mut plain = report(old, new, &result);
+        plain.language = None;
+        let html = plain.render_html(HtmlLayout::SideBySide);
+        assert!(html.contains("<td class=\"code empty\"></td>"));
+        assert!(html.contains("<td class=\"code delete\">b</td>"));
+    }
+
+    #[test]
+    fn test_markdown_fence_outlasts_backticks() {
+        let old = "text\n";
+        let new = "```rust\ntext\n";
+        let result = compute_diff(old, new, &DiffOptions::default()).expect("failed to diff");
+
+        let markdown = report(old, new, &result).render_markdown();
+
+        assert!(markdown.contains("**+1** / **-0**"));
+        assert!(markdown.contains("\n````diff\n"));
+        assert!(markdown.ends_with("\n````\n"));
+        assert!(markdown.contains("+```rust\n"));
+    }
+
+    #[test]
+    fn test_markdown_leaves_out_ignored_changes() {
+        let old = "fn main() {   \n    run();\n}\n";
+        let new = "fn main() {\n    run();\n    stop();\n}\n";
+        let options = DiffOptions {
+            ignore_trailing_whitespace: true,
+            context_lines: 0,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(old, new, &options).expect("failed to diff");
+
+        let markdown = report(old, new, &result).render_markdown();
+
+        assert!(markdown.contains("**+1** / **-0**"));
+        assert!(markdown.contains("+    stop();\n"));
+        assert!(!markdown.contains("-fn main()"));
+    }
+
+    #[test]
+    fn test_every_language_highlights() {
+        for language in [
+            SyntaxLanguage::Rust,
+            SyntaxLanguage::TypeScript,
+            SyntaxLanguage::Tsx,
+            SyntaxLanguage::Python,
+            SyntaxLanguage::Json,
+        ] {
+            let lines = highlight_lines("\"text\"\n", Some(language));
+            assert!(lines.is_some(), "{:?} queries should compile", language);
+        }
+    }
+}
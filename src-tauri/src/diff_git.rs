@@ -0,0 +1,536 @@
+use crate::constants::GIT_SHORT_ID_LEN;
+use crate::diff::{compute_diff, DiffOptions, DiffResult};
+use crate::diff_dir::{DirDiffSummary, EntryStatus};
+use crate::file_text::decode_text;
+use crate::prelude::*;
+use crate::utils::Utils;
+use gix::bstr::ByteSlice;
+use gix::status::tree_index::TrackRenames;
+use gix::status::UntrackedFiles;
+use gix::{ObjectId, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author: String,
+
+    /// Seconds since the unix epoch.
+    pub time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitRefs {
+    /// Checked out branch, `None` when HEAD is detached or unborn.
+    pub head: Option<String>,
+    pub branches: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileDiff {
+    /// Path relative to the repository root, always `/` separated.
+    pub path: String,
+    pub status: EntryStatus,
+    pub binary: bool,
+
+    /// Line diff, `None` for binary files.
+    pub diff: Option<DiffResult>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitDiff {
+    pub summary: DirDiffSummary,
+    pub files: Vec<GitFileDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileRevision {
+    pub commit: GitCommit,
+    pub status: EntryStatus,
+    pub binary: bool,
+
+    /// Changes against the first parent, `None` for binary files.
+    pub diff: Option<DiffResult>,
+}
+
+/// Opens the repository containing `path`, which may be any directory inside
+/// the working tree.
+pub fn open_repository(path: &str) -> anyhow::Result<Repository, AppError> {
+    gix::discover(Utils::normalise_path(path))
+        .map_err(|e| AppError::Git(format!("Could not open a repository at {}: {}", path, e)))
+}
+
+pub fn list_refs(repo: &Repository) -> anyhow::Result<GitRefs, AppError> {
+    let head = repo
+        .head_name()
+        .map_err(to_git_err)?
+        .map(|name| name.shorten().to_string());
+
+    let references = repo.references().map_err(to_git_err)?;
+    let mut branches = vec![];
+
+    for reference in references.local_branches().map_err(to_git_err)? {
+        let reference = reference.map_err(to_git_err)?;
+        branches.push(reference.name().shorten().to_string());
+    }
+
+    Ok(GitRefs { head, branches })
+}
+
+/// Uncommitted changes, staged or not, between HEAD and the working tree.
+/// Untracked files are reported as added.
+pub fn diff_worktree(
+    repo: &Repository,
+    options: &DiffOptions,
+) -> anyhow::Result<GitDiff, AppError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| AppError::Git("Repository has no working tree".to_string()))?
+        .to_path_buf();
+
+    let head_tree = repo.head_tree_id_or_empty().map_err(to_git_err)?;
+    let head = tree_files(repo, head_tree.detach())?;
+
+    let status = repo
+        .status(gix::progress::Discard)
+        .map_err(to_git_err)?
+        .untracked_files(UntrackedFiles::Files)
+        .index_worktree_rewrites(None)
+        .tree_index_track_renames(TrackRenames::Disabled)
+        .into_iter(None)
+        .map_err(to_git_err)?;
+
+    let mut paths = BTreeSet::new();
+
+    for item in status {
+        let item = item.map_err(to_git_err)?;
+        paths.insert(item.location().to_str_lossy().into_owned());
+    }
+
+    let mut diff = GitDiff::default();
+
+    for path in paths {
+        let old = head.get(&path).map(|id| read_blob(repo, *id)).transpose()?;
+
+        let file = workdir.join(&path);
+        let new = if file.is_file() {
+            Some(std::fs::read(&file).map_err(|e| AppError::File(e.to_string()))?)
+        } else {
+            None
+        };
+
+        if let Some(file_diff) = diff_file(path, old.as_deref(), new.as_deref(), options)? {
+            diff.push(file_diff);
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Changes from `old_rev` to `new_rev`, anything git can resolve to a commit
+/// such as a branch, tag, `HEAD~2` or a commit id.
+pub fn diff_revisions(
+    repo: &Repository,
+    old_rev: &str,
+    new_rev: &str,
+    options: &DiffOptions,
+) -> anyhow::Result<GitDiff, AppError> {
+    let old = tree_files(repo, resolve_tree(repo, old_rev)?)?;
+    let new = tree_files(repo, resolve_tree(repo, new_rev)?)?;
+
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut diff = GitDiff::default();
+
+    for path in paths {
+        let (old_id, new_id) = (old.get(path), new.get(path));
+
+        if old_id == new_id {
+            continue;
+        }
+
+        let old_blob = old_id.map(|id| read_blob(repo, *id)).transpose()?;
+        let new_blob = new_id.map(|id| read_blob(repo, *id)).transpose()?;
+
+        if let Some(file_diff) = diff_file(
+            path.clone(),
+            old_blob.as_deref(),
+            new_blob.as_deref(),
+            options,
+        )? {
+            diff.push(file_diff);
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Commits reachable from HEAD that changed `file_path`, newest first, each
+/// with its changes to the file. Renames are not followed.
+pub fn file_history(
+    repo: &Repository,
+    file_path: &str,
+    limit: usize,
+    options: &DiffOptions,
+) -> anyhow::Result<Vec<GitFileRevision>, AppError> {
+    let path = relative_path(repo, file_path);
+    let head = repo.head_id().map_err(to_git_err)?;
+
+    let walk = repo.rev_walk([head]).all().map_err(to_git_err)?;
+    let mut revisions = vec![];
+
+    for info in walk {
+        if revisions.len() >= limit {
+            break;
+        }
+
+        let commit = info.map_err(to_git_err)?.object().map_err(to_git_err)?;
+        let new_id = blob_at(&commit, &path)?;
+
+        let old_id = match commit.parent_ids().next() {
+            Some(parent) => {
+                let parent = parent
+                    .object()
+                    .map_err(to_git_err)?
+                    .peel_to_commit()
+                    .map_err(to_git_err)?;
+
+                blob_at(&parent, &path)?
+            }
+            None => None,
+        };
+
+        if old_id == new_id {
+            continue;
+        }
+
+        let old_blob = old_id.map(|id| read_blob(repo, id)).transpose()?;
+        let new_blob = new_id.map(|id| read_blob(repo, id)).transpose()?;
+
+        if let Some(file_diff) = diff_file(
+            path.clone(),
+            old_blob.as_deref(),
+            new_blob.as_deref(),
+            options,
+        )? {
+            revisions.push(GitFileRevision {
+                commit: commit_info(&commit)?,
+                status: file_diff.status,
+                binary: file_diff.binary,
+                diff: file_diff.diff,
+            });
+        }
+    }
+
+    Ok(revisions)
+}
+
+impl GitDiff {
+    fn push(&mut self, file: GitFileDiff) {
+        match file.status {
+            EntryStatus::Added => self.summary.added += 1,
+            EntryStatus::Removed => self.summary.removed += 1,
+            EntryStatus::Modified => self.summary.modified += 1,
+            EntryStatus::Unchanged => self.summary.unchanged += 1,
+        }
+
+        self.files.push(file);
+    }
+}
+
+/// Diffs one file given the contents of each side, `None` where the file does
+/// not exist. Returns `None` when nothing changed once options are applied.
+fn diff_file(
+    path: String,
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    options: &DiffOptions,
+) -> anyhow::Result<Option<GitFileDiff>, AppError> {
+    let status = match (old, new) {
+        (Some(old), Some(new)) if old == new => return Ok(None),
+        (Some(_), Some(_)) => EntryStatus::Modified,
+        (Some(_), None) => EntryStatus::Removed,
+        (None, Some(_)) => EntryStatus::Added,
+        (None, None) => return Ok(None),
+    };
+
+    let old_text = old.map(decode_text);
+    let new_text = new.map(decode_text);
+
+    // A side that exists but does not decode is binary.
+    if matches!(old_text, Some(None)) || matches!(new_text, Some(None)) {
+        return Ok(Some(GitFileDiff {
+            path,
+            status,
+            binary: true,
+            diff: None,
+        }));
+    }
+
+    let old_text = old_text.flatten().map(|d| d.text).unwrap_or_default();
+    let new_text = new_text.flatten().map(|d| d.text).unwrap_or_default();
+
+    let result = compute_diff(&old_text, &new_text, options)?;
+
+    if status == EntryStatus::Modified && result.hunks.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(GitFileDiff {
+        path,
+        status,
+        binary: false,
+        diff: Some(result),
+    }))
+}
+
+fn resolve_tree(repo: &Repository, rev: &str) -> anyhow::Result<ObjectId, AppError> {
+    let commit = repo
+        .rev_parse_single(rev)
+        .map_err(|e| AppError::Git(format!("Unknown revision '{}': {}", rev, e)))?
+        .object()
+        .map_err(to_git_err)?
+        .peel_to_commit()
+        .map_err(to_git_err)?;
+
+    Ok(commit.tree_id().map_err(to_git_err)?.detach())
+}
+
+/// Path to blob id of every file in the tree, symlinks included.
+fn tree_files(
+    repo: &Repository,
+    tree_id: ObjectId,
+) -> anyhow::Result<BTreeMap<String, ObjectId>, AppError> {
+    let tree = repo.find_tree(tree_id).map_err(to_git_err)?;
+
+    let files = tree
+        .traverse()
+        .breadthfirst
+        .files()
+        .map_err(to_git_err)?
+        .into_iter()
+        .filter(|entry| entry.mode.is_blob_or_symlink())
+        .map(|entry| (entry.filepath.to_str_lossy().into_owned(), entry.oid))
+        .collect();
+
+    Ok(files)
+}
+
+fn blob_at(commit: &gix::Commit, path: &str) -> anyhow::Result<Option<ObjectId>, AppError> {
+    let tree = commit.tree().map_err(to_git_err)?;
+
+    let entry = tree.lookup_entry_by_path(path).map_err(to_git_err)?;
+
+    Ok(entry
+        .filter(|entry| entry.mode().is_blob_or_symlink())
+        .map(|entry| entry.object_id()))
+}
+
+fn read_blob(repo: &Repository, id: ObjectId) -> anyhow::Result<Vec<u8>, AppError> {
+    let blob = repo.find_blob(id).map_err(to_git_err)?;
+    Ok(blob.detach().data)
+}
+
+fn commit_info(commit: &gix::Commit) -> anyhow::Result<GitCommit, AppError> {
+    let message = commit.message().map_err(to_git_err)?;
+    let author = commit.author().map_err(to_git_err)?;
+    let time = commit.time().map_err(to_git_err)?;
+
+    Ok(GitCommit {
+        id: commit.id.to_string(),
+        short_id: commit.id.to_hex_with_len(GIT_SHORT_ID_LEN).to_string(),
+        summary: message.summary().to_string(),
+        author: author.name.to_string(),
+        time: time.seconds,
+    })
+}
+
+/// Accepts paths relative to the repository root or absolute paths inside the
+/// working tree, git always stores them `/` separated.
+fn relative_path(repo: &Repository, file_path: &str) -> String {
+    let file_path = Utils::normalise_path(file_path);
+    let path = Path::new(&file_path);
+
+    let relative = repo
+        .workdir()
+        .and_then(|workdir| {
+            let workdir = std::fs::canonicalize(workdir).ok()?;
+            let path = std::fs::canonicalize(path).ok()?;
+
+            path.strip_prefix(workdir).ok().map(Path::to_path_buf)
+        })
+        .unwrap_or_else(|| path.to_path_buf());
+
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn to_git_err(e: impl std::fmt::Display) -> AppError {
+    AppError::Git(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    struct TempRepo(PathBuf);
+
+    impl TempRepo {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("differ_git_{}", Utils::get_random_id()));
+            fs::create_dir_all(&path).expect("failed to create repo dir");
+
+            let repo = Self(path);
+            repo.git(&["init", "--quiet", "--initial-branch=main"]);
+            repo.git(&["config", "user.name", "Differ"]);
+            repo.git(&["config", "user.email", "differ@example.com"]);
+            repo.git(&["config", "commit.gpgsign", "false"]);
+
+            repo
+        }
+
+        fn git(&self, args: &[&str]) {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&self.0)
+                .status()
+                .expect("failed to run git");
+
+            assert!(status.success(), "git {:?} failed", args);
+        }
+
+        fn write(&self, path: &str, contents: &[u8]) {
+            fs::write(self.0.join(path), contents).expect("failed to write file");
+        }
+
+        fn commit(&self, message: &str) {
+            self.git(&["add", "--all"]);
+            self.git(&["commit", "--quiet", "-m", message]);
+        }
+
+        fn open(&self) -> Repository {
+            open_repository(self.0.to_str().expect("temp path should be valid utf-8"))
+                .expect("failed to open repo")
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    fn statuses(diff: &GitDiff) -> Vec<(&str, EntryStatus)> {
+        diff.files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_worktree_against_head() {
+        let repo = TempRepo::new();
+        repo.write("kept.txt", b"one\ntwo\n");
+        repo.write("removed.txt", b"bye\n");
+        repo.commit("initial");
+
+        repo.write("kept.txt", b"one\nthree\n");
+        fs::remove_file(repo.0.join("removed.txt")).expect("failed to remove file");
+        repo.write("new.txt", b"hello\n");
+        repo.write("image.bin", b"\x00\x01\x02");
+
+        let diff = diff_worktree(&repo.open(), &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                ("image.bin", EntryStatus::Added),
+                ("kept.txt", EntryStatus::Modified),
+                ("new.txt", EntryStatus::Added),
+                ("removed.txt", EntryStatus::Removed),
+            ]
+        );
+        assert!(diff.files[0].binary);
+
+        let kept = diff.files[1].diff.as_ref().expect("text file should diff");
+        assert_eq!((kept.stats.insertions, kept.stats.deletions), (1, 1));
+    }
+
+    #[test]
+    fn test_branches_and_revisions() {
+        let repo = TempRepo::new();
+        repo.write("a.txt", b"base\n");
+        repo.commit("initial");
+
+        repo.git(&["checkout", "--quiet", "-b", "feature"]);
+        repo.write("a.txt", b"base\nfeature\n");
+        repo.write("b.txt", b"new\n");
+        repo.commit("feature work");
+
+        let git = repo.open();
+
+        let refs = list_refs(&git).expect("failed to list refs");
+        assert_eq!(refs.head.as_deref(), Some("feature"));
+        assert_eq!(refs.branches, vec!["feature", "main"]);
+
+        let diff = diff_revisions(&git, "main", "feature", &DiffOptions::default())
+            .expect("failed to diff");
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                ("a.txt", EntryStatus::Modified),
+                ("b.txt", EntryStatus::Added)
+            ]
+        );
+        assert_eq!(diff.summary.added, 1);
+
+        let same = diff_revisions(&git, "HEAD", "feature", &DiffOptions::default())
+            .expect("failed to diff");
+        assert!(same.files.is_empty());
+
+        assert!(diff_revisions(&git, "main", "missing", &DiffOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_file_history() {
+        let repo = TempRepo::new();
+        repo.write("notes.txt", b"first\n");
+        repo.commit("add notes");
+
+        repo.write("other.txt", b"unrelated\n");
+        repo.commit("unrelated change");
+
+        repo.write("notes.txt", b"first\nsecond\n");
+        repo.commit("extend notes");
+
+        let git = repo.open();
+        let history =
+            file_history(&git, "notes.txt", 10, &DiffOptions::default()).expect("failed to walk");
+
+        let summaries: Vec<_> = history
+            .iter()
+            .map(|r| (r.commit.summary.as_str(), r.status))
+            .collect();
+        assert_eq!(
+            summaries,
+            vec![
+                ("extend notes", EntryStatus::Modified),
+                ("add notes", EntryStatus::Added),
+            ]
+        );
+        assert_eq!(history[0].commit.short_id.len(), GIT_SHORT_ID_LEN);
+
+        let limited =
+            file_history(&git, "notes.txt", 1, &DiffOptions::default()).expect("failed to walk");
+        assert_eq!(limited.len(), 1);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::constants::IMAGE_DIFF_TOLERANCE;
+use crate::prelude::*;
+use crate::utils::Utils;
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const HIGHLIGHT: [f32; 3] = [255.0, 0.0, 64.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageDiffOptions {
+    /// Largest per channel difference still treated as equal, absorbs JPEG
+    /// and WebP compression noise.
+    pub tolerance: u8,
+}
+
+impl Default for ImageDiffOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: IMAGE_DIFF_TOLERANCE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageDiffResult {
+    pub old: ImageInfo,
+    pub new: ImageInfo,
+    pub same_dimensions: bool,
+
+    /// Pixels compared, the area covering both images.
+    pub total_pixels: u64,
+    pub changed_pixels: u64,
+    pub difference_percent: f64,
+
+    /// PNG with the changed pixels highlighted over a faded copy of the new
+    /// image, `None` when nothing changed.
+    pub overlay_path: Option<String>,
+}
+
+/// Compares two PNG, JPEG or WebP images pixel by pixel. Images of different
+/// sizes are aligned at the top left corner and the area only one of them
+/// covers counts as changed. The overlay is written into `overlay_dir`, one
+/// file per pair of paths that the next diff of the pair replaces.
+pub fn diff_images(
+    old_path: &str,
+    new_path: &str,
+    overlay_dir: &Path,
+    options: &ImageDiffOptions,
+) -> anyhow::Result<ImageDiffResult, AppError> {
+    let (old, old_info) = load_image(old_path)?;
+    let (new, new_info) = load_image(new_path)?;
+
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+
+    let mut overlay = RgbaImage::new(width, height);
+    let mut changed_pixels: u64 = 0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let old_pixel = pixel_at(&old, x, y);
+            let new_pixel = pixel_at(&new, x, y);
+
+            let changed = match (old_pixel, new_pixel) {
+                (Some(a), Some(b)) => !same_pixel(a, b, options.tolerance),
+                _ => true,
+            };
+
+            let base = new_pixel.or(old_pixel).unwrap_or(Rgba([255, 255, 255, 0]));
+
+            overlay.put_pixel(x, y, overlay_pixel(base, changed));
+
+            if changed {
+                changed_pixels += 1;
+            }
+        }
+    }
+
+    let total_pixels = u64::from(width) * u64::from(height);
+
+    let difference_percent = if total_pixels == 0 {
+        0.0
+    } else {
+        changed_pixels as f64 * 100.0 / total_pixels as f64
+    };
+
+    let path = overlay_dir.join(overlay_file_name(old_path, new_path));
+
+    let overlay_path = if changed_pixels > 0 {
+        overlay
+            .save_with_format(&path, ImageFormat::Png)
+            .context(format!("Could not write overlay: {}", path.display()))
+            .map_err(|e| AppError::File(e.to_string()))?;
+
+        Some(path.to_string_lossy().to_string())
+    } else {
+        // The overlay of an earlier diff of the pair is stale now.
+        std::fs::remove_file(&path).ok();
+        None
+    };
+
+    Ok(ImageDiffResult {
+        same_dimensions: old_info.width == new_info.width && old_info.height == new_info.height,
+        old: old_info,
+        new: new_info,
+        total_pixels,
+        changed_pixels,
+        difference_percent,
+        overlay_path,
+    })
+}
+
+/// FNV-1a of both paths, unlike `DefaultHasher` it stays the same across Rust
+/// releases so a pair keeps its file.
+fn overlay_file_name(old_path: &str, new_path: &str) -> String {
+    let old_path = Utils::normalise_path(old_path);
+    let new_path = Utils::normalise_path(new_path);
+
+    let bytes = old_path.bytes().chain([0]).chain(new_path.bytes());
+    let hash = bytes.fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+
+    format!("{:016x}.png", hash)
+}
+
+fn load_image(path: &str) -> anyhow::Result<(RgbaImage, ImageInfo), AppError> {
+    let path = Utils::normalise_path(path);
+
+    let reader = ImageReader::open(&path)
+        .and_then(|reader| reader.with_guessed_format())
+        .context(format!("Could not open: {}", path))
+        .map_err(|e| AppError::File(e.to_string()))?;
+
+    let format = match reader.format() {
+        Some(ImageFormat::Png) => "png",
+        Some(ImageFormat::Jpeg) => "jpeg",
+        Some(ImageFormat::WebP) => "webp",
+        _ => {
+            return Err(AppError::Diff(format!(
+                "{} is not a PNG, JPEG or WebP image",
+                path
+            )))
+        }
+    };
+
+    let image: DynamicImage = reader
+        .decode()
+        .map_err(|e| AppError::Diff(format!("Could not decode {}: {}", path, e)))?;
+
+    let info = ImageInfo {
+        width: image.width(),
+        height: image.height(),
+        format: format.to_string(),
+    };
+
+    Ok((image.into_rgba8(), info))
+}
+
+fn pixel_at(image: &RgbaImage, x: u32, y: u32) -> Option<Rgba<u8>> {
+    (x < image.width() && y < image.height()).then(|| *image.get_pixel(x, y))
+}
+
+/// Fully transparent pixels are equal whatever their colour channels hold.
+fn same_pixel(a: Rgba<u8>, b: Rgba<u8>, tolerance: u8) -> bool {
+    if a[3] == 0 && b[3] == 0 {
+        return true;
+    }
+
+    a.0.iter()
+        .zip(b.0.iter())
+        .all(|(x, y)| x.abs_diff(*y) <= tolerance)
+}
+
+/// Unchanged pixels become a faded grey so the highlighted ones stand out.
+fn overlay_pixel(base: Rgba<u8>, changed: bool) -> Rgba<u8> {
+    let alpha = f32::from(base[3]) / 255.0;
+    let luma = 0.299 * f32::from(base[0]) + 0.587 * f32::from(base[1]) + 0.114 * f32::from(base[2]);
+
+    // Flatten onto white, then fade towards white.
+    let flat = luma * alpha + 255.0 * (1.0 - alpha);
+    let faded = 255.0 - (255.0 - flat) * 0.35;
+
+    let channel = |highlight: f32| {
+        let value = if changed {
+            highlight * 0.75 + faded * 0.25
+        } else {
+            faded
+        };
+
+        value.round().clamp(0.0, 255.0) as u8
+    };
+
+    Rgba([
+        channel(HIGHLIGHT[0]),
+        channel(HIGHLIGHT[1]),
+        channel(HIGHLIGHT[2]),
+        255,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path =
+                std::env::temp_dir().join(format!("differ_images_{}", Utils::get_random_id()));
+            fs::create_dir_all(&path).expect("failed to create temp dir");
+
+            Self(path)
+        }
+
+        fn image(&self, name: &str, image: &RgbaImage, format: ImageFormat) -> String {
+            let path = self.0.join(name);
+
+            // JPEG has no alpha channel.
+            let image = match format {
+                ImageFormat::Jpeg => DynamicImage::ImageRgba8(image.clone()).to_rgb8().into(),
+                _ => DynamicImage::ImageRgba8(image.clone()),
+            };
+            image
+                .save_with_format(&path, format)
+                .expect("failed to write image");
+
+            path.to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(color))
+    }
+
+    #[test]
+    fn test_identical_images() {
+        let dir = TempDir::new();
+        let old = dir.image("old.png", &solid(4, 4, [10, 20, 30, 255]), ImageFormat::Png);
+        let new = dir.image("new.png", &solid(4, 4, [10, 20, 30, 255]), ImageFormat::Png);
+
+        let result =
+            diff_images(&old, &new, &dir.0, &ImageDiffOptions::default()).expect("failed to diff");
+
+        assert!(result.same_dimensions);
+        assert_eq!(result.changed_pixels, 0);
+        assert_eq!(result.difference_percent, 0.0);
+        assert!(result.overlay_path.is_none());
+    }
+
+    #[test]
+    fn test_changed_pixels_and_overlay() {
+        let dir = TempDir::new();
+
+        let mut changed = solid(10, 10, [200, 200, 200, 255]);
+        for x in 0..5 {
+            changed.put_pixel(x, 0, Rgba([0, 0, 0, 255]));
+        }
+        // Within the tolerance, not a change.
+        changed.put_pixel(9, 9, Rgba([204, 200, 196, 255]));
+
+        let old = dir.image(
+            "old.png",
+            &solid(10, 10, [200, 200, 200, 255]),
+            ImageFormat::Png,
+        );
+        let new = dir.image("new.png", &changed, ImageFormat::Png);
+
+        let result =
+            diff_images(&old, &new, &dir.0, &ImageDiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(result.changed_pixels, 5);
+        assert_eq!(result.difference_percent, 5.0);
+
+        let overlay_path = result.overlay_path.expect("overlay should be written");
+        let overlay = image::open(&overlay_path)
+            .expect("failed to read overlay")
+            .into_rgba8();
+
+        assert_eq!(overlay.dimensions(), (10, 10));
+        assert_ne!(overlay.get_pixel(0, 0), overlay.get_pixel(9, 9));
+        assert_eq!(overlay.get_pixel(5, 5), overlay.get_pixel(9, 9));
+
+        let strict = ImageDiffOptions { tolerance: 0 };
+        let result = diff_images(&old, &new, &dir.0, &strict).expect("failed to diff");
+        assert_eq!(result.changed_pixels, 6);
+        assert_eq!(result.overlay_path.as_deref(), Some(overlay_path.as_str()));
+    }
+
+    #[test]
+    fn test_overlay_is_replaced_per_pair() {
+        let dir = TempDir::new();
+        let overlays = TempDir::new();
+        let old = dir.image("old.png", &solid(4, 4, [0, 0, 0, 255]), ImageFormat::Png);
+        let new = dir.image("new.png", &solid(4, 4, [255, 0, 0, 255]), ImageFormat::Png);
+        let options = ImageDiffOptions::default();
+
+        for _ in 0..3 {
+            diff_images(&old, &new, &overlays.0, &options).expect("failed to diff");
+        }
+        assert_eq!(
+            fs::read_dir(&overlays.0).expect("failed to list").count(),
+            1
+        );
+
+        dir.image("new.png", &solid(4, 4, [0, 0, 0, 255]), ImageFormat::Png);
+        let result = diff_images(&old, &new, &overlays.0, &options).expect("failed to diff");
+
+        assert!(result.overlay_path.is_none());
+        assert_eq!(
+            fs::read_dir(&overlays.0).expect("failed to list").count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_overlay_file_name_is_stable() {
+        assert_eq!(
+            overlay_file_name("/images/old.png", "/images/new.png"),
+            "451e036c05c1ed4c.png"
+        );
+        assert_eq!(
+            overlay_file_name("\\images\\old.png", "\\images\\new.png"),
+            "451e036c05c1ed4c.png"
+        );
+        assert_ne!(
+            overlay_file_name("/images/new.png", "/images/old.png"),
+            "451e036c05c1ed4c.png"
+        );
+    }
+
+    #[test]
+    fn test_dimensions_and_formats() {
+        let dir = TempDir::new();
+        let old = dir.image("old.png", &solid(4, 2, [0, 0, 255, 255]), ImageFormat::Png);
+        let new = dir.image("new.jpg", &solid(4, 4, [0, 0, 255, 255]), ImageFormat::Jpeg);
+
+        let result =
+            diff_images(&old, &new, &dir.0, &ImageDiffOptions::default()).expect("failed to diff");
+
+        assert!(!result.same_dimensions);
+        assert_eq!(
+            (result.old.format.as_str(), result.new.format.as_str()),
+            ("png", "jpeg")
+        );
+        assert_eq!(result.total_pixels, 16);
+        assert_eq!(result.changed_pixels, 8);
+
+        let text = dir.0.join("notes.txt");
+        fs::write(&text, "not an image").expect("failed to write file");
+        assert!(diff_images(
+            &old,
+            &text.to_string_lossy(),
+            &dir.0,
+            &ImageDiffOptions::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use crate::diff::{diff_lines, split_lines};
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use similar::DiffTag;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeOptions {
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub base_label: String,
+
+    /// Include the base section (`|||||||`) in conflict markers, like `merge.conflictStyle=diff3`.
+    pub show_base: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            ours_label: "ours".to_string(),
+            theirs_label: "theirs".to_string(),
+            base_label: "base".to_string(),
+            show_base: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSide {
+    /// Neither side touched the lines.
+    Base,
+    Ours,
+    Theirs,
+    /// Both sides made the same change.
+    Both,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum MergeRegion {
+    Resolved {
+        source: MergeSide,
+        text: String,
+    },
+    Conflict {
+        id: usize,
+        /// 1-based start lines of the region in each input.
+        base_start: usize,
+        ours_start: usize,
+        theirs_start: usize,
+        base: String,
+        ours: String,
+        theirs: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub conflicts: usize,
+    pub regions: Vec<MergeRegion>,
+
+    /// Merged text with git-style conflict markers around unresolved regions.
+    pub merged: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    Base,
+    /// Ours followed by theirs.
+    Both,
+    Custom {
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveConflict {
+    pub id: usize,
+    pub resolution: ConflictResolution,
+}
+
+/// A changed range of base lines and the lines replacing it on one side.
+#[derive(Debug, Clone)]
+struct Change {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+/// Three-way merge of `ours` and `theirs` against their common `base`.
+/// Changes that do not overlap are applied automatically, overlapping ones
+/// become conflict regions unless both sides made the same edit.
+pub fn merge_three_way(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    options: &MergeOptions,
+) -> MergeResult {
+    let base_lines = split_lines(base);
+    let ours_lines = split_lines(ours);
+    let theirs_lines = split_lines(theirs);
+
+    let ours_changes = collect_changes(&base_lines, &ours_lines);
+    let theirs_changes = collect_changes(&base_lines, &theirs_lines);
+
+    let mut regions: Vec<MergeRegion> = vec![];
+    let mut conflicts = 0;
+
+    let (mut i, mut j) = (0, 0);
+    let mut base_pos = 0;
+    let mut ours_offset: isize = 0;
+    let mut theirs_offset: isize = 0;
+
+    loop {
+        let next_ours = ours_changes.get(i);
+        let next_theirs = theirs_changes.get(j);
+
+        let first = match (next_ours, next_theirs) {
+            (None, None) => break,
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (Some(a), Some(b)) => {
+                if a.base.start <= b.base.start {
+                    a
+                } else {
+                    b
+                }
+            }
+        };
+
+        // Grow the cluster until no change from either side touches it.
+        let cluster_start = first.base.start;
+        let mut cluster_end = first.base.end;
+        let (ours_from, theirs_from) = (i, j);
+
+        loop {
+            if let Some(a) = ours_changes.get(i).filter(|c| c.base.start <= cluster_end) {
+                cluster_end = cluster_end.max(a.base.end);
+                i += 1;
+                continue;
+            }
+
+            if let Some(b) = theirs_changes
+                .get(j)
+                .filter(|c| c.base.start <= cluster_end)
+            {
+                cluster_end = cluster_end.max(b.base.end);
+                j += 1;
+                continue;
+            }
+
+            break;
+        }
+
+        push_resolved(
+            &mut regions,
+            MergeSide::Base,
+            join(&base_lines[base_pos..cluster_start]),
+        );
+
+        let cluster = cluster_start..cluster_end;
+        let ours_range = side_range(&ours_changes[ours_from..i], &cluster, ours_offset);
+        let theirs_range = side_range(&theirs_changes[theirs_from..j], &cluster, theirs_offset);
+
+        ours_offset = ours_range.end as isize - cluster_end as isize;
+        theirs_offset = theirs_range.end as isize - cluster_end as isize;
+
+        let ours_text = join(&ours_lines[ours_range.clone()]);
+        let theirs_text = join(&theirs_lines[theirs_range.clone()]);
+
+        if ours_from == i {
+            push_resolved(&mut regions, MergeSide::Theirs, theirs_text);
+        } else if theirs_from == j {
+            push_resolved(&mut regions, MergeSide::Ours, ours_text);
+        } else if ours_text == theirs_text {
+            push_resolved(&mut regions, MergeSide::Both, ours_text);
+        } else {
+            regions.push(MergeRegion::Conflict {
+                id: conflicts,
+                base_start: cluster.start + 1,
+                ours_start: ours_range.start + 1,
+                theirs_start: theirs_range.start + 1,
+                base: join(&base_lines[cluster]),
+                ours: ours_text,
+                theirs: theirs_text,
+            });
+
+            conflicts += 1;
+        }
+
+        base_pos = cluster_end;
+    }
+
+    push_resolved(&mut regions, MergeSide::Base, join(&base_lines[base_pos..]));
+
+    let merged = render_conflict_markers(&regions, options);
+
+    MergeResult {
+        conflicts,
+        regions,
+        merged,
+    }
+}
+
+/// Renders regions as a single text, wrapping conflicts in git-style markers.
+pub fn render_conflict_markers(regions: &[MergeRegion], options: &MergeOptions) -> String {
+    let mut merged = String::new();
+
+    for region in regions {
+        match region {
+            MergeRegion::Resolved { text, .. } => merged.push_str(text),
+            MergeRegion::Conflict {
+                base, ours, theirs, ..
+            } => {
+                push_marker(&mut merged, "<<<<<<<", &options.ours_label);
+                push_section(&mut merged, ours);
+
+                if options.show_base {
+                    push_marker(&mut merged, "|||||||", &options.base_label);
+                    push_section(&mut merged, base);
+                }
+
+                push_marker(&mut merged, "=======", "");
+                push_section(&mut merged, theirs);
+                push_marker(&mut merged, ">>>>>>>", &options.theirs_label);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Applies a resolution to every conflict region and returns the final text.
+/// Fails when a conflict is left without a resolution.
+pub fn resolve_conflicts(
+    regions: &[MergeRegion],
+    resolutions: &[ResolveConflict],
+) -> anyhow::Result<String, AppError> {
+    let mut merged = String::new();
+
+    for region in regions {
+        match region {
+            MergeRegion::Resolved { text, .. } => merged.push_str(text),
+            MergeRegion::Conflict {
+                id,
+                base,
+                ours,
+                theirs,
+                ..
+            } => {
+                let resolution = resolutions
+                    .iter()
+                    .find(|r| r.id == *id)
+                    .ok_or_else(|| AppError::Merge(format!("Conflict {} is unresolved", id)))?;
+
+                match &resolution.resolution {
+                    ConflictResolution::Ours => merged.push_str(ours),
+                    ConflictResolution::Theirs => merged.push_str(theirs),
+                    ConflictResolution::Base => merged.push_str(base),
+                    ConflictResolution::Both => {
+                        push_section(&mut merged, ours);
+                        merged.push_str(theirs);
+                    }
+                    ConflictResolution::Custom { text } => merged.push_str(text),
+                }
+            }
+        }
+    }
+
+    Ok(merged)
+}
+
+fn collect_changes(base: &[&str], side: &[&str]) -> Vec<Change> {
+    diff_lines(base, side)
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| Change {
+            base: op.old_range(),
+            side: op.new_range(),
+        })
+        .collect()
+}
+
+/// Maps a cluster of base lines to the matching lines on one side. `changes`
+/// are the side's changes inside the cluster, `offset` is how far the side
+/// has drifted from base before the cluster.
+fn side_range(changes: &[Change], cluster: &Range<usize>, offset: isize) -> Range<usize> {
+    match (changes.first(), changes.last()) {
+        (Some(first), Some(last)) => {
+            let start = first.side.start - (first.base.start - cluster.start);
+            let end = last.side.end + (cluster.end - last.base.end);
+
+            start..end
+        }
+        _ => {
+            let start = (cluster.start as isize + offset) as usize;
+            let end = (cluster.end as isize + offset) as usize;
+
+            start..end
+        }
+    }
+}
+
+/// Appends a resolved region, merging it into the previous one from the same source.
+fn push_resolved(regions: &mut Vec<MergeRegion>, source: MergeSide, text: String) {
+    if text.is_empty() {
+        return;
+    }
+
+    if let Some(MergeRegion::Resolved {
+        source: last_source,
+        text: last_text,
+    }) = regions.last_mut()
+    {
+        if *last_source == source {
+            last_text.push_str(&text);
+            return;
+        }
+    }
+
+    regions.push(MergeRegion::Resolved { source, text });
+}
+
+fn push_marker(merged: &mut String, marker: &str, label: &str) {
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+
+    merged.push_str(marker);
+
+    if !label.is_empty() {
+        merged.push(' ');
+        merged.push_str(label);
+    }
+
+    merged.push('\n');
+}
+
+/// Appends a section, making sure whatever follows starts on a new line.
+fn push_section(merged: &mut String, section: &str) {
+    merged.push_str(section);
+
+    if !section.is_empty() && !section.ends_with('\n') {
+        merged.push('\n');
+    }
+}
+
+fn join(lines: &[&str]) -> String {
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+    #[test]
+    fn test_non_overlapping_changes_merge_cleanly() {
+        let ours = "ONE\ntwo\nthree\nfour\nfive\n";
+        let theirs = "one\ntwo\nthree\nfour\nFIVE\n";
+
+        let result = merge_three_way(BASE, ours, theirs, &MergeOptions::default());
+
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, "ONE\ntwo\nthree\nfour\nFIVE\n");
+    }
+
+    #[test]
+    fn test_identical_changes_are_not_conflicts() {
+        let ours = "one\n2\nthree\nfour\nfive\n";
+
+        let result = merge_three_way(BASE, ours, ours, &MergeOptions::default());
+
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, ours);
+    }
+
+    #[test]
+    fn test_overlapping_changes_conflict() {
+        let ours = "one\ntwo\nTHREE (ours)\nfour\nfive\n";
+        let theirs = "one\ntwo\nTHREE (theirs)\nfour\nfive\n";
+
+        let result = merge_three_way(BASE, ours, theirs, &MergeOptions::default());
+
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.merged,
+            "one\ntwo\n<<<<<<< ours\nTHREE (ours)\n=======\nTHREE (theirs)\n>>>>>>> theirs\nfour\nfive\n"
+        );
+
+        match &result.regions[1] {
+            MergeRegion::Conflict {
+                base_start, base, ..
+            } => {
+                assert_eq!(*base_start, 3);
+                assert_eq!(base, "three\n");
+            }
+            _ => panic!("expected a conflict region"),
+        }
+    }
+
+    #[test]
+    fn test_conflict_markers_with_base() {
+        let ours = "one\ntwo\nours\nfour\nfive\n";
+        let theirs = "one\ntwo\ntheirs\nfour\nfive\n";
+        let options = MergeOptions {
+            show_base: true,
+            ..MergeOptions::default()
+        };
+
+        let result = merge_three_way(BASE, ours, theirs, &options);
+
+        assert!(result.merged.contains("||||||| base\nthree\n=======\n"));
+    }
+
+    #[test]
+    fn test_changes_after_insertions_keep_offsets() {
+        let ours = "zero\none\ntwo\nthree\nfour\nfive\n";
+        let theirs = "one\ntwo\nthree\nfour\nfive\nsix\n";
+
+        let result = merge_three_way(BASE, ours, theirs, &MergeOptions::default());
+
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, "zero\none\ntwo\nthree\nfour\nfive\nsix\n");
+    }
+
+    #[test]
+    fn test_resolve_conflicts() {
+        let ours = "one\ntwo\nours\nfour\nfive\n";
+        let theirs = "one\ntwo\ntheirs\nfour\nfive\n";
+
+        let result = merge_three_way(BASE, ours, theirs, &MergeOptions::default());
+
+        let resolved = resolve_conflicts(
+            &result.regions,
+            &[ResolveConflict {
+                id: 0,
+                resolution: ConflictResolution::Both,
+            }],
+        )
+        .expect("failed to resolve conflicts");
+
+        assert_eq!(resolved, "one\ntwo\nours\ntheirs\nfour\nfive\n");
+
+        assert!(resolve_conflicts(&result.regions, &[]).is_err());
+    }
+}
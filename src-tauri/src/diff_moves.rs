@@ -0,0 +1,389 @@
+use crate::constants::{
+    MOVED_BLOCK_MAX_CANDIDATES, MOVED_BLOCK_MAX_GAP, MOVED_BLOCK_MIN_CHARS, MOVED_BLOCK_MIN_LINES,
+};
+use crate::diff::{build_hunks, ChangeKind, Hunk, Normalizer};
+use serde::{Deserialize, Serialize};
+use similar::{DiffOp, DiffTag};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// A block deleted in one place and inserted in another. Its lines are still
+/// part of the regular hunks, tagged with the block `id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovedBlock {
+    pub id: usize,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+
+    /// Changes made to the block while moving it, empty for a plain move.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<Hunk>,
+}
+
+/// A run of identical deleted and inserted lines, 0-based.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    old_start: usize,
+    new_start: usize,
+    len: usize,
+}
+
+/// Pairs deleted lines with identical inserted lines elsewhere, git
+/// `--color-moved` style. Runs shorter than [`MOVED_BLOCK_MIN_LINES`] or with
+/// fewer than [`MOVED_BLOCK_MIN_CHARS`] alphanumeric characters are ignored so
+/// that braces and blank lines do not count as moves. Runs separated by a few
+/// edited lines are joined into one block with edits.
+///
+/// Lines inserted more than [`MOVED_BLOCK_MAX_CANDIDATES`] times (`}`, blank
+/// lines, `end`) never start a run, and the search stops at `deadline` with
+/// the blocks found so far.
+pub(crate) fn detect_moves(
+    old: &[&str],
+    new: &[&str],
+    ops: &[DiffOp],
+    normalizer: &Normalizer,
+    deadline: Instant,
+) -> Vec<MovedBlock> {
+    let mut deleted = vec![false; old.len()];
+    let mut inserted = vec![false; new.len()];
+
+    for op in ops {
+        if matches!(op.tag(), DiffTag::Delete | DiffTag::Replace) {
+            op.old_range().for_each(|i| deleted[i] = true);
+        }
+
+        if matches!(op.tag(), DiffTag::Insert | DiffTag::Replace) {
+            op.new_range().for_each(|j| inserted[j] = true);
+        }
+    }
+
+    let old_keys: Vec<Cow<str>> = old.iter().map(|line| normalizer.key(line)).collect();
+    let new_keys: Vec<Cow<str>> = new.iter().map(|line| normalizer.key(line)).collect();
+
+    let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+    for j in (0..new.len()).filter(|&j| inserted[j]) {
+        positions.entry(new_keys[j].as_ref()).or_default().push(j);
+    }
+
+    positions.retain(|_, destinations| destinations.len() <= MOVED_BLOCK_MAX_CANDIDATES);
+
+    let mut old_used = vec![false; old.len()];
+    let mut new_used = vec![false; new.len()];
+
+    let run_len = |i: usize, j: usize, old_used: &[bool], new_used: &[bool]| {
+        let mut len = 0;
+
+        while i + len < old.len()
+            && j + len < new.len()
+            && deleted[i + len]
+            && inserted[j + len]
+            && !old_used[i + len]
+            && !new_used[j + len]
+            && old_keys[i + len] == new_keys[j + len]
+        {
+            len += 1;
+        }
+
+        len
+    };
+
+    let mut runs: Vec<Run> = vec![];
+    let mut i = 0;
+
+    while i < old.len() {
+        if Instant::now() >= deadline {
+            break;
+        }
+
+        if !deleted[i] || old_used[i] {
+            i += 1;
+            continue;
+        }
+
+        let candidates: Vec<(usize, usize)> = positions
+            .get(old_keys[i].as_ref())
+            .into_iter()
+            .flatten()
+            .map(|&j| (j, run_len(i, j, &old_used, &new_used)))
+            .filter(|&(_, len)| len > 0)
+            .collect();
+
+        let best = longest(candidates.iter().copied())
+            .filter(|&(_, len)| is_significant(&old[i..i + len]));
+
+        // Short runs still count when they continue the previous block, e.g.
+        // the tail of a moved function after an edited line.
+        let continuation = runs.last().and_then(|last| {
+            longest(candidates.iter().copied().filter(|&(j, _)| {
+                continues(last.old_start + last.len, last.new_start + last.len, i, j)
+            }))
+        });
+
+        let chosen = match (best, continuation) {
+            (Some(best), Some(continuation)) if continuation.1 >= best.1 => Some(continuation),
+            (Some(best), _) => Some(best),
+            (None, continuation) => continuation,
+        };
+
+        match chosen {
+            Some((j, len)) => {
+                old_used[i..i + len].fill(true);
+                new_used[j..j + len].fill(true);
+
+                runs.push(Run {
+                    old_start: i,
+                    new_start: j,
+                    len,
+                });
+
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+
+    join_runs(runs, &deleted, &inserted, &old_used, &new_used)
+        .into_iter()
+        .filter(|(old_range, _, _)| is_significant(&old[old_range.clone()]))
+        .enumerate()
+        .map(|(id, (old_range, new_range, has_edits))| {
+            let edits = if has_edits {
+                let old_block = &old[old_range.clone()];
+                let new_block = &new[new_range.clone()];
+                let ops = normalizer.diff(old_block, new_block);
+
+                build_hunks(
+                    old_block,
+                    new_block,
+                    &ops,
+                    normalizer,
+                    old_range.start,
+                    new_range.start,
+                )
+            } else {
+                vec![]
+            };
+
+            MovedBlock {
+                id,
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+                edits,
+            }
+        })
+        .collect()
+}
+
+/// Longest `(destination, length)` candidate, the earliest destination on ties.
+fn longest(candidates: impl Iterator<Item = (usize, usize)>) -> Option<(usize, usize)> {
+    candidates.max_by_key(|&(j, len)| (len, std::cmp::Reverse(j)))
+}
+
+/// Whether a run starting at `i` and `j` is close enough to the ends of the
+/// previous one to join it.
+fn continues(old_end: usize, new_end: usize, i: usize, j: usize) -> bool {
+    i >= old_end
+        && j >= new_end
+        && i - old_end <= MOVED_BLOCK_MAX_GAP
+        && j - new_end <= MOVED_BLOCK_MAX_GAP
+}
+
+/// Joins consecutive runs when the lines between them were changed on both
+/// sides and are not part of another move.
+fn join_runs(
+    runs: Vec<Run>,
+    deleted: &[bool],
+    inserted: &[bool],
+    old_used: &[bool],
+    new_used: &[bool],
+) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>, bool)> {
+    let mut blocks: Vec<(std::ops::Range<usize>, std::ops::Range<usize>, bool)> = vec![];
+
+    for run in runs {
+        let old_range = run.old_start..run.old_start + run.len;
+        let new_range = run.new_start..run.new_start + run.len;
+
+        if let Some((last_old, last_new, has_edits)) = blocks.last_mut() {
+            let joins = continues(last_old.end, last_new.end, old_range.start, new_range.start)
+                && (last_old.end..old_range.start).all(|i| deleted[i] && !old_used[i])
+                && (last_new.end..new_range.start).all(|j| inserted[j] && !new_used[j]);
+
+            if joins {
+                *has_edits |= old_range.start > last_old.end || new_range.start > last_new.end;
+                last_old.end = old_range.end;
+                last_new.end = new_range.end;
+                continue;
+            }
+        }
+
+        blocks.push((old_range, new_range, false));
+    }
+
+    blocks
+}
+
+fn is_significant(lines: &[&str]) -> bool {
+    let non_blank = lines.iter().filter(|l| !l.trim().is_empty()).count();
+    let alphanumeric = lines
+        .iter()
+        .flat_map(|l| l.chars())
+        .filter(|c| c.is_alphanumeric())
+        .count();
+
+    non_blank >= MOVED_BLOCK_MIN_LINES && alphanumeric >= MOVED_BLOCK_MIN_CHARS
+}
+
+/// Tags the hunk lines that belong to a moved block with its id.
+pub(crate) fn mark_moved_lines(hunks: &mut [Hunk], moves: &[MovedBlock]) {
+    if moves.is_empty() {
+        return;
+    }
+
+    for line in hunks.iter_mut().flat_map(|h| h.lines.iter_mut()) {
+        line.move_id = match (line.kind, line.old_line, line.new_line) {
+            (ChangeKind::Delete, Some(n), _) => moves
+                .iter()
+                .find(|m| (m.old_start..m.old_start + m.old_lines).contains(&n))
+                .map(|m| m.id),
+            (ChangeKind::Insert, _, Some(n)) => moves
+                .iter()
+                .find(|m| (m.new_start..m.new_start + m.new_lines).contains(&n))
+                .map(|m| m.id),
+            _ => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{compute_diff, diff_deadline, split_lines, DiffOptions};
+    use std::time::Duration;
+
+    const PARSE: &str = "fn parse(input: &str) -> Vec<String> {\n    input.lines().map(String::from).collect()\n}\n";
+    const RENDER: &str = "fn render(lines: &[String]) -> String {\n    lines.join(\"\\n\")\n}\n";
+
+    #[test]
+    fn test_moved_function() {
+        let old = format!("{PARSE}\n{RENDER}\nfn main() {{}}\n");
+        let new = format!("{RENDER}\nfn main() {{}}\n\n{PARSE}");
+
+        let result = compute_diff(&old, &new, &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(result.moves.len(), 1);
+
+        let moved = &result.moves[0];
+        assert_eq!((moved.old_start, moved.old_lines), (1, 3));
+        assert_eq!((moved.new_start, moved.new_lines), (7, 3));
+        assert!(moved.edits.is_empty());
+
+        let tagged = result
+            .hunks
+            .iter()
+            .flat_map(|h| h.lines.iter())
+            .filter(|l| l.move_id == Some(moved.id))
+            .count();
+        assert_eq!(tagged, 6);
+    }
+
+    #[test]
+    fn test_moved_block_with_edits() {
+        let function = "fn one() {\n    let first = 1;\n    let second = 2;\n    let third = 3;\n    let fourth = 4;\n}\n";
+        let others: String = (1..=10).map(|i| format!("other line {i}\n")).collect();
+
+        let old = format!("{function}{others}");
+        let new = format!("{others}{}", function.replace("third = 3", "third = 33"));
+
+        let result = compute_diff(&old, &new, &DiffOptions::default()).expect("failed to diff");
+
+        assert_eq!(result.moves.len(), 1);
+
+        let moved = &result.moves[0];
+        assert_eq!((moved.old_start, moved.old_lines), (1, 6));
+        assert_eq!((moved.new_start, moved.new_lines), (11, 6));
+        assert_eq!(moved.edits.len(), 1);
+
+        let edited: Vec<_> = moved.edits[0]
+            .lines
+            .iter()
+            .filter(|l| l.old_line.is_none() || l.new_line.is_none())
+            .map(|l| (l.old_line, l.new_line))
+            .collect();
+        assert_eq!(edited, vec![(Some(4), None), (None, Some(14))]);
+    }
+
+    /// A diff that gave up at its deadline, every line deleted and inserted.
+    fn replace_all(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+        vec![DiffOp::Replace {
+            old_index: 0,
+            old_len: old.len(),
+            new_index: 0,
+            new_len: new.len(),
+        }]
+    }
+
+    #[test]
+    fn test_repeated_lines_stay_fast() {
+        let repeated = 20_000;
+        let old_text: String = (0..repeated).map(|i| format!("}}\nold {i}\n")).collect();
+        let new_text: String = (0..repeated).map(|i| format!("}}\nnew {i}\n")).collect();
+        let old_text = format!("{PARSE}{old_text}");
+        let new_text = format!("{new_text}{PARSE}");
+
+        let old = split_lines(&old_text);
+        let new = split_lines(&new_text);
+        let options = DiffOptions::default();
+        let normalizer = Normalizer::new(&options).expect("failed to build normalizer");
+
+        let started = Instant::now();
+        let moves = detect_moves(
+            &old,
+            &new,
+            &replace_all(&old, &new),
+            &normalizer,
+            diff_deadline(),
+        );
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].old_start, moves[0].old_lines), (1, 3));
+    }
+
+    #[test]
+    fn test_search_stops_at_the_deadline() {
+        let old_text = format!("{PARSE}{RENDER}");
+        let new_text = format!("{RENDER}{PARSE}");
+        let old = split_lines(&old_text);
+        let new = split_lines(&new_text);
+        let options = DiffOptions::default();
+        let normalizer = Normalizer::new(&options).expect("failed to build normalizer");
+        let ops = replace_all(&old, &new);
+
+        let moves = detect_moves(&old, &new, &ops, &normalizer, Instant::now());
+        assert!(moves.is_empty());
+
+        let moves = detect_moves(&old, &new, &ops, &normalizer, diff_deadline());
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn test_short_or_trivial_blocks_are_not_moves() {
+        let old = "}\n}\n}\nlet value = compute();\n";
+        let new = "let value = compute();\n}\n}\n}\n";
+
+        let result = compute_diff(old, new, &DiffOptions::default()).expect("failed to diff");
+        assert!(result.moves.is_empty());
+
+        let options = DiffOptions {
+            detect_moves: false,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(PARSE, "", &options).expect("failed to diff");
+        assert!(result.moves.is_empty());
+    }
+}
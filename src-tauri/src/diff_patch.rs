@@ -0,0 +1,590 @@
+use crate::diff::{hunk_start, split_lines, trim_line_ending, ChangeKind, Hunk};
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchLine {
+    pub kind: ChangeKind,
+
+    /// Line contents including the line ending, without the `+`/`-`/` ` prefix.
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<PatchLine>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilePatch {
+    /// Paths without the `a/` and `b/` prefixes, `None` for `/dev/null`.
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<PatchHunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkOutcome {
+    /// Index of the hunk in the patch.
+    pub hunk: usize,
+    pub applied: bool,
+
+    /// Lines between where the hunk said it applies and where it matched.
+    pub offset: isize,
+
+    /// Context lines that had to be ignored for the hunk to match.
+    pub fuzz: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyResult {
+    pub text: String,
+    pub applied: usize,
+    pub failed: usize,
+    pub hunks: Vec<HunkOutcome>,
+}
+
+/// Renders hunks from [`crate::diff::compute_diff`] of `old` and `new` as a
+/// unified diff, the format understood by `git apply` and `patch`. Changes
+/// dropped by the ignore options stay out of the patch. Returns an empty
+/// string when there are no hunks.
+pub fn hunks_to_unified(
+    old: &str,
+    new: &str,
+    hunks: &[Hunk],
+    old_name: &str,
+    new_name: &str,
+) -> String {
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    let mut patch = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    // Changes dropped by the ignore options may add or remove lines, the new
+    // side of a hunk starts where the hunks before it in the patch put it.
+    let mut offset: isize = 0;
+
+    for hunk in hunks {
+        let old_index = match hunk.old_lines {
+            0 => hunk.old_start,
+            _ => hunk.old_start - 1,
+        };
+        let new_start = hunk_start(old_index.saturating_add_signed(offset), hunk.new_lines);
+
+        offset += hunk.new_lines as isize - hunk.old_lines as isize;
+
+        patch.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            format_range(hunk.old_start, hunk.old_lines),
+            format_range(new_start, hunk.new_lines),
+        ));
+
+        for line in &hunk.lines {
+            match (line.kind, line.old_line, line.new_line) {
+                (ChangeKind::Equal, Some(i), _) => {
+                    push_patch_line(&mut patch, ' ', old_lines[i - 1])
+                }
+                (ChangeKind::Delete, Some(i), _) => {
+                    push_patch_line(&mut patch, '-', old_lines[i - 1])
+                }
+                (ChangeKind::Insert, _, Some(j)) => {
+                    push_patch_line(&mut patch, '+', new_lines[j - 1])
+                }
+                _ => {}
+            }
+        }
+    }
+
+    patch
+}
+
+/// Parses a unified diff, possibly touching several files.
+pub fn parse_patch(patch: &str) -> anyhow::Result<Vec<FilePatch>, AppError> {
+    let mut files: Vec<FilePatch> = vec![];
+    let mut lines = split_lines(patch).into_iter().peekable();
+
+    while let Some(line) = lines.next() {
+        let text = trim_line_ending(line);
+
+        if let Some(path) = text.strip_prefix("--- ") {
+            files.push(FilePatch {
+                old_path: parse_path(path, "a/"),
+                ..FilePatch::default()
+            });
+            continue;
+        }
+
+        if let Some(path) = text.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = parse_path(path, "b/");
+            }
+            continue;
+        }
+
+        if !text.starts_with("@@ ") {
+            // `diff --git`, `index` and other extended headers carry nothing we need.
+            continue;
+        }
+
+        let mut hunk = parse_hunk_header(text)?;
+        let mut old_remaining = hunk.old_lines;
+        let mut new_remaining = hunk.new_lines;
+
+        while old_remaining > 0 || new_remaining > 0 {
+            let line = lines.next().ok_or_else(|| {
+                AppError::Patch(format!("Hunk '{}' ends before all of its lines", text))
+            })?;
+
+            let (kind, content) = match line.chars().next() {
+                Some(' ') => (ChangeKind::Equal, &line[1..]),
+                Some('-') => (ChangeKind::Delete, &line[1..]),
+                Some('+') => (ChangeKind::Insert, &line[1..]),
+                // Some editors strip the single space of empty context lines.
+                Some('\n') | Some('\r') => (ChangeKind::Equal, line),
+                Some('\\') => {
+                    trim_last_line(&mut hunk);
+                    continue;
+                }
+                _ => {
+                    return Err(AppError::Patch(format!(
+                        "Unexpected line in hunk '{}': {}",
+                        text,
+                        trim_line_ending(line)
+                    )))
+                }
+            };
+
+            match kind {
+                ChangeKind::Equal => {
+                    old_remaining = old_remaining.saturating_sub(1);
+                    new_remaining = new_remaining.saturating_sub(1);
+                }
+                ChangeKind::Delete => old_remaining = old_remaining.saturating_sub(1),
+                ChangeKind::Insert => new_remaining = new_remaining.saturating_sub(1),
+            }
+
+            hunk.lines.push(PatchLine {
+                kind,
+                content: content.to_string(),
+            });
+        }
+
+        // The marker applies to the line right before it.
+        if lines
+            .peek()
+            .is_some_and(|next| next.starts_with(NO_NEWLINE_MARKER))
+        {
+            lines.next();
+            trim_last_line(&mut hunk);
+        }
+
+        match files.last_mut() {
+            Some(file) => file.hunks.push(hunk),
+            None => files.push(FilePatch {
+                hunks: vec![hunk],
+                ..FilePatch::default()
+            }),
+        }
+    }
+
+    if files.iter().all(|f| f.hunks.is_empty()) {
+        return Err(AppError::Patch("No hunks found in patch".to_string()));
+    }
+
+    Ok(files)
+}
+
+/// Applies the hunks of `patch` to `text`. Hunks are matched at their
+/// recorded position first, then at growing offsets from it, then with up to
+/// `max_fuzz` context lines ignored at either end. Hunks that still do not
+/// match are skipped and reported.
+pub fn apply_patch(text: &str, patch: &FilePatch, max_fuzz: usize) -> ApplyResult {
+    let lines = split_lines(text);
+
+    let mut output = String::new();
+    let mut outcomes = vec![];
+    let mut pos = 0;
+    let mut drift: isize = 0;
+
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|l| l.kind != ChangeKind::Insert)
+            .map(|l| l.content.as_str())
+            .collect();
+        let new: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|l| l.kind != ChangeKind::Delete)
+            .map(|l| l.content.as_str())
+            .collect();
+
+        let leading = hunk
+            .lines
+            .iter()
+            .take_while(|l| l.kind == ChangeKind::Equal)
+            .count();
+        let trailing = hunk
+            .lines
+            .iter()
+            .rev()
+            .take_while(|l| l.kind == ChangeKind::Equal)
+            .count()
+            .min(old.len() - leading);
+
+        let recorded = if hunk.old_lines == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (recorded as isize + drift).max(0) as usize;
+
+        let mut matched = None;
+
+        for fuzz in 0..=max_fuzz.min(leading.max(trailing)) {
+            let front = fuzz.min(leading);
+            let back = fuzz.min(trailing);
+            let needle = &old[front..old.len() - back];
+
+            if let Some(at) = find_hunk(&lines, needle, pos, expected + front) {
+                matched = Some((at, fuzz, front, back, needle.len()));
+                break;
+            }
+        }
+
+        match matched {
+            Some((at, fuzz, front, back, len)) => {
+                output.push_str(&lines[pos..at].concat());
+                output.push_str(&new[front..new.len() - back].concat());
+
+                let offset = at as isize - (expected + front) as isize;
+                drift += offset;
+                pos = at + len;
+
+                outcomes.push(HunkOutcome {
+                    hunk: index,
+                    applied: true,
+                    offset,
+                    fuzz,
+                });
+            }
+            None => outcomes.push(HunkOutcome {
+                hunk: index,
+                applied: false,
+                offset: 0,
+                fuzz: 0,
+            }),
+        }
+    }
+
+    output.push_str(&lines[pos..].concat());
+
+    let applied = outcomes.iter().filter(|o| o.applied).count();
+
+    ApplyResult {
+        text: output,
+        applied,
+        failed: outcomes.len() - applied,
+        hunks: outcomes,
+    }
+}
+
+/// Position closest to `expected`, not before `from`, where `needle` matches.
+/// Line endings are ignored so patches survive CRLF conversions.
+fn find_hunk(lines: &[&str], needle: &[&str], from: usize, expected: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(needle.len())?;
+
+    if from > last {
+        return None;
+    }
+
+    let expected = expected.clamp(from, last);
+    let matches_at = |at: usize| {
+        lines[at..at + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| trim_line_ending(a) == trim_line_ending(b))
+    };
+
+    for distance in 0..=(last - from) {
+        if expected + distance <= last && matches_at(expected + distance) {
+            return Some(expected + distance);
+        }
+
+        if distance > 0 && distance <= expected - from && matches_at(expected - distance) {
+            return Some(expected - distance);
+        }
+    }
+
+    None
+}
+
+fn push_patch_line(patch: &mut String, prefix: char, line: &str) {
+    patch.push(prefix);
+    patch.push_str(line);
+
+    if !line.ends_with('\n') {
+        patch.push('\n');
+        patch.push_str(NO_NEWLINE_MARKER);
+        patch.push('\n');
+    }
+}
+
+fn format_range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, len)
+    }
+}
+
+fn parse_path(path: &str, prefix: &str) -> Option<String> {
+    // Timestamps from `diff -u` are separated from the path by a tab.
+    let path = path.split('\t').next().unwrap_or_default().trim_end();
+
+    if path == "/dev/null" {
+        return None;
+    }
+
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Parses `@@ -old_start,old_lines +new_start,new_lines @@ section`.
+fn parse_hunk_header(header: &str) -> anyhow::Result<PatchHunk, AppError> {
+    let invalid = || AppError::Patch(format!("Invalid hunk header: {}", header));
+
+    let ranges = header
+        .strip_prefix("@@ ")
+        .and_then(|rest| rest.split(" @@").next())
+        .ok_or_else(invalid)?;
+
+    let mut parts = ranges.split(' ');
+
+    let old = parts
+        .next()
+        .and_then(|p| p.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let new = parts
+        .next()
+        .and_then(|p| p.strip_prefix('+'))
+        .ok_or_else(invalid)?;
+
+    let (old_start, old_lines) = parse_range(old).ok_or_else(invalid)?;
+    let (new_start, new_lines) = parse_range(new).ok_or_else(invalid)?;
+
+    Ok(PatchHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: vec![],
+    })
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Handles `\ No newline at end of file`, which applies to the line before it.
+fn trim_last_line(hunk: &mut PatchHunk) {
+    if let Some(last) = hunk.lines.last_mut() {
+        let trimmed = trim_line_ending(&last.content).len();
+        last.content.truncate(trimmed);
+    }
+}
+
+/// Convenience for callers that expect a single file in the patch.
+pub fn single_file_patch(patch: &str) -> anyhow::Result<FilePatch, AppError> {
+    let mut files = parse_patch(patch)?;
+
+    if files.len() != 1 {
+        return Err(AppError::Patch(format!(
+            "Expected a patch for one file, found {}",
+            files.len()
+        )));
+    }
+
+    Ok(files.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{compute_diff, DiffOptions};
+
+    /// Patch of the full diff with `context` lines around each hunk.
+    fn unified_patch(
+        old: &str,
+        new: &str,
+        old_name: &str,
+        new_name: &str,
+        context: usize,
+    ) -> String {
+        let options = DiffOptions {
+            context_lines: context,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(old, new, &options).expect("failed to diff");
+
+        hunks_to_unified(old, new, &result.hunks, old_name, new_name)
+    }
+
+    const OLD: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+    const NEW: &str = "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\neight\n";
+
+    #[test]
+    fn test_unified_patch_format() {
+        let patch = unified_patch(OLD, NEW, "a/file.txt", "b/file.txt", 3);
+
+        assert_eq!(
+            patch,
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,7 +1,8 @@\n one\n two\n-three\n+THREE\n four\n five\n six\n seven\n+eight\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_patch_marks_missing_newline() {
+        let patch = unified_patch("a\nb", "a\nc", "a/f", "b/f", 3);
+
+        assert!(
+            patch.contains("-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n")
+        );
+    }
+
+    #[test]
+    fn test_hunks_to_unified_renumbers_after_ignored_changes() {
+        let old = "a\n\n\nb\nc\nd\ne\nf\n";
+        let new = "a\nb\nc\nd\ne\nF\ng\n";
+        let options = DiffOptions {
+            ignore_blank_lines: true,
+            context_lines: 1,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(old, new, &options).expect("failed to diff");
+        let patch = hunks_to_unified(old, new, &result.hunks, "a/f", "b/f");
+
+        // The blank lines are left out, so `f` is still line 8 after the patch.
+        assert_eq!(patch, "--- a/f\n+++ b/f\n@@ -7,2 +7,3 @@\n e\n-f\n+F\n+g\n");
+
+        let parsed = parse_patch(&patch).expect("failed to parse patch");
+        let applied = apply_patch(old, &parsed[0], 0);
+
+        assert_eq!(applied.failed, 0);
+        assert_eq!(applied.hunks[0].offset, 0);
+        assert_eq!(applied.text, "a\n\n\nb\nc\nd\ne\nF\ng\n");
+    }
+
+    #[test]
+    fn test_hunks_to_unified_follows_diff_options() {
+        let options = DiffOptions {
+            ignore_case: true,
+            context_lines: 1,
+            ..DiffOptions::default()
+        };
+        let result = compute_diff(OLD, NEW, &options).expect("failed to diff");
+        let patch = hunks_to_unified(OLD, NEW, &result.hunks, "a/f", "b/f");
+
+        assert_eq!(patch, "--- a/f\n+++ b/f\n@@ -7 +7,2 @@\n seven\n+eight\n");
+
+        let only_case = compute_diff(OLD, &OLD.to_uppercase(), &options).expect("failed to diff");
+        assert_eq!(
+            hunks_to_unified(OLD, &OLD.to_uppercase(), &only_case.hunks, "a/f", "b/f"),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_identical_texts_produce_empty_patch() {
+        assert_eq!(unified_patch(OLD, OLD, "a/f", "b/f", 3), "");
+    }
+
+    #[test]
+    fn test_parse_and_apply_round_trip() {
+        let patch = unified_patch(OLD, NEW, "a/file.txt", "b/file.txt", 3);
+
+        let file = single_file_patch(&patch).expect("failed to parse patch");
+        assert_eq!(file.old_path.as_deref(), Some("file.txt"));
+        assert_eq!(file.new_path.as_deref(), Some("file.txt"));
+
+        let result = apply_patch(OLD, &file, 0);
+
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.text, NEW);
+    }
+
+    #[test]
+    fn test_round_trip_without_trailing_newline() {
+        let old = "a\nb";
+        let new = "a\nb\nc";
+
+        let patch = unified_patch(old, new, "a/f", "b/f", 3);
+        let file = single_file_patch(&patch).expect("failed to parse patch");
+
+        assert_eq!(apply_patch(old, &file, 0).text, new);
+    }
+
+    #[test]
+    fn test_apply_with_offset() {
+        let patch = unified_patch(OLD, NEW, "a/file.txt", "b/file.txt", 1);
+        let file = single_file_patch(&patch).expect("failed to parse patch");
+
+        let shifted = format!("zero\nzero\n{}", OLD);
+        let result = apply_patch(&shifted, &file, 0);
+
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.hunks[0].offset, 2);
+        assert_eq!(result.text, format!("zero\nzero\n{}", NEW));
+    }
+
+    #[test]
+    fn test_apply_with_fuzz() {
+        let patch = unified_patch(OLD, NEW, "a/file.txt", "b/file.txt", 1);
+        let file = single_file_patch(&patch).expect("failed to parse patch");
+
+        let edited = OLD.replace("two\n", "TWO\n");
+
+        let strict = apply_patch(&edited, &file, 0);
+        assert_eq!(strict.failed, 1);
+        assert!(!strict.hunks[0].applied);
+
+        let fuzzy = apply_patch(&edited, &file, 1);
+        assert_eq!(fuzzy.failed, 0);
+        assert_eq!(fuzzy.hunks[0].fuzz, 1);
+        assert!(fuzzy.text.contains("TWO\nTHREE\n"));
+    }
+
+    #[test]
+    fn test_parse_git_patch_with_headers() {
+        let patch = "diff --git a/src/main.rs b/src/main.rs\nindex 83db48f..bf269f4 100644\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-fn main() {}\n+fn main() { run() }\ndiff --git a/new.txt b/new.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n";
+
+        let files = parse_patch(patch).expect("failed to parse patch");
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].new_path.as_deref(), Some("src/main.rs"));
+        assert_eq!(files[1].old_path, None);
+
+        let created = apply_patch("", &files[1], 0);
+        assert_eq!(created.text, "hello\nworld\n");
+    }
+
+    #[test]
+    fn test_truncated_hunk_is_an_error() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n a\n-b\n";
+
+        assert!(parse_patch(patch).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::constants::STREAM_DIFF_WINDOW_LINES;
+use crate::diff::{build_hunks, DiffOptions, DiffStats, Hunk, Normalizer};
+use crate::prelude::*;
+use crate::utils::Utils;
+use serde::Serialize;
+use similar::DiffOp;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[derive(Clone, Serialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "event",
+    content = "data"
+)]
+pub enum DiffStreamEvent {
+    Started { old_size: u64, new_size: u64 },
+    Hunk { hunk: Hunk },
+    Progress { bytes_read: u64, total: u64 },
+    Finished { identical: bool, stats: DiffStats },
+}
+
+/// Diffs two files without loading them whole. Both files are read in windows
+/// of [`STREAM_DIFF_WINDOW_LINES`] lines and every window is cut after the last
+/// run of unchanged lines, so memory stays bounded by the window size. Changes
+/// longer than a window are reported as they were seen.
+pub fn stream_diff<F>(
+    old_path: &str,
+    new_path: &str,
+    options: &DiffOptions,
+    on_event: F,
+) -> anyhow::Result<DiffStats, AppError>
+where
+    F: FnMut(DiffStreamEvent),
+{
+    stream_diff_windowed(
+        old_path,
+        new_path,
+        options,
+        STREAM_DIFF_WINDOW_LINES,
+        on_event,
+    )
+}
+
+fn stream_diff_windowed<F>(
+    old_path: &str,
+    new_path: &str,
+    options: &DiffOptions,
+    window: usize,
+    mut on_event: F,
+) -> anyhow::Result<DiffStats, AppError>
+where
+    F: FnMut(DiffStreamEvent),
+{
+    let normalizer = Normalizer::new(options)?;
+
+    let (mut old, old_size) = LineWindow::open(old_path)?;
+    let (mut new, new_size) = LineWindow::open(new_path)?;
+
+    on_event(DiffStreamEvent::Started { old_size, new_size });
+
+    let mut stats = DiffStats::default();
+    let mut identical = true;
+
+    loop {
+        old.fill(window)?;
+        new.fill(window)?;
+
+        if old.lines.is_empty() && new.lines.is_empty() {
+            break;
+        }
+
+        let (old_cut, new_cut) = {
+            let old_lines: Vec<&str> = old.lines.iter().map(String::as_str).collect();
+            let new_lines: Vec<&str> = new.lines.iter().map(String::as_str).collect();
+
+            let ops = normalizer.diff(&old_lines, &new_lines);
+
+            let (ops, old_cut, new_cut) = if old.eof && new.eof {
+                (ops, old_lines.len(), new_lines.len())
+            } else {
+                cut_at_anchor(ops, options.context_lines, old_lines.len(), new_lines.len())
+            };
+
+            let window_stats = normalizer.count_changes(&old_lines, &new_lines, &ops);
+            stats.insertions += window_stats.insertions;
+            stats.deletions += window_stats.deletions;
+
+            let hunks = build_hunks(
+                &old_lines,
+                &new_lines,
+                &ops,
+                &normalizer,
+                old.offset,
+                new.offset,
+            );
+
+            for hunk in hunks {
+                identical = false;
+                on_event(DiffStreamEvent::Hunk { hunk });
+            }
+
+            (old_cut, new_cut)
+        };
+
+        old.consume(old_cut);
+        new.consume(new_cut);
+
+        on_event(DiffStreamEvent::Progress {
+            bytes_read: old.bytes_read + new.bytes_read,
+            total: old_size + new_size,
+        });
+    }
+
+    on_event(DiffStreamEvent::Finished {
+        identical,
+        stats: stats.clone(),
+    });
+
+    Ok(stats)
+}
+
+/// Keeps the ops up to the last run of unchanged lines that is long enough to
+/// separate two hunks. The run is cut `context` lines from its end so that the
+/// next window starts with the leading context of its first hunk. Returns the
+/// kept ops and how many old and new lines they cover.
+fn cut_at_anchor(
+    mut ops: Vec<DiffOp>,
+    context: usize,
+    old_len: usize,
+    new_len: usize,
+) -> (Vec<DiffOp>, usize, usize) {
+    let min_len = (2 * context).max(1);
+
+    let anchor = ops.iter().enumerate().rev().find_map(|(i, op)| match *op {
+        DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } if len >= min_len => Some((i, old_index, new_index, len)),
+        _ => None,
+    });
+
+    match anchor {
+        Some((i, old_index, new_index, len)) => {
+            let keep = len - context;
+
+            ops.truncate(i);
+            ops.push(DiffOp::Equal {
+                old_index,
+                new_index,
+                len: keep,
+            });
+
+            (ops, old_index + keep, new_index + keep)
+        }
+        None => (ops, old_len, new_len),
+    }
+}
+
+struct LineWindow {
+    reader: BufReader<File>,
+    lines: Vec<String>,
+
+    /// Lines consumed before the start of the window.
+    offset: usize,
+    bytes_read: u64,
+    eof: bool,
+}
+
+impl LineWindow {
+    fn open(path: &str) -> anyhow::Result<(Self, u64), AppError> {
+        let path = Utils::normalise_path(path);
+
+        let file = File::open(&path)
+            .context(format!("Could not open: {}", path))
+            .map_err(|e| AppError::File(e.to_string()))?;
+
+        let size = file
+            .metadata()
+            .map_err(|e| AppError::File(e.to_string()))?
+            .len();
+
+        let window = Self {
+            reader: BufReader::new(file),
+            lines: vec![],
+            offset: 0,
+            bytes_read: 0,
+            eof: false,
+        };
+
+        Ok((window, size))
+    }
+
+    /// Reads lines, endings included, until the window holds `window` lines.
+    /// Invalid UTF-8 is replaced rather than failing the whole diff.
+    fn fill(&mut self, window: usize) -> anyhow::Result<(), AppError> {
+        let mut buf = vec![];
+
+        while !self.eof && self.lines.len() < window {
+            buf.clear();
+
+            let read = self
+                .reader
+                .read_until(b'\n', &mut buf)
+                .map_err(|e| AppError::File(e.to_string()))?;
+
+            if read == 0 {
+                self.eof = true;
+                break;
+            }
+
+            self.bytes_read += read as u64;
+            self.lines.push(String::from_utf8_lossy(&buf).into_owned());
+        }
+
+        Ok(())
+    }
+
+    fn consume(&mut self, count: usize) {
+        self.lines.drain(..count);
+        self.offset += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::compute_diff;
+    use std::fs;
+    use std::path::PathBuf;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("differ_{}.txt", Utils::get_random_id()));
+            fs::write(&path, contents).expect("failed to write file");
+
+            Self(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().expect("temp path should be valid utf-8")
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            fs::remove_file(&self.0).ok();
+        }
+    }
+
+    fn stream(old: &str, new: &str, window: usize) -> (Vec<DiffStreamEvent>, DiffStats) {
+        let old = TempFile::new(old);
+        let new = TempFile::new(new);
+
+        let mut events = vec![];
+        let stats = stream_diff_windowed(
+            old.path(),
+            new.path(),
+            &DiffOptions::default(),
+            window,
+            |event| events.push(event),
+        )
+        .expect("failed to stream diff");
+
+        (events, stats)
+    }
+
+    fn streamed_hunks(events: &[DiffStreamEvent]) -> serde_json::Value {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                DiffStreamEvent::Hunk { hunk } => serde_json::to_value(hunk).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_small_windows_match_the_full_diff() {
+        let old: String = (1..=200).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 5\n", "line five\n")
+            .replace("line 48\n", "")
+            .replace("line 120\n", "line 120\nextra\n")
+            .replace("line 200\n", "line 200");
+
+        let full = compute_diff(&old, &new, &DiffOptions::default()).expect("failed to diff");
+
+        for window in [16, 50, 1000] {
+            let (events, stats) = stream(&old, &new, window);
+
+            assert_eq!(stats, full.stats, "window {window}");
+            assert_eq!(
+                streamed_hunks(&events),
+                serde_json::to_value(&full.hunks).expect("failed to serialize"),
+                "window {window}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_events_bracket_the_hunks() {
+        let (events, _) = stream("a\nb\n", "a\nc\n", 8);
+
+        assert!(matches!(
+            events.first(),
+            Some(DiffStreamEvent::Started {
+                old_size: 4,
+                new_size: 4
+            })
+        ));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            DiffStreamEvent::Progress {
+                bytes_read: 8,
+                total: 8
+            }
+        )));
+        assert!(matches!(
+            events.last(),
+            Some(DiffStreamEvent::Finished {
+                identical: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let text: String = (1..=50).map(|i| format!("{i}\n")).collect();
+
+        let (events, stats) = stream(&text, &text, 10);
+
+        assert_eq!(stats, DiffStats::default());
+        assert!(matches!(
+            events.last(),
+            Some(DiffStreamEvent::Finished {
+                identical: true,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "json" | "jsonc" => Some(DataFormat::Json),
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            "toml" => Some(DataFormat::Toml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, text: &str) -> anyhow::Result<Value, AppError> {
+        match self {
+            DataFormat::Json => serde_json::from_str(&strip_jsonc(text))
+                .map_err(|e| AppError::JsonParse(format!("Invalid JSON: {}", e))),
+            DataFormat::Yaml => serde_yaml::from_str(text)
+                .map_err(|e| AppError::JsonParse(format!("Invalid YAML: {}", e))),
+            DataFormat::Toml => toml::from_str::<toml::Value>(text)
+                .map(toml_to_json)
+                .map_err(|e| AppError::JsonParse(format!("Invalid TOML: {}", e))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuralChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuralChange {
+    /// JSONPath-like address, e.g. `$.services[2].image`.
+    pub path: String,
+    pub kind: StructuralChangeKind,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+
+    /// Human readable form, e.g. `$.services[2].image: "a" -> "b"`.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuralDiff {
+    pub format: DataFormat,
+    pub identical: bool,
+    pub changes: Vec<StructuralChange>,
+}
+
+/// Parses both sides and compares them as trees. Object keys are matched by
+/// name so ordering and formatting never show up as changes, arrays are
+/// compared index by index.
+pub fn compute_structural_diff(
+    old: &str,
+    new: &str,
+    format: Option<DataFormat>,
+) -> anyhow::Result<StructuralDiff, AppError> {
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(old).ok_or_else(|| {
+            AppError::JsonParse("Could not detect a JSON, YAML or TOML document".to_string())
+        })?,
+    };
+
+    let old_value = format.parse(old)?;
+    let new_value = format.parse(new)?;
+
+    let mut changes = vec![];
+    compare_values("$".to_string(), &old_value, &new_value, &mut changes);
+
+    Ok(StructuralDiff {
+        format,
+        identical: changes.is_empty(),
+        changes,
+    })
+}
+
+/// YAML accepts almost any text as a plain scalar, so it is tried last and
+/// only accepted when it yields a mapping or a sequence.
+fn detect_format(text: &str) -> Option<DataFormat> {
+    if serde_json::from_str::<Value>(&strip_jsonc(text)).is_ok() {
+        return Some(DataFormat::Json);
+    }
+
+    if toml::from_str::<toml::Value>(text).is_ok() {
+        return Some(DataFormat::Toml);
+    }
+
+    match serde_yaml::from_str::<Value>(text) {
+        Ok(Value::Object(_)) | Ok(Value::Array(_)) => Some(DataFormat::Yaml),
+        _ => None,
+    }
+}
+
+/// Blanks out the comments and trailing commas JSONC allows (`tsconfig.json`,
+/// VS Code settings) so `serde_json` can parse it. Everything is replaced by
+/// spaces, keeping line and column numbers in parse errors right.
+fn strip_jsonc(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+
+        match (c, chars.get(i + 1)) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+                i += 1;
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(' ');
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2);
+
+                out.extend(
+                    chars[i..end]
+                        .iter()
+                        .map(|&c| if c == '\n' { c } else { ' ' }),
+                );
+                i = end;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    // With comments gone, a comma followed only by whitespace and a closing
+    // bracket is a trailing one.
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for i in 0..out.len() {
+        let c = out[i];
+
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            ',' => {
+                let next = out[i + 1..].iter().find(|c| !c.is_whitespace());
+                if matches!(next, Some('}') | Some(']')) {
+                    out[i] = ' ';
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out.into_iter().collect()
+}
+
+fn compare_values(path: String, old: &Value, new: &Value, changes: &mut Vec<StructuralChange>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+
+            for key in keys {
+                let child = format!("{}{}", path, key_segment(key));
+
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(o), Some(n)) => compare_values(child, o, n, changes),
+                    (Some(o), None) => changes.push(change(child, Some(o), None)),
+                    (None, Some(n)) => changes.push(change(child, None, Some(n))),
+                    (None, None) => (),
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for i in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{}[{}]", path, i);
+
+                match (old_items.get(i), new_items.get(i)) {
+                    (Some(o), Some(n)) => compare_values(child, o, n, changes),
+                    (Some(o), None) => changes.push(change(child, Some(o), None)),
+                    (None, Some(n)) => changes.push(change(child, None, Some(n))),
+                    (None, None) => (),
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                changes.push(change(path, Some(old), Some(new)));
+            }
+        }
+    }
+}
+
+fn change(path: String, old: Option<&Value>, new: Option<&Value>) -> StructuralChange {
+    let (kind, summary) = match (old, new) {
+        (Some(o), Some(n)) => (
+            StructuralChangeKind::Changed,
+            format!("{}: {} -> {}", path, o, n),
+        ),
+        (Some(o), None) => (
+            StructuralChangeKind::Removed,
+            format!("{}: removed {}", path, o),
+        ),
+        _ => (
+            StructuralChangeKind::Added,
+            format!("{}: added {}", path, new.unwrap_or(&Value::Null)),
+        ),
+    };
+
+    StructuralChange {
+        path,
+        kind,
+        old: old.cloned(),
+        new: new.cloned(),
+        summary,
+    }
+}
+
+/// `.key` for identifier-like keys, `["some key"]` otherwise.
+fn key_segment(key: &str) -> String {
+    let is_identifier = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if is_identifier {
+        format!(".{}", key)
+    } else {
+        format!("[{}]", Value::String(key.to_string()))
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summaries(diff: &StructuralDiff) -> Vec<&str> {
+        diff.changes.iter().map(|c| c.summary.as_str()).collect()
+    }
+
+    #[test]
+    fn test_json_key_order_and_formatting_are_ignored() {
+        let old = r#"{"a": 1, "b": [1, 2]}"#;
+        let new = "{\n  \"b\": [1, 2],\n  \"a\": 1\n}";
+
+        let diff = compute_structural_diff(old, new, None).expect("failed to diff json");
+
+        assert_eq!(diff.format, DataFormat::Json);
+        assert!(diff.identical);
+    }
+
+    #[test]
+    fn test_jsonc_comments_and_trailing_commas() {
+        let old = r#"{
+  // Compiler options
+  "compilerOptions": {
+    "strict": true, /* keep */
+    "paths": { "@/*": ["./src/*"], },
+  },
+}"#;
+        let new = r#"{"compilerOptions": {"strict": false, "paths": {"@/*": ["./src/*"]}}}"#;
+
+        let format = DataFormat::from_path("tsconfig.jsonc");
+        let diff = compute_structural_diff(old, new, format).expect("failed to diff jsonc");
+
+        assert_eq!(
+            summaries(&diff),
+            vec!["$.compilerOptions.strict: true -> false"]
+        );
+
+        // Comment markers and commas inside strings are left alone.
+        assert_eq!(
+            strip_jsonc(r#"{"url": "http://a/*b*/", "s": ",]"}"#),
+            r#"{"url": "http://a/*b*/", "s": ",]"}"#
+        );
+    }
+
+    #[test]
+    fn test_yaml_paths() {
+        let old = "services:\n  - name: web\n    image: a\n  - name: db\n    image: postgres\n";
+        let new = "services:\n  - name: web\n    image: b\n  - name: db\n    image: postgres\n    ports: [5432]\n";
+
+        let diff =
+            compute_structural_diff(old, new, Some(DataFormat::Yaml)).expect("failed to diff yaml");
+
+        assert_eq!(
+            summaries(&diff),
+            vec![
+                r#"$.services[0].image: "a" -> "b""#,
+                "$.services[1].ports: added [5432]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toml_changes() {
+        let old = "[package]\nname = \"differ\"\nversion = \"0.1.0\"\n\n[features]\ndefault = []\n";
+        let new = "[package]\nversion = \"0.2.0\"\nname = \"differ\"\n";
+
+        let diff = compute_structural_diff(old, new, None).expect("failed to diff toml");
+
+        assert_eq!(diff.format, DataFormat::Toml);
+        assert_eq!(
+            summaries(&diff),
+            vec![
+                "$.features: removed {\"default\":[]}",
+                r#"$.package.version: "0.1.0" -> "0.2.0""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keys_that_need_quoting() {
+        let diff = compute_structural_diff(r#"{"a b": 1}"#, r#"{"a b": 2}"#, None)
+            .expect("failed to diff json");
+
+        assert_eq!(diff.changes[0].path, r#"$["a b"]"#);
+
+        let diff = compute_structural_diff(r#"{"foo-bar": 1}"#, r#"{"foo-bar": 2}"#, None)
+            .expect("failed to diff json");
+
+        assert_eq!(diff.changes[0].path, r#"$["foo-bar"]"#);
+    }
+
+    #[test]
+    fn test_invalid_document_is_an_error() {
+        let result = compute_structural_diff("{", "{}", Some(DataFormat::Json));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(DataFormat::from_path("a/b.yml"), Some(DataFormat::Yaml));
+        assert_eq!(DataFormat::from_path("Cargo.toml"), Some(DataFormat::Toml));
+        assert_eq!(DataFormat::from_path("notes.txt"), None);
+    }
+}
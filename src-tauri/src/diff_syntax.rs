@@ -0,0 +1,760 @@
+use crate::diff::{compute_diff, diff_lines, DiffOptions, DiffResult, Hunk};
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use similar::DiffTag;
+use std::collections::HashMap;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntaxLanguage {
+    Rust,
+    #[serde(rename = "typescript")]
+    TypeScript,
+    /// TSX, also used for plain JavaScript.
+    Tsx,
+    Python,
+    Json,
+}
+
+impl SyntaxLanguage {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "rs" => Some(SyntaxLanguage::Rust),
+            "ts" | "mts" | "cts" => Some(SyntaxLanguage::TypeScript),
+            "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some(SyntaxLanguage::Tsx),
+            "py" | "pyi" => Some(SyntaxLanguage::Python),
+            "json" => Some(SyntaxLanguage::Json),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn grammar(self) -> Language {
+        match self {
+            SyntaxLanguage::Rust => tree_sitter_rust::LANGUAGE.into(),
+            SyntaxLanguage::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            SyntaxLanguage::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            SyntaxLanguage::Python => tree_sitter_python::LANGUAGE.into(),
+            SyntaxLanguage::Json => tree_sitter_json::LANGUAGE.into(),
+        }
+    }
+
+    /// Highlight queries, TypeScript builds on the JavaScript ones the same way
+    /// the upstream grammar does.
+    pub(crate) fn highlights_query(self) -> String {
+        match self {
+            SyntaxLanguage::Rust => tree_sitter_rust::HIGHLIGHTS_QUERY.to_string(),
+            SyntaxLanguage::TypeScript => [
+                tree_sitter_javascript::HIGHLIGHT_QUERY,
+                tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            ]
+            .join("\n"),
+            SyntaxLanguage::Tsx => [
+                tree_sitter_javascript::HIGHLIGHT_QUERY,
+                tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+                tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            ]
+            .join("\n"),
+            SyntaxLanguage::Python => tree_sitter_python::HIGHLIGHTS_QUERY.to_string(),
+            SyntaxLanguage::Json => tree_sitter_json::HIGHLIGHTS_QUERY.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntaxChangeKind {
+    Added,
+    Removed,
+    Modified,
+    Moved,
+    Renamed,
+    /// Only whitespace or layout changed.
+    Reformatted,
+}
+
+/// 1-based, inclusive line range of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntaxChange {
+    pub kind: SyntaxChangeKind,
+
+    /// Grammar node kind, e.g. `function_item`.
+    pub node_kind: String,
+
+    /// Name qualified by its containers, e.g. `Parser.parse`.
+    pub name: Option<String>,
+
+    /// Name before a rename.
+    pub old_name: Option<String>,
+    pub old_range: Option<LineRange>,
+    pub new_range: Option<LineRange>,
+
+    /// Line diff of the node, only set for modified nodes.
+    pub diff: Option<DiffResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntaxDiff {
+    pub language: SyntaxLanguage,
+    pub identical: bool,
+    pub changes: Vec<SyntaxChange>,
+}
+
+/// Compares the declarations of two sources instead of their lines. Nodes are
+/// matched by their tokens, so a node that only moved, was renamed or was
+/// reformatted is reported as such instead of as removed and added lines.
+/// Returns `None` when either side does not parse, callers then fall back to
+/// a line diff.
+pub fn compute_syntax_diff(
+    old: &str,
+    new: &str,
+    language: SyntaxLanguage,
+    options: &DiffOptions,
+) -> anyhow::Result<Option<SyntaxDiff>, AppError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|e| AppError::Diff(e.to_string()))?;
+
+    let (Some(old_tree), Some(new_tree)) = (parser.parse(old, None), parser.parse(new, None))
+    else {
+        return Ok(None);
+    };
+
+    if old_tree.root_node().has_error() || new_tree.root_node().has_error() {
+        return Ok(None);
+    }
+
+    let mut changes = vec![];
+
+    diff_children(
+        &Source::new(old, container(old_tree.root_node())),
+        &Source::new(new, container(new_tree.root_node())),
+        None,
+        options,
+        &mut changes,
+    )?;
+
+    changes.sort_by_key(|change| {
+        change
+            .new_range
+            .or(change.old_range)
+            .map(|range| range.start)
+    });
+
+    Ok(Some(SyntaxDiff {
+        language,
+        identical: changes.is_empty(),
+        changes,
+    }))
+}
+
+struct Source<'a> {
+    text: &'a str,
+    parent: Node<'a>,
+}
+
+impl<'a> Source<'a> {
+    fn new(text: &'a str, parent: Node<'a>) -> Self {
+        Self { text, parent }
+    }
+
+    fn with_parent(&self, parent: Node<'a>) -> Self {
+        Self::new(self.text, parent)
+    }
+}
+
+/// A direct child of the node being compared.
+struct Unit<'a> {
+    node: Node<'a>,
+    name: Option<String>,
+    tokens: Vec<&'a str>,
+
+    /// Node kind and tokens, equal for nodes that differ in layout only.
+    key: String,
+}
+
+impl<'a> Unit<'a> {
+    fn new(node: Node<'a>, text: &'a str) -> Self {
+        let mut tokens = vec![];
+        collect_tokens(node, text, &mut tokens);
+
+        Self {
+            node,
+            name: node_name(node, text),
+            key: format!("{} {}", node.kind(), tokens.join(" ")),
+            tokens,
+        }
+    }
+
+    /// Key with the own name blanked, equal for nodes that were only renamed.
+    fn renamed_key(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|token| match &self.name {
+                Some(name) if name == token => "\u{0}",
+                _ => token,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn text(&self, source: &'a str) -> &'a str {
+        &source[self.node.byte_range()]
+    }
+
+    /// Node text from the start of its first line, so indentation diffs too.
+    fn lines(&self, source: &'a str) -> &'a str {
+        let start = self.node.start_byte() - self.node.start_position().column;
+
+        &source[start..self.node.end_byte()]
+    }
+
+    fn range(&self) -> LineRange {
+        LineRange {
+            start: self.node.start_position().row + 1,
+            end: self.node.end_position().row + 1,
+        }
+    }
+}
+
+fn diff_children(
+    old: &Source,
+    new: &Source,
+    prefix: Option<&str>,
+    options: &DiffOptions,
+    changes: &mut Vec<SyntaxChange>,
+) -> anyhow::Result<(), AppError> {
+    let old_units = units(old);
+    let new_units = units(new);
+
+    let old_keys: Vec<&str> = old_units.iter().map(|u| u.key.as_str()).collect();
+    let new_keys: Vec<&str> = new_units.iter().map(|u| u.key.as_str()).collect();
+
+    let mut removed = vec![];
+    let mut added = vec![];
+
+    for op in diff_lines(&old_keys, &new_keys) {
+        match op.tag() {
+            DiffTag::Equal => {
+                for (i, j) in op.old_range().zip(op.new_range()) {
+                    let (o, n) = (&old_units[i], &new_units[j]);
+
+                    if o.text(old.text) != n.text(new.text) {
+                        changes.push(change(SyntaxChangeKind::Reformatted, prefix, Some(o), n));
+                    }
+                }
+            }
+            _ => {
+                removed.extend(op.old_range());
+                added.extend(op.new_range());
+            }
+        }
+    }
+
+    // Moved: the same tokens on both sides, just somewhere else.
+    let mut by_key: HashMap<&str, Vec<usize>> = HashMap::new();
+    for &j in added.iter().rev() {
+        by_key.entry(new_keys[j]).or_default().push(j);
+    }
+
+    let mut unmatched = vec![];
+
+    for i in removed {
+        match by_key.get_mut(old_keys[i]).and_then(|js| js.pop()) {
+            Some(j) => {
+                changes.push(change(
+                    SyntaxChangeKind::Moved,
+                    prefix,
+                    Some(&old_units[i]),
+                    &new_units[j],
+                ));
+                added.retain(|&a| a != j);
+            }
+            None => unmatched.push(i),
+        }
+    }
+
+    for i in unmatched {
+        let o = &old_units[i];
+
+        let same_name = added.iter().position(|&j| {
+            let n = &new_units[j];
+            n.node.kind() == o.node.kind() && n.name.is_some() && n.name == o.name
+        });
+
+        if let Some(position) = same_name {
+            let n = &new_units[added.remove(position)];
+
+            match (container_body(o.node), container_body(n.node)) {
+                (Some(old_body), Some(new_body)) => {
+                    // The body is compared member by member, the header
+                    // around it (`extends`, generics, bases) on its own.
+                    if outside_body(old.text, o.node, old_body)
+                        != outside_body(new.text, n.node, new_body)
+                    {
+                        let diff = diff_outside_body(
+                            (old.text, o.node, old_body),
+                            (new.text, n.node, new_body),
+                            options,
+                        )?;
+
+                        if !diff.identical {
+                            let mut modified =
+                                change(SyntaxChangeKind::Modified, prefix, Some(o), n);
+                            modified.diff = Some(diff);
+                            changes.push(modified);
+                        }
+                    }
+
+                    let prefix = qualify(prefix, n.name.as_deref());
+
+                    diff_children(
+                        &old.with_parent(old_body),
+                        &new.with_parent(new_body),
+                        prefix.as_deref(),
+                        options,
+                        changes,
+                    )?;
+                }
+                _ => {
+                    let mut diff = compute_diff(o.lines(old.text), n.lines(new.text), options)?;
+                    shift_lines(&mut diff, o.range().start - 1, n.range().start - 1);
+
+                    let mut modified = change(SyntaxChangeKind::Modified, prefix, Some(o), n);
+                    modified.diff = Some(diff);
+                    changes.push(modified);
+                }
+            }
+
+            continue;
+        }
+
+        let renamed = added.iter().position(|&j| {
+            let n = &new_units[j];
+            n.node.kind() == o.node.kind()
+                && n.name.is_some()
+                && o.name.is_some()
+                && n.renamed_key() == o.renamed_key()
+        });
+
+        if let Some(position) = renamed {
+            let n = &new_units[added.remove(position)];
+
+            let mut renamed = change(SyntaxChangeKind::Renamed, prefix, Some(o), n);
+            renamed.old_name = qualify(prefix, o.name.as_deref());
+            changes.push(renamed);
+
+            continue;
+        }
+
+        changes.push(SyntaxChange {
+            kind: SyntaxChangeKind::Removed,
+            node_kind: o.node.kind().to_string(),
+            name: qualify(prefix, o.name.as_deref()),
+            old_name: None,
+            old_range: Some(o.range()),
+            new_range: None,
+            diff: None,
+        });
+    }
+
+    for j in added {
+        changes.push(change(SyntaxChangeKind::Added, prefix, None, &new_units[j]));
+    }
+
+    Ok(())
+}
+
+fn units<'a>(source: &Source<'a>) -> Vec<Unit<'a>> {
+    let mut cursor = source.parent.walk();
+
+    source
+        .parent
+        .named_children(&mut cursor)
+        .map(|node| Unit::new(node, source.text))
+        .collect()
+}
+
+fn change(
+    kind: SyntaxChangeKind,
+    prefix: Option<&str>,
+    old: Option<&Unit>,
+    new: &Unit,
+) -> SyntaxChange {
+    SyntaxChange {
+        kind,
+        node_kind: new.node.kind().to_string(),
+        name: qualify(prefix, new.name.as_deref()),
+        old_name: None,
+        old_range: old.map(Unit::range),
+        new_range: Some(new.range()),
+        diff: None,
+    }
+}
+
+fn qualify(prefix: Option<&str>, name: Option<&str>) -> Option<String> {
+    match (prefix, name) {
+        (Some(prefix), Some(name)) => Some(format!("{}.{}", prefix, name)),
+        (None, Some(name)) => Some(name.to_string()),
+        (Some(prefix), None) => Some(prefix.to_string()),
+        (None, None) => None,
+    }
+}
+
+fn collect_tokens<'a>(node: Node<'a>, text: &'a str, tokens: &mut Vec<&'a str>) {
+    if node.child_count() == 0 {
+        tokens.push(&text[node.byte_range()]);
+        return;
+    }
+
+    let mut cursor = node.walk();
+
+    for child in node.children(&mut cursor) {
+        collect_tokens(child, text, tokens);
+    }
+}
+
+/// JSON documents are compared from their top level object or array.
+fn container(root: Node) -> Node {
+    let mut cursor = root.walk();
+    let mut children = root.named_children(&mut cursor);
+
+    match (children.next(), children.next()) {
+        (Some(child), None) if matches!(child.kind(), "object" | "array") => child,
+        _ => root,
+    }
+}
+
+/// Body of nodes whose children are worth matching one by one.
+fn container_body(node: Node) -> Option<Node> {
+    let body = match node.kind() {
+        "export_statement" => node
+            .child_by_field_name("declaration")
+            .and_then(container_body),
+        "decorated_definition" => node
+            .child_by_field_name("definition")
+            .and_then(container_body),
+        "impl_item"
+        | "trait_item"
+        | "mod_item"
+        | "class_declaration"
+        | "abstract_class_declaration"
+        | "interface_declaration"
+        | "class_definition" => node.child_by_field_name("body"),
+        "pair" => node
+            .child_by_field_name("value")
+            .filter(|value| matches!(value.kind(), "object" | "array")),
+        _ => None,
+    };
+
+    body.filter(|body| body.named_child_count() > 0)
+}
+
+fn node_name(node: Node, text: &str) -> Option<String> {
+    let field_text = |field: &str| {
+        node.child_by_field_name(field)
+            .map(|child| text[child.byte_range()].to_string())
+    };
+
+    match node.kind() {
+        "export_statement" => node
+            .child_by_field_name("declaration")
+            .and_then(|declaration| node_name(declaration, text)),
+        "decorated_definition" => node
+            .child_by_field_name("definition")
+            .and_then(|definition| node_name(definition, text)),
+        "impl_item" => {
+            let ty = field_text("type")?;
+
+            match field_text("trait") {
+                Some(tr) => Some(format!("{} for {}", tr, ty)),
+                None => Some(ty),
+            }
+        }
+        "lexical_declaration" | "variable_declaration" => {
+            let mut cursor = node.walk();
+            let declarator = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "variable_declarator")?;
+
+            node_name(declarator, text)
+        }
+        "pair" => field_text("key").map(|key| key.trim_matches('"').to_string()),
+        _ => field_text("name"),
+    }
+}
+
+/// Line diff of the lines of a node outside its body: the header up to the
+/// line the body opens on, or up to the body when it starts its own line as
+/// in Python, and whatever follows the body.
+fn diff_outside_body(
+    old: (&str, Node, Node),
+    new: (&str, Node, Node),
+    options: &DiffOptions,
+) -> anyhow::Result<DiffResult, AppError> {
+    let (_, old_node, old_body) = old;
+    let (_, new_node, new_body) = new;
+
+    let mut diff = compute_diff(header_lines(old), header_lines(new), options)?;
+    shift_lines(
+        &mut diff,
+        old_node.start_position().row,
+        new_node.start_position().row,
+    );
+
+    let mut trailer_diff = compute_diff(trailer_lines(old), trailer_lines(new), options)?;
+    shift_lines(
+        &mut trailer_diff,
+        old_body.end_position().row,
+        new_body.end_position().row,
+    );
+
+    diff.identical &= trailer_diff.identical;
+    diff.stats.insertions += trailer_diff.stats.insertions;
+    diff.stats.deletions += trailer_diff.stats.deletions;
+    diff.hunks.extend(trailer_diff.hunks);
+    diff.moves.extend(trailer_diff.moves);
+
+    Ok(diff)
+}
+
+/// Tokens of a node outside its body, whitespace aside.
+fn outside_body<'a>(text: &'a str, node: Node, body: Node) -> Vec<&'a str> {
+    let before = &text[node.start_byte()..body.start_byte()];
+    let after = &text[body.end_byte()..node.end_byte()];
+
+    before
+        .split_whitespace()
+        .chain(["\u{0}"])
+        .chain(after.split_whitespace())
+        .collect()
+}
+
+fn header_lines<'a>((text, node, body): (&'a str, Node, Node)) -> &'a str {
+    let body_line = line_start(text, body.start_byte());
+
+    let end = if text[body_line..body.start_byte()].trim().is_empty() {
+        body_line
+    } else {
+        text[body.start_byte()..]
+            .find('\n')
+            .map_or(text.len(), |i| body.start_byte() + i + 1)
+    };
+
+    &text[line_start(text, node.start_byte())..end]
+}
+
+fn trailer_lines<'a>((text, node, body): (&'a str, Node, Node)) -> &'a str {
+    &text[line_start(text, body.end_byte())..node.end_byte()]
+}
+
+fn line_start(text: &str, byte: usize) -> usize {
+    text[..byte].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Line numbers of a node diff are relative to the node, move them to file lines.
+fn shift_lines(diff: &mut DiffResult, old_offset: usize, new_offset: usize) {
+    shift_hunks(&mut diff.hunks, old_offset, new_offset);
+
+    for moved in diff.moves.iter_mut() {
+        moved.old_start += old_offset;
+        moved.new_start += new_offset;
+
+        shift_hunks(&mut moved.edits, old_offset, new_offset);
+    }
+}
+
+fn shift_hunks(hunks: &mut [Hunk], old_offset: usize, new_offset: usize) {
+    for hunk in hunks.iter_mut() {
+        hunk.old_start += old_offset;
+        hunk.new_start += new_offset;
+
+        for line in hunk.lines.iter_mut() {
+            line.old_line = line.old_line.map(|n| n + old_offset);
+            line.new_line = line.new_line.map(|n| n + new_offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_diff(old: &str, new: &str, language: SyntaxLanguage) -> SyntaxDiff {
+        compute_syntax_diff(old, new, language, &DiffOptions::default())
+            .expect("failed to diff")
+            .expect("sources should parse")
+    }
+
+    fn kinds(diff: &SyntaxDiff) -> Vec<(SyntaxChangeKind, Option<&str>)> {
+        diff.changes
+            .iter()
+            .map(|c| (c.kind, c.name.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_moves_renames_and_reformats() {
+        let old = "fn a() -> u8 { 1 }\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}\n";
+        let new = "fn a() -> u8 {\n    1\n}\nfn bee() {}\nfn d() {}\nfn e() {}\nfn c() {}\n";
+
+        let diff = syntax_diff(old, new, SyntaxLanguage::Rust);
+
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (SyntaxChangeKind::Reformatted, Some("a")),
+                (SyntaxChangeKind::Renamed, Some("bee")),
+                (SyntaxChangeKind::Moved, Some("c")),
+            ]
+        );
+        assert_eq!(diff.changes[1].old_name.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_rust_impl_members_are_matched_by_name() {
+        let old = "struct S;\n\nimpl S {\n    fn one(&self) -> u8 {\n        1\n    }\n\n    fn two(&self) {}\n}\n";
+        let new = "struct S;\n\nimpl S {\n    fn one(&self) -> u8 {\n        2\n    }\n\n    fn three(&self, x: u8) {}\n}\n";
+
+        let diff = syntax_diff(old, new, SyntaxLanguage::Rust);
+
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (SyntaxChangeKind::Modified, Some("S.one")),
+                (SyntaxChangeKind::Removed, Some("S.two")),
+                (SyntaxChangeKind::Added, Some("S.three")),
+            ]
+        );
+
+        let modified = diff.changes[0]
+            .diff
+            .as_ref()
+            .expect("modified node should have a diff");
+        let changed: Vec<_> = modified.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.old_line == Some(5) || l.new_line == Some(5))
+            .map(|l| l.content.trim())
+            .collect();
+        assert_eq!(changed, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_header_changes_around_a_body() {
+        let typescript = syntax_diff(
+            "class A extends B {\n  x = 1;\n}\n",
+            "class A extends C {\n  x = 1;\n}\n",
+            SyntaxLanguage::TypeScript,
+        );
+        assert!(!typescript.identical);
+        assert_eq!(
+            kinds(&typescript),
+            vec![(SyntaxChangeKind::Modified, Some("A"))]
+        );
+
+        let header = typescript.changes[0]
+            .diff
+            .as_ref()
+            .expect("modified node should have a diff");
+        assert_eq!(header.stats.insertions, 1);
+        assert_eq!(header.stats.deletions, 1);
+        assert!(header.hunks[0]
+            .lines
+            .iter()
+            .all(|l| l.old_line.unwrap_or(1) == 1 && l.new_line.unwrap_or(1) == 1));
+
+        let rust = syntax_diff(
+            "struct S<T>(T);\n\nimpl<T> S<T> {\n    fn a(&self) {}\n}\n",
+            "struct S<T>(T);\n\nimpl<T> S<T>\nwhere\n    T: Clone,\n{\n    fn a(&self) {}\n}\n",
+            SyntaxLanguage::Rust,
+        );
+        assert_eq!(
+            kinds(&rust),
+            vec![(SyntaxChangeKind::Modified, Some("S<T>"))]
+        );
+
+        let python = syntax_diff(
+            "class A(B):\n    x = 1\n",
+            "class A(C):\n    x = 1\n",
+            SyntaxLanguage::Python,
+        );
+        assert_eq!(
+            kinds(&python),
+            vec![(SyntaxChangeKind::Modified, Some("A"))]
+        );
+
+        // Only the header line, not the body that starts its own line.
+        let header = python.changes[0].diff.as_ref().expect("missing diff");
+        assert_eq!(header.stats.insertions, 1);
+        assert_eq!(header.stats.deletions, 1);
+
+        let trait_changed = syntax_diff(
+            "impl Foo for X {\n    fn a() {}\n}\n",
+            "impl Bar for X {\n    fn a() {}\n}\n",
+            SyntaxLanguage::Rust,
+        );
+        assert!(!trait_changed.identical);
+    }
+
+    #[test]
+    fn test_json_keys() {
+        let old = r#"{"name": "differ", "scripts": {"dev": "vite", "build": "tsc"}}"#;
+        let new = "{\n  \"scripts\": {\"build\": \"tsc\", \"dev\": \"vite --host\"},\n  \"name\": \"differ\"\n}";
+
+        let diff = syntax_diff(old, new, SyntaxLanguage::Json);
+
+        assert_eq!(
+            kinds(&diff),
+            vec![(SyntaxChangeKind::Modified, Some("scripts.dev"))]
+        );
+    }
+
+    #[test]
+    fn test_python_and_typescript() {
+        let python = syntax_diff(
+            "def a():\n    return 1\n",
+            "def a():\n    return 1\n\ndef b():\n    pass\n",
+            SyntaxLanguage::Python,
+        );
+        assert_eq!(kinds(&python), vec![(SyntaxChangeKind::Added, Some("b"))]);
+
+        let typescript = syntax_diff(
+            "export function a() { return 1; }\n",
+            "export function a() {\n  return 1;\n}\n",
+            SyntaxLanguage::TypeScript,
+        );
+        assert_eq!(
+            kinds(&typescript),
+            vec![(SyntaxChangeKind::Reformatted, Some("a"))]
+        );
+    }
+
+    #[test]
+    fn test_invalid_source_falls_back() {
+        let result = compute_syntax_diff(
+            "fn a() {",
+            "fn a() {}",
+            SyntaxLanguage::Rust,
+            &DiffOptions::default(),
+        )
+        .expect("failed to diff");
+
+        assert!(result.is_none());
+        assert_eq!(
+            SyntaxLanguage::from_path("main.py"),
+            Some(SyntaxLanguage::Python)
+        );
+        assert_eq!(SyntaxLanguage::from_path("notes.md"), None);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::constants::DIFF_WATCH_DEBOUNCE_MS;
+use crate::diff::{compute_diff, DiffOptions, DiffResult};
+use crate::ipc_utils::{read_file_contents, ReadFileOptions};
+use crate::prelude::*;
+use crate::utils::Utils;
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, EventTarget, Listener};
+
+pub const DIFF_WATCH_EVENT: &str = "diff_watch";
+pub const DIFF_WATCH_CLOSE_EVENT: &str = "cmd_watch_diff_close";
+
+#[derive(Clone, Serialize)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "event",
+    content = "data"
+)]
+pub enum DiffWatchEvent {
+    Updated {
+        watch_id: String,
+        result: DiffResult,
+    },
+    Failed {
+        watch_id: String,
+        error: String,
+    },
+}
+
+/// Watches both files of a diff session and emits a fresh diff whenever either
+/// changes. Runs until `app_close`, or until [`DIFF_WATCH_CLOSE_EVENT`] is
+/// emitted with this `watch_id` as payload.
+pub fn spawn_diff_watch(
+    app: AppHandle,
+    watch_id: String,
+    old_path: String,
+    new_path: String,
+    options: DiffOptions,
+) -> anyhow::Result<(), AppError> {
+    let old_path = canonical_path(&old_path)?;
+    let new_path = canonical_path(&new_path)?;
+
+    let (change_tx, mut change_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    let (cancel_tx, mut cancel_rx) = tokio::sync::watch::channel(false);
+
+    let watched: HashSet<PathBuf> = [old_path.clone(), new_path.clone()].into();
+
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DIFF_WATCH_DEBOUNCE_MS),
+        move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+
+            if events.iter().any(|event| watched.contains(&event.path)) {
+                change_tx.send(()).ok();
+            }
+        },
+    )
+    .map_err(|e| AppError::File(e.to_string()))?;
+
+    // Watch the parent directories, editors often replace a file on save which
+    // would silently end a watch on the file itself.
+    let parents: HashSet<PathBuf> = [&old_path, &new_path]
+        .iter()
+        .filter_map(|path| path.parent().map(PathBuf::from))
+        .collect();
+
+    for parent in parents {
+        debouncer
+            .watcher()
+            .watch(&parent, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::File(e.to_string()))?;
+    }
+
+    let cancel_tx2 = cancel_tx.clone();
+
+    let on_app_close: u32 = app.listen_any("app_close", move |_| {
+        cancel_tx.send(true).ok();
+    });
+
+    let closed_id = watch_id.clone();
+
+    let on_watch_close: u32 = app.listen_any(DIFF_WATCH_CLOSE_EVENT, move |event| {
+        if serde_json::from_str::<String>(event.payload()).is_ok_and(|id| id == closed_id) {
+            cancel_tx2.send(true).ok();
+        }
+    });
+
+    tauri::async_runtime::spawn(async move {
+        // Moved in so the watcher lives as long as the task.
+        let _debouncer = debouncer;
+
+        emit_diff(&app, &watch_id, &old_path, &new_path, &options).await;
+
+        loop {
+            tokio::select! {
+                _ = cancel_rx.changed() => break,
+                change = change_rx.recv() => {
+                    if change.is_none() {
+                        break;
+                    }
+
+                    emit_diff(&app, &watch_id, &old_path, &new_path, &options).await;
+                }
+            }
+        }
+
+        app.unlisten(on_app_close);
+        app.unlisten(on_watch_close);
+
+        println!("Diff watch '{}' has been terminated.", watch_id);
+    });
+
+    Ok(())
+}
+
+async fn emit_diff(
+    app: &AppHandle,
+    watch_id: &str,
+    old_path: &Path,
+    new_path: &Path,
+    options: &DiffOptions,
+) {
+    let old_path = old_path.to_string_lossy().to_string();
+    let new_path = new_path.to_string_lossy().to_string();
+    let options = options.clone();
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let old = read_file_contents(&old_path, &ReadFileOptions::default())?;
+        let new = read_file_contents(&new_path, &ReadFileOptions::default())?;
+
+        compute_diff(&old.contents, &new.contents, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))
+    .and_then(|result| result);
+
+    let event = match result {
+        Ok(result) => DiffWatchEvent::Updated {
+            watch_id: watch_id.to_string(),
+            result,
+        },
+        Err(err) => DiffWatchEvent::Failed {
+            watch_id: watch_id.to_string(),
+            error: err.to_string(),
+        },
+    };
+
+    app.emit_to(EventTarget::any(), DIFF_WATCH_EVENT, event)
+        .ok();
+}
+
+fn canonical_path(path: &str) -> anyhow::Result<PathBuf, AppError> {
+    std::fs::canonicalize(Utils::normalise_path(path))
+        .context(format!("Could not watch: {}", path))
+        .map_err(|e| AppError::File(e.to_string()))
+}
@@ -1,4 +1,5 @@
 use axum::response::{IntoResponse, Response};
+use axum::Json;
 use reqwest::StatusCode;
 use serde::Serialize;
 use thiserror::Error;
@@ -38,6 +39,21 @@ pub enum AppError {
     #[error("AI chat error: {0}")]
     UnsupportedProvider(String),
 
+    #[error("Unsupported model: {0}")]
+    UnsupportedModel(String),
+
+    #[error("Diff error: {0}")]
+    Diff(String),
+
+    #[error("Merge error: {0}")]
+    Merge(String),
+
+    #[error("Patch error: {0}")]
+    Patch(String),
+
+    #[error("Git error: {0}")]
+    Git(String),
+
     #[error("Missing apikey: {0}")]
     MissingApiKey(String),
 
@@ -78,16 +94,110 @@ impl From<aisdk::Error> for AppError {
     }
 }
 
+impl AppError {
+    /// Machine readable code sent along with errors from the chat api, so
+    /// the UI can react to eg a missing api key.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Runtime(_) => "runtime",
+            AppError::Subscription(_) => "subscription",
+            AppError::DbConnection => "db_connection",
+            AppError::DbOperation => "db_operation",
+            AppError::NotFound => "not_found",
+            AppError::File(_) => "file",
+            AppError::JsonParse(_) => "invalid_json",
+            AppError::Convex(_) => "convex",
+            AppError::Request(_) => "invalid_request",
+            AppError::AIChat(_) => "upstream_error",
+            AppError::UnsupportedProvider(_) => "unsupported_provider",
+            AppError::UnsupportedModel(_) => "unsupported_model",
+            AppError::Diff(_) => "diff",
+            AppError::Merge(_) => "merge",
+            AppError::Patch(_) => "patch",
+            AppError::Git(_) => "git",
+            AppError::MissingApiKey(_) => "missing_api_key",
+            AppError::Unknown => "unknown",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::JsonParse(_)
+            | AppError::Request(_)
+            | AppError::UnsupportedProvider(_)
+            | AppError::UnsupportedModel(_)
+            | AppError::Diff(_)
+            | AppError::Merge(_)
+            | AppError::Patch(_) => StatusCode::BAD_REQUEST,
+            AppError::MissingApiKey(_) => StatusCode::UNAUTHORIZED,
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::AIChat(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Body of every error returned by the chat api.
+#[derive(Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let body = match self {
+        let message = match &self {
             AppError::AIChat(msg) => format!("Ai chat error: {}", msg),
             AppError::MissingApiKey(msg) => format!("Missing api key for provider: {}", msg),
             AppError::UnsupportedProvider(msg) => format!("Unsupported provider: {}", msg),
+            AppError::UnsupportedModel(msg) => format!("Unsupported model: {}", msg),
+            AppError::JsonParse(_)
+            | AppError::Request(_)
+            | AppError::NotFound
+            | AppError::Diff(_)
+            | AppError::Merge(_)
+            | AppError::Patch(_) => self.to_string(),
             AppError::Unknown => "Internal error, failed to process request.".to_string(),
             _ => "Internal error, please try again later.".to_string(),
         };
 
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        let body = ErrorBody {
+            code: self.code(),
+            message,
+        };
+
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let response = AppError::MissingApiKey("groq".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("failed to read body");
+        let body: serde_json::Value = serde_json::from_slice(&bytes).expect("invalid json");
+
+        assert_eq!(body["code"], "missing_api_key");
+        assert_eq!(body["message"], "Missing api key for provider: groq");
+
+        let response = AppError::DbOperation.into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        assert_eq!(
+            AppError::UnsupportedModel("gpt-0".to_string()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(AppError::NotFound.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            AppError::AIChat("timeout".to_string()).status_code(),
+            StatusCode::BAD_GATEWAY
+        );
     }
 }
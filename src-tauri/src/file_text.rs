@@ -0,0 +1,313 @@
+use crate::constants::{BINARY_SNIFF_BYTES, HEX_PREVIEW_BYTES};
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Decoded as Windows-1252, the superset browsers use for Latin-1.
+    Latin1,
+    ShiftJis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    Mixed,
+    /// Single line text.
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: TextEncoding,
+    pub has_bom: bool,
+}
+
+/// Decodes `bytes` as text, returns `None` when they look binary. A BOM wins,
+/// then UTF-16 without BOM is recognised by its zero bytes, then UTF-8, then
+/// Shift-JIS when it decodes cleanly and reads as Japanese, and Latin-1 as the
+/// last resort.
+pub fn decode_text(bytes: &[u8]) -> Option<DecodedText> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let text = decode_strict(encoding, &bytes[bom_len..])?;
+
+        return Some(DecodedText {
+            text,
+            encoding: text_encoding(encoding),
+            has_bom: true,
+        });
+    }
+
+    if let Some(encoding) = sniff_utf16(bytes) {
+        if let Some(text) = decode_strict(encoding, bytes) {
+            return Some(DecodedText {
+                text,
+                encoding: text_encoding(encoding),
+                has_bom: false,
+            });
+        }
+    }
+
+    if is_binary(bytes) {
+        return None;
+    }
+
+    let (text, encoding) = match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), TextEncoding::Utf8),
+        Err(_) => match decode_strict(SHIFT_JIS, bytes).filter(|text| looks_japanese(text)) {
+            Some(text) => (text, TextEncoding::ShiftJis),
+            None => (
+                WINDOWS_1252
+                    .decode_without_bom_handling(bytes)
+                    .0
+                    .into_owned(),
+                TextEncoding::Latin1,
+            ),
+        },
+    };
+
+    Some(DecodedText {
+        text,
+        encoding,
+        has_bom: false,
+    })
+}
+
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let cr = text.matches('\r').count() - crlf;
+
+    match (lf > 0, crlf > 0, cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Lf,
+        (false, true, false) => LineEnding::Crlf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// `xxd` style dump of the first bytes, shown in place of binary contents.
+pub fn hex_summary(bytes: &[u8]) -> String {
+    let mut summary = String::new();
+
+    for (row, chunk) in bytes[..bytes.len().min(HEX_PREVIEW_BYTES)]
+        .chunks(16)
+        .enumerate()
+    {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        writeln!(
+            summary,
+            "{:08x}  {:<47}  |{}|",
+            row * 16,
+            hex.join(" "),
+            ascii
+        )
+        .ok();
+    }
+
+    if bytes.len() > HEX_PREVIEW_BYTES {
+        writeln!(
+            summary,
+            "... {} more bytes",
+            bytes.len() - HEX_PREVIEW_BYTES
+        )
+        .ok();
+    }
+
+    summary
+}
+
+/// Same heuristic as git: a NUL byte near the start means binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// ASCII heavy UTF-16 has a zero in every other byte.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+
+    if sample.len() < 2 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+
+    // Mostly zero on one side and almost never on the other.
+    if odd_zeros * 10 >= pairs * 4 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 4 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Plenty of Latin-1 byte pairs are valid Shift-JIS, `ü` or `é` followed by
+/// a letter decode as kanji and `À`..`ß` as half-width katakana. Japanese text
+/// has kana, either full-width or half-width words like `ｺｰﾋｰ` on receipts and
+/// legacy exports. Mojibake half-width kana stand alone or inside Latin words.
+fn looks_japanese(text: &str) -> bool {
+    let is_half_width = |c: char| matches!(c, '\u{FF61}'..='\u{FF9F}');
+
+    if text
+        .chars()
+        .any(|c| matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}'))
+    {
+        return true;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let latin_at = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_alphabetic());
+
+    let mut has_word = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !is_half_width(chars[i]) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && is_half_width(chars[i]) {
+            i += 1;
+        }
+
+        if (start > 0 && latin_at(start - 1)) || latin_at(i) {
+            return false;
+        }
+
+        has_word |= i - start > 1;
+    }
+
+    has_word
+}
+
+fn decode_strict(encoding: &'static Encoding, bytes: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+}
+
+fn text_encoding(encoding: &'static Encoding) -> TextEncoding {
+    if encoding == UTF_16LE {
+        TextEncoding::Utf16Le
+    } else if encoding == UTF_16BE {
+        TextEncoding::Utf16Be
+    } else {
+        TextEncoding::Utf8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xff, 0xfe] } else { vec![] };
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn test_utf8_with_and_without_bom() {
+        let plain = decode_text("héllo\n".as_bytes()).expect("should be text");
+        assert_eq!(plain.encoding, TextEncoding::Utf8);
+        assert!(!plain.has_bom);
+
+        let with_bom = decode_text(b"\xef\xbb\xbfhello\n").expect("should be text");
+        assert_eq!(with_bom.text, "hello\n");
+        assert!(with_bom.has_bom);
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let with_bom = decode_text(&utf16le("hello\r\n", true)).expect("should be text");
+        assert_eq!(with_bom.encoding, TextEncoding::Utf16Le);
+        assert_eq!(with_bom.text, "hello\r\n");
+
+        let without_bom = decode_text(&utf16le("hello world\n", false)).expect("should be text");
+        assert_eq!(without_bom.encoding, TextEncoding::Utf16Le);
+        assert!(!without_bom.has_bom);
+    }
+
+    #[test]
+    fn test_legacy_encodings() {
+        let latin1 = decode_text(b"caf\xe9 cr\xe8me\n").expect("should be text");
+        assert_eq!(latin1.encoding, TextEncoding::Latin1);
+        assert_eq!(latin1.text, "café crème\n");
+
+        let (mueller, _, _) = WINDOWS_1252.encode("Müller café\n");
+        let mueller = decode_text(&mueller).expect("should be text");
+        assert_eq!(mueller.encoding, TextEncoding::Latin1);
+        assert_eq!(mueller.text, "Müller café\n");
+
+        // Valid Shift-JIS byte for byte, as a kanji and half-width katakana.
+        for text in ["élan\n", "À la carte\n", "MÜNCHEN\n"] {
+            let (bytes, _, _) = WINDOWS_1252.encode(text);
+            assert!(decode_strict(SHIFT_JIS, &bytes).is_some());
+
+            let decoded = decode_text(&bytes).expect("should be text");
+            assert_eq!(decoded.encoding, TextEncoding::Latin1);
+            assert_eq!(decoded.text, text);
+        }
+
+        let (sjis, _, _) = SHIFT_JIS.encode("こんにちは\n");
+        let sjis = decode_text(&sjis).expect("should be text");
+        assert_eq!(sjis.encoding, TextEncoding::ShiftJis);
+        assert_eq!(sjis.text, "こんにちは\n");
+
+        let (receipt, _, _) = SHIFT_JIS.encode("ｺｰﾋｰ 350\nﾊﾟﾝ 180\n");
+        let receipt = decode_text(&receipt).expect("should be text");
+        assert_eq!(receipt.encoding, TextEncoding::ShiftJis);
+        assert_eq!(receipt.text, "ｺｰﾋｰ 350\nﾊﾟﾝ 180\n");
+    }
+
+    #[test]
+    fn test_binary_and_hex_summary() {
+        let bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+        assert!(decode_text(bytes).is_none());
+        assert_eq!(
+            hex_summary(bytes),
+            "00000000  89 50 4e 47 0d 0a 1a 0a 00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n"
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(detect_line_ending("a\nb\n"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("a\rb"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("a\r\nb\n"), LineEnding::Mixed);
+        assert_eq!(detect_line_ending("a"), LineEnding::None);
+    }
+}
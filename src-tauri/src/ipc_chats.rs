@@ -1,7 +1,12 @@
 use crate::db_chats::{
-    create_chat, delete_chat_by_id, find_many, findone_by_id, update_chat_message, ChatsRecord,
+    create_chat, delete_chat_by_id, find_page, findone_with_messages, fork_at_message,
+    save_chat_messages, switch_branch, update_message_parts, ChatSummary, ChatWithMessages,
+    ChatsRecord,
 };
+use crate::db_page::PageOptions;
 use crate::prelude::*;
+use crate::ui_message::UIMessage;
+use serde_json::Value;
 use tauri::Manager;
 use tauri::{AppHandle, Runtime};
 
@@ -19,11 +24,16 @@ pub async fn cmd_get_chat_api_endpoint(app: AppHandle) -> anyhow::Result<TaskSta
 pub async fn cmd_save_initial_chat<R: Runtime>(
     app: AppHandle<R>,
     chat: ChatsRecord,
+    messages: Vec<UIMessage>,
 ) -> anyhow::Result<bool, AppError> {
     let state = app.state::<DbOnlyState>();
     let db = &state.db;
 
-    let query = create_chat(db, chat).await.map_err(to_app_err)?;
+    let query = create_chat(db, chat.clone()).await.map_err(to_app_err)?;
+
+    save_chat_messages(db, &chat, &messages)
+        .await
+        .map_err(to_app_err)?;
 
     let result = query.rows_affected() == 1;
 
@@ -34,25 +44,29 @@ pub async fn cmd_save_initial_chat<R: Runtime>(
 pub async fn cmd_get_chat_by_id<R: Runtime>(
     app: AppHandle<R>,
     chat_id: &str,
-) -> anyhow::Result<ChatsRecord, AppError> {
+) -> anyhow::Result<ChatWithMessages, AppError> {
     let state = app.state::<DbOnlyState>();
     let db = &state.db;
 
-    let query = findone_by_id(db, chat_id).await.map_err(to_app_err)?;
+    let query = findone_with_messages(db, chat_id)
+        .await
+        .map_err(to_app_err)?;
 
     Ok(query)
 }
 
+/// Replaces the parts of one message, eg after it is edited.
 #[tauri::command(rename_all = "snake_case")]
 pub async fn cmd_update_chat_message<R: Runtime>(
     app: AppHandle<R>,
     chat_id: &str,
-    messages: &str,
+    message_id: &str,
+    parts: Vec<Value>,
 ) -> anyhow::Result<bool, AppError> {
     let state = app.state::<DbOnlyState>();
     let db = &state.db;
 
-    let operation = update_chat_message(db, chat_id, messages)
+    let operation = update_message_parts(db, chat_id, message_id, &parts)
         .await
         .map_err(to_app_err)?;
 
@@ -61,12 +75,61 @@ pub async fn cmd_update_chat_message<R: Runtime>(
     Ok(result)
 }
 
+/// Starts a branch after `message_id` and returns the chat on it, so the
+/// prompts that followed can be edited without losing the original thread.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_fork_chat_at_message<R: Runtime>(
+    app: AppHandle<R>,
+    chat_id: &str,
+    message_id: &str,
+) -> anyhow::Result<ChatWithMessages, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    fork_at_message(db, chat_id, message_id)
+        .await
+        .map_err(to_app_err)?;
+
+    let chat = findone_with_messages(db, chat_id)
+        .await
+        .map_err(to_app_err)?;
+
+    Ok(chat)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_switch_chat_branch<R: Runtime>(
+    app: AppHandle<R>,
+    chat_id: &str,
+    branch_id: &str,
+) -> anyhow::Result<ChatWithMessages, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    switch_branch(db, chat_id, branch_id)
+        .await
+        .map_err(to_app_err)?;
+
+    let chat = findone_with_messages(db, chat_id)
+        .await
+        .map_err(to_app_err)?;
+
+    Ok(chat)
+}
+
+/// Most recently updated chats. Pass `options` to page through older ones,
+/// see [`PageOptions`].
 #[tauri::command(rename_all = "snake_case")]
-pub async fn cmd_find_recent_chats(app: AppHandle) -> anyhow::Result<Vec<ChatsRecord>, AppError> {
+pub async fn cmd_find_recent_chats(
+    app: AppHandle,
+    options: Option<PageOptions>,
+) -> anyhow::Result<Vec<ChatSummary>, AppError> {
     let state = app.state::<DbOnlyState>();
     let db = &state.db;
 
-    let result = find_many(db).await.map_err(to_app_err)?;
+    let result = find_page(db, &options.unwrap_or_default())
+        .await
+        .map_err(to_app_err)?;
 
     Ok(result)
 }
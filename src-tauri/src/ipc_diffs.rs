@@ -0,0 +1,567 @@
+use crate::constants::{APP_ID, DEFAULT_PATCH_FUZZ, GIT_HISTORY_LIMIT, IMAGE_DIFFS};
+use crate::db_diffs::{
+    delete_diff_by_id, find_many, findone_by_id, save_diff, DiffRecord, DiffSummary, SaveDiff,
+};
+use crate::diff::{compute_diff, diff_metrics, DiffMetrics, DiffOptions, DiffResult, DiffStats};
+use crate::diff_dir::{diff_directories, DirDiffOptions, DirDiffResult};
+use crate::diff_export::{DiffReport, ReportFormat};
+use crate::diff_git::{
+    diff_revisions, diff_worktree, file_history, list_refs, open_repository, GitDiff,
+    GitFileRevision, GitRefs,
+};
+use crate::diff_image::{diff_images, ImageDiffOptions, ImageDiffResult};
+use crate::diff_merge::{
+    merge_three_way, resolve_conflicts, MergeOptions, MergeRegion, MergeResult, ResolveConflict,
+};
+use crate::diff_patch::{apply_patch, hunks_to_unified, single_file_patch, ApplyResult};
+use crate::diff_stream::{stream_diff, DiffStreamEvent};
+use crate::diff_structural::{compute_structural_diff, DataFormat, StructuralDiff};
+use crate::diff_syntax::{compute_syntax_diff, SyntaxDiff, SyntaxLanguage};
+use crate::diff_watch::spawn_diff_watch;
+use crate::file_text::TextEncoding;
+use crate::ipc_utils::{pick_save_path, read_file_contents, ReadFileOptions};
+use crate::prelude::*;
+use crate::utils::Utils;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::ipc::Channel;
+use tauri::Manager;
+use tauri::{AppHandle, Runtime};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum DiffSource {
+    Text { old: String, new: String },
+    Files { old_path: String, new_path: String },
+}
+
+impl DiffSource {
+    /// Resolves the old and new texts, reading from disk when given paths.
+    pub fn load(self) -> anyhow::Result<(String, String), AppError> {
+        match self {
+            DiffSource::Text { old, new } => Ok((old, new)),
+            DiffSource::Files { old_path, new_path } => {
+                let old = read_file_contents(&old_path, &ReadFileOptions::default())?;
+                let new = read_file_contents(&new_path, &ReadFileOptions::default())?;
+
+                Ok((old.contents, new.contents))
+            }
+        }
+    }
+
+    /// Format hint for structural diffs, taken from the file extensions.
+    fn data_format(&self) -> Option<DataFormat> {
+        match self {
+            DiffSource::Text { .. } => None,
+            DiffSource::Files { old_path, new_path } => {
+                DataFormat::from_path(new_path).or_else(|| DataFormat::from_path(old_path))
+            }
+        }
+    }
+
+    /// Language hint for syntax diffs, taken from the file extensions.
+    fn syntax_language(&self) -> Option<SyntaxLanguage> {
+        match self {
+            DiffSource::Text { .. } => None,
+            DiffSource::Files { old_path, new_path } => {
+                SyntaxLanguage::from_path(new_path).or_else(|| SyntaxLanguage::from_path(old_path))
+            }
+        }
+    }
+
+    /// How each side is named in reports.
+    fn labels(&self) -> (String, String) {
+        match self {
+            DiffSource::Text { .. } => ("old".to_string(), "new".to_string()),
+            DiffSource::Files { old_path, new_path } => (old_path.clone(), new_path.clone()),
+        }
+    }
+
+    /// Name used in `---`/`+++` patch headers, before the `a/` and `b/` prefixes.
+    fn patch_name(&self) -> String {
+        match self {
+            DiffSource::Text { .. } => "untitled".to_string(),
+            DiffSource::Files { new_path, .. } => Path::new(new_path)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum DiffMode {
+    #[default]
+    Text,
+    /// Compare JSON, YAML or TOML documents as trees. The format is detected
+    /// from the file extensions or the contents when not given.
+    Structural { format: Option<DataFormat> },
+    /// Compare source code node by node. Falls back to a text diff when the
+    /// language is unknown or either side does not parse.
+    Syntax { language: Option<SyntaxLanguage> },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "snake_case", tag = "mode", content = "result")]
+pub enum DiffOutput {
+    Text(DiffResult),
+    Structural(StructuralDiff),
+    Syntax(SyntaxDiff),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(
+    rename_all = "snake_case",
+    rename_all_fields = "snake_case",
+    tag = "type"
+)]
+pub enum PatchTarget {
+    Text {
+        text: String,
+    },
+    /// The file is only rewritten when every hunk applies.
+    File {
+        path: String,
+    },
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_compute_diff(
+    source: DiffSource,
+    options: Option<DiffOptions>,
+    mode: Option<DiffMode>,
+) -> anyhow::Result<DiffOutput, AppError> {
+    let options = options.unwrap_or_default();
+
+    // Diffing large inputs is CPU bound, keep it off the async runtime.
+    tauri::async_runtime::spawn_blocking(move || {
+        let format_hint = source.data_format();
+        let language_hint = source.syntax_language();
+        let (old, new) = source.load()?;
+
+        match mode.unwrap_or_default() {
+            DiffMode::Text => Ok(DiffOutput::Text(compute_diff(&old, &new, &options)?)),
+            DiffMode::Structural { format } => {
+                let diff = compute_structural_diff(&old, &new, format.or(format_hint))?;
+
+                Ok(DiffOutput::Structural(diff))
+            }
+            DiffMode::Syntax { language } => {
+                let diff = match language.or(language_hint) {
+                    Some(language) => compute_syntax_diff(&old, &new, language, &options)?,
+                    None => None,
+                };
+
+                match diff {
+                    Some(diff) => Ok(DiffOutput::Syntax(diff)),
+                    None => Ok(DiffOutput::Text(compute_diff(&old, &new, &options)?)),
+                }
+            }
+        }
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Line counts, similarity and hunk positions without the hunk lines.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_diff_stats(
+    source: DiffSource,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<DiffMetrics, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (old, new) = source.load()?;
+
+        diff_metrics(&old, &new, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_merge_three_way(
+    base: String,
+    ours: String,
+    theirs: String,
+    options: Option<MergeOptions>,
+) -> anyhow::Result<MergeResult, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || merge_three_way(&base, &ours, &theirs, &options))
+        .await
+        .map_err(|e| AppError::Runtime(e.to_string()))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn cmd_resolve_merge_conflicts(
+    regions: Vec<MergeRegion>,
+    resolutions: Vec<ResolveConflict>,
+) -> anyhow::Result<String, AppError> {
+    resolve_conflicts(&regions, &resolutions)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_diff_directories(
+    old_dir: String,
+    new_dir: String,
+    options: Option<DirDiffOptions>,
+) -> anyhow::Result<DirDiffResult, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || diff_directories(&old_dir, &new_dir, &options))
+        .await
+        .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_unified_diff(
+    source: DiffSource,
+    options: Option<DiffOptions>,
+    file_name: Option<String>,
+) -> anyhow::Result<String, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let name = file_name.unwrap_or_else(|| source.patch_name());
+        let (old, new) = source.load()?;
+
+        // Built from the hunks so the patch leaves out what the options ignore.
+        let result = compute_diff(&old, &new, &options)?;
+
+        Ok::<_, AppError>(hunks_to_unified(
+            &old,
+            &new,
+            &result.hunks,
+            &format!("a/{}", name),
+            &format!("b/{}", name),
+        ))
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Saves the unified diff to a path picked in the save dialog.
+/// Returns the saved path, or `None` when the dialog was dismissed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_export_patch<R: Runtime>(
+    app: AppHandle<R>,
+    source: DiffSource,
+    options: Option<DiffOptions>,
+    file_name: Option<String>,
+) -> anyhow::Result<Option<String>, AppError> {
+    let name = file_name.unwrap_or_else(|| source.patch_name());
+    let patch = cmd_unified_diff(source, options, Some(name.clone())).await?;
+
+    if patch.is_empty() {
+        return Err(AppError::Patch(
+            "Nothing to export, both sides are identical".to_string(),
+        ));
+    }
+
+    let path = pick_save_path(
+        &app,
+        &format!("{}.patch", name),
+        "Patch",
+        &["patch", "diff"],
+    )
+    .await?;
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    tokio::fs::write(&path, patch)
+        .await
+        .map_err(|e| AppError::File(e.to_string()))?;
+
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Renders the diff as a standalone HTML page or a Markdown snippet and saves
+/// it where the user chooses. Resolves to `None` when the dialog is dismissed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_export_report<R: Runtime>(
+    app: AppHandle<R>,
+    source: DiffSource,
+    format: ReportFormat,
+    options: Option<DiffOptions>,
+    file_name: Option<String>,
+) -> anyhow::Result<Option<String>, AppError> {
+    let options = options.unwrap_or_default();
+    let name = file_name.unwrap_or_else(|| source.patch_name());
+
+    let report_name = name.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let language = source.syntax_language();
+        let (old_label, new_label) = source.labels();
+        let (old, new) = source.load()?;
+
+        let result = compute_diff(&old, &new, &options)?;
+
+        if result.identical {
+            return Err(AppError::Diff(
+                "Nothing to export, both sides are identical".to_string(),
+            ));
+        }
+
+        let report = DiffReport {
+            title: &report_name,
+            old_label: &old_label,
+            new_label: &new_label,
+            old: &old,
+            new: &new,
+            result: &result,
+            language,
+        };
+
+        Ok(match format {
+            ReportFormat::Html { layout } => report.render_html(layout),
+            ReportFormat::Markdown => report.render_markdown(),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))??;
+
+    let extension = format.extension();
+
+    let path = pick_save_path(
+        &app,
+        &format!("{}.{}", name, extension),
+        format.filter_name(),
+        &[extension],
+    )
+    .await?;
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    tokio::fs::write(&path, report)
+        .await
+        .map_err(|e| AppError::File(e.to_string()))?;
+
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_apply_patch(
+    patch: String,
+    target: PatchTarget,
+    max_fuzz: Option<usize>,
+) -> anyhow::Result<ApplyResult, AppError> {
+    let max_fuzz = max_fuzz.unwrap_or(DEFAULT_PATCH_FUZZ);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let file_patch = single_file_patch(&patch)?;
+
+        match target {
+            PatchTarget::Text { text } => Ok(apply_patch(&text, &file_patch, max_fuzz)),
+            PatchTarget::File { path } => {
+                let file = read_file_contents(&path, &ReadFileOptions::default())?;
+
+                // Writing back is always UTF-8, other encodings would be converted silently.
+                if file.encoding != Some(TextEncoding::Utf8) {
+                    return Err(AppError::Patch(format!(
+                        "{} is not UTF-8 and cannot be patched in place",
+                        file.name
+                    )));
+                }
+
+                let result = apply_patch(&file.contents, &file_patch, max_fuzz);
+
+                if result.failed == 0 {
+                    let text = if file.has_bom {
+                        format!("\u{feff}{}", result.text)
+                    } else {
+                        result.text.clone()
+                    };
+
+                    std::fs::write(Utils::normalise_path(&path), text)
+                        .context(format!("Could not write: {}", path))
+                        .map_err(|e| AppError::File(e.to_string()))?;
+                }
+
+                Ok(result)
+            }
+        }
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Diffs two large files straight from disk, hunks are sent over `channel` as
+/// they are found instead of being returned at the end.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_stream_diff(
+    old_path: String,
+    new_path: String,
+    options: Option<DiffOptions>,
+    channel: Channel<DiffStreamEvent>,
+) -> anyhow::Result<DiffStats, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        stream_diff(&old_path, &new_path, &options, |event| {
+            channel.send(event).ok();
+        })
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Starts watching both files, recomputed diffs arrive as `diff_watch` events.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_watch_diff(
+    app: AppHandle,
+    watch_id: String,
+    old_path: String,
+    new_path: String,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<(), AppError> {
+    spawn_diff_watch(
+        app,
+        watch_id,
+        old_path,
+        new_path,
+        options.unwrap_or_default(),
+    )
+}
+
+/// Compares two PNG, JPEG or WebP files. The highlighted overlay is written to
+/// `Documents/Differ/image_diffs`, one file per pair of paths.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_diff_images(
+    old_path: String,
+    new_path: String,
+    options: Option<ImageDiffOptions>,
+) -> anyhow::Result<ImageDiffResult, AppError> {
+    let options = options.unwrap_or_default();
+
+    let document_dir = Utils::get_document_dir().map_err(AppError::File)?;
+    let overlay_dir = document_dir.join(APP_ID).join(IMAGE_DIFFS);
+
+    tokio::fs::create_dir_all(&overlay_dir)
+        .await
+        .map_err(|e| AppError::File(e.to_string()))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        diff_images(&old_path, &new_path, &overlay_dir, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_git_refs(repo_path: String) -> anyhow::Result<GitRefs, AppError> {
+    tauri::async_runtime::spawn_blocking(move || list_refs(&open_repository(&repo_path)?))
+        .await
+        .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Uncommitted changes in the repository containing `repo_path`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_git_diff_worktree(
+    repo_path: String,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<GitDiff, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        diff_worktree(&open_repository(&repo_path)?, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+/// Diffs two commits, branches or any other revisions git understands.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_git_diff_revisions(
+    repo_path: String,
+    old_rev: String,
+    new_rev: String,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<GitDiff, AppError> {
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        diff_revisions(&open_repository(&repo_path)?, &old_rev, &new_rev, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_git_file_history(
+    repo_path: String,
+    file_path: String,
+    limit: Option<usize>,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<Vec<GitFileRevision>, AppError> {
+    let options = options.unwrap_or_default();
+    let limit = limit.unwrap_or(GIT_HISTORY_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        file_history(&open_repository(&repo_path)?, &file_path, limit, &options)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_save_diff<R: Runtime>(
+    app: AppHandle<R>,
+    diff: SaveDiff,
+) -> anyhow::Result<i64, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let diff_id = save_diff(db, diff).await.map_err(to_app_err)?;
+
+    Ok(diff_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_get_diff_by_id<R: Runtime>(
+    app: AppHandle<R>,
+    diff_id: i64,
+) -> anyhow::Result<DiffRecord, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let query = findone_by_id(db, diff_id).await.map_err(to_app_err)?;
+
+    Ok(query)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_find_recent_diffs(app: AppHandle) -> anyhow::Result<Vec<DiffSummary>, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let result = find_many(db).await.map_err(to_app_err)?;
+
+    Ok(result)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_delete_diff_by_id(app: AppHandle, diff_id: i64) -> anyhow::Result<bool, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let operation = delete_diff_by_id(db, diff_id).await.map_err(to_app_err)?;
+
+    let result = operation.rows_affected() == 1;
+    Ok(result)
+}
@@ -1,11 +1,15 @@
 use crate::constants::{APP_ID, ATTACHMENTS};
+use crate::db_page::PageOptions;
 use crate::db_pastebin::create_paste;
 use crate::db_pastebin::delete_paste_by_id;
 use crate::db_pastebin::find_many;
+use crate::db_pastebin::find_page;
 use crate::db_pastebin::findone_by_id;
 use crate::db_pastebin::AttachmentRecord;
 use crate::db_pastebin::PasteRecord;
+use crate::diff::{rank_by_similarity, DiffOptions};
 use crate::ipc_convex::convex_delete_paste;
+use crate::ipc_utils::{read_file_contents, ReadFileOptions};
 use crate::prelude::*;
 use crate::utils::Utils;
 use convex::FunctionResult;
@@ -31,18 +35,75 @@ pub async fn cmd_get_paste_by_id<R: Runtime>(
     Ok(query)
 }
 
+/// Most recently updated pastes. Pass `options` to page through older ones,
+/// see [`PageOptions`].
 #[tauri::command(rename_all = "snake_case")]
 pub async fn cmd_find_recent_local_pastes(
     app: AppHandle,
+    options: Option<PageOptions>,
 ) -> anyhow::Result<Vec<PasteRecord>, AppError> {
     let state = app.state::<DbOnlyState>();
     let db = &state.db;
 
-    let result = find_many(db).await.map_err(to_app_err)?;
+    let result = find_page(db, &options.unwrap_or_default())
+        .await
+        .map_err(to_app_err)?;
 
     Ok(result)
 }
 
+/// Ranks local pastes by how closely their body matches the file at
+/// `file_path`, most similar first. Only `paste_ids` are compared when given,
+/// the recent pastes otherwise.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_rank_pastes_by_similarity(
+    app: AppHandle,
+    file_path: String,
+    paste_ids: Option<Vec<String>>,
+    options: Option<DiffOptions>,
+) -> anyhow::Result<Vec<PasteSimilarity>, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let pastes = match paste_ids {
+        Some(paste_ids) => {
+            let mut pastes = vec![];
+
+            for paste_id in paste_ids {
+                pastes.push(findone_by_id(db, &paste_id).await.map_err(to_app_err)?);
+            }
+
+            pastes
+        }
+        None => find_many(db).await.map_err(to_app_err)?,
+    };
+
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let target = read_file_contents(&file_path, &ReadFileOptions::default())?;
+
+        let candidates = pastes
+            .into_iter()
+            .map(|paste| (paste.id, paste.body))
+            .collect();
+
+        let ranked = rank_by_similarity(&target.contents, candidates, &options)?
+            .into_iter()
+            .map(|(paste_id, metrics)| PasteSimilarity {
+                paste_id,
+                similarity: metrics.similarity,
+                insertions: metrics.insertions,
+                deletions: metrics.deletions,
+            })
+            .collect();
+
+        Ok(ranked)
+    })
+    .await
+    .map_err(|e| AppError::Runtime(e.to_string()))?
+}
+
 #[tauri::command(rename_all = "snake_case")]
 pub async fn cmd_delete_remote_paste_by_id(
     app: AppHandle,
@@ -181,6 +242,16 @@ pub struct SaveRemotePasteResponse {
     pub paste_id: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasteSimilarity {
+    pub paste_id: String,
+    pub similarity: f64,
+
+    /// Lines the file adds to and removes from the paste.
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IsSyncedResponse {
     #[serde(rename = "isSynced")]
@@ -0,0 +1,71 @@
+use crate::db_models::{self, ModelRecord};
+use crate::db_providers::{
+    delete_provider_by_id, find_many, save_provider, CustomProviderRecord, SaveCustomProvider,
+};
+use crate::openai_compatible::parse_base_url;
+use crate::prelude::*;
+use tauri::Manager;
+use tauri::{AppHandle, Runtime};
+
+/// Saves an OpenAI compatible provider, returning its id. Select it by
+/// storing the id as `selected_provider` in the app config, the model name
+/// goes in `selected_model` as is.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_save_custom_provider<R: Runtime>(
+    app: AppHandle<R>,
+    provider: SaveCustomProvider,
+) -> anyhow::Result<String, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    if provider.name.trim().is_empty() {
+        return Err(AppError::Request("Provider name is required".to_string()));
+    }
+
+    parse_base_url(&provider.base_url)?;
+
+    let provider_id = save_provider(db, provider).await.map_err(to_app_err)?;
+
+    Ok(provider_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_find_custom_providers<R: Runtime>(
+    app: AppHandle<R>,
+) -> anyhow::Result<Vec<CustomProviderRecord>, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let providers = find_many(db).await.map_err(to_app_err)?;
+
+    Ok(providers)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_delete_custom_provider<R: Runtime>(
+    app: AppHandle<R>,
+    provider_id: &str,
+) -> anyhow::Result<bool, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let operation = delete_provider_by_id(db, provider_id)
+        .await
+        .map_err(to_app_err)?;
+
+    Ok(operation.rows_affected() == 1)
+}
+
+/// Models of the built in providers, the same registry the chat server
+/// checks the selected model against.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_list_models<R: Runtime>(
+    app: AppHandle<R>,
+) -> anyhow::Result<Vec<ModelRecord>, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let models = db_models::find_many(db).await.map_err(to_app_err)?;
+
+    Ok(models)
+}
@@ -0,0 +1,20 @@
+use crate::constants::SEARCH_RESULTS_LIMIT;
+use crate::db_search::{search, SearchResult};
+use crate::prelude::*;
+use tauri::Manager;
+use tauri::{self, AppHandle};
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cmd_search(
+    app: AppHandle,
+    query: &str,
+) -> anyhow::Result<Vec<SearchResult>, AppError> {
+    let state = app.state::<DbOnlyState>();
+    let db = &state.db;
+
+    let results = search(db, query, SEARCH_RESULTS_LIMIT)
+        .await
+        .map_err(to_app_err)?;
+
+    Ok(results)
+}
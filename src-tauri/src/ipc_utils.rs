@@ -1,12 +1,28 @@
+use crate::constants::MAX_READ_FILE_BYTES;
 use crate::db_config::{get_app_config, update_app_config, AppConfigRecord, UpdateAppConfig};
+use crate::file_text::{decode_text, detect_line_ending, hex_summary, LineEnding, TextEncoding};
 use crate::prelude::*;
 use crate::utils::Utils;
+use std::path::PathBuf;
 use std::{fs, path::Path};
 use tauri::Manager;
 use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::DialogExt;
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn cmd_read_file(file_path: &str) -> anyhow::Result<FileContents, AppError> {
+pub fn cmd_read_file(
+    file_path: &str,
+    options: Option<ReadFileOptions>,
+) -> anyhow::Result<FileContents, AppError> {
+    read_file_contents(file_path, &options.unwrap_or_default())
+}
+
+/// Reads a text file in whatever encoding it was saved in. Binary files are
+/// refused, or summarised as a hex dump when asked for.
+pub fn read_file_contents(
+    file_path: &str,
+    options: &ReadFileOptions,
+) -> anyhow::Result<FileContents, AppError> {
     let normalized_path = Utils::normalise_path(file_path);
     let p = Path::new(normalized_path.as_str());
 
@@ -17,11 +33,79 @@ pub fn cmd_read_file(file_path: &str) -> anyhow::Result<FileContents, AppError>
         .unwrap_or_default()
         .to_string();
 
-    let contents = fs::read_to_string(normalized_path)
+    let size = fs::metadata(p)
+        .context(format!("Could not read: {}", file_path))
+        .map_err(|e| AppError::File(e.to_string()))?
+        .len();
+
+    if size > options.max_size {
+        return Err(AppError::File(format!(
+            "{} is {} bytes, larger than the {} byte limit",
+            name, size, options.max_size
+        )));
+    }
+
+    let bytes = fs::read(p)
         .context(format!("Could not read: {}", file_path))
         .map_err(|e| AppError::File(e.to_string()))?;
 
-    Ok(FileContents { name, contents })
+    match decode_text(&bytes) {
+        Some(decoded) => Ok(FileContents {
+            name,
+            line_ending: Some(detect_line_ending(&decoded.text)),
+            contents: decoded.text,
+            encoding: Some(decoded.encoding),
+            has_bom: decoded.has_bom,
+            binary: false,
+            size,
+        }),
+        None => match options.binary {
+            BinaryHandling::Refuse => {
+                Err(AppError::File(format!("{} looks like a binary file", name)))
+            }
+            BinaryHandling::HexSummary => Ok(FileContents {
+                name,
+                contents: hex_summary(&bytes),
+                encoding: None,
+                has_bom: false,
+                line_ending: None,
+                binary: true,
+                size,
+            }),
+        },
+    }
+}
+
+/// Opens the native save dialog and waits for the user to pick a destination.
+/// Returns `None` when the dialog is dismissed.
+pub async fn pick_save_path<R: Runtime>(
+    app: &AppHandle<R>,
+    file_name: &str,
+    filter_name: &str,
+    extensions: &[&str],
+) -> anyhow::Result<Option<PathBuf>, AppError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    app.dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(filter_name, extensions)
+        .save_file(move |path| {
+            tx.send(path).ok();
+        });
+
+    let path = rx.await.map_err(|e| AppError::Runtime(e.to_string()))?;
+
+    match path {
+        Some(path) => {
+            let path = path
+                .into_path()
+                .map_err(|e| AppError::File(e.to_string()))?;
+
+            Ok(Some(path))
+        }
+        None => Ok(None),
+    }
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -104,5 +188,41 @@ pub struct Tasks {
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct FileContents {
     pub name: String,
+
+    /// Decoded text, or a hex dump for binary files.
     pub contents: String,
+
+    /// `None` for binary files.
+    pub encoding: Option<TextEncoding>,
+    pub has_bom: bool,
+    pub line_ending: Option<LineEnding>,
+    pub binary: bool,
+
+    /// Size on disk in bytes.
+    pub size: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryHandling {
+    #[default]
+    Refuse,
+    HexSummary,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct ReadFileOptions {
+    /// Files larger than this many bytes are refused.
+    pub max_size: u64,
+    pub binary: BinaryHandling,
+}
+
+impl Default for ReadFileOptions {
+    fn default() -> Self {
+        Self {
+            max_size: MAX_READ_FILE_BYTES,
+            binary: BinaryHandling::default(),
+        }
+    }
 }
@@ -2,17 +2,42 @@ mod axum;
 mod constants;
 mod db_chats;
 mod db_config;
+mod db_diffs;
+mod db_models;
+mod db_page;
 mod db_pastebin;
+mod db_providers;
+mod db_search;
+mod diff;
+mod diff_dir;
+mod diff_explain;
+mod diff_export;
+mod diff_git;
+mod diff_image;
+mod diff_merge;
+mod diff_moves;
+mod diff_patch;
+mod diff_stream;
+mod diff_structural;
+mod diff_syntax;
+mod diff_watch;
 mod error;
+mod file_text;
 mod ipc_chats;
 mod ipc_convex;
+mod ipc_diffs;
 mod ipc_pastebin;
+mod ipc_providers;
+mod ipc_search;
 mod ipc_utils;
+mod openai_compatible;
 mod prelude;
+mod ui_message;
 mod utils;
 
 use crate::axum::init_chat_api;
 use crate::db_config::init_app_config;
+use crate::db_models::seed_models;
 use crate::prelude::*;
 use crate::utils::{init_tracer, Utils};
 use crate::{Db, DifferState};
@@ -64,6 +89,15 @@ pub async fn run() -> AppResult<()> {
 
     span.end();
 
+    let mut span = tracer.start("seed_models");
+    if let Err(err) = seed_models(&db).await {
+        span.record_error(err.as_ref());
+        span.set_status(Status::error(err.to_string()));
+        panic!("Model registry error: {}", err)
+    };
+
+    span.end();
+
     let deployment_url = dotenvy::var("VITE_CONVEX_URL")
         .expect("[VITE_CONVEX_URL] is required, it must end with .cloud");
 
@@ -125,16 +159,44 @@ pub async fn run() -> AppResult<()> {
             ipc_chats::cmd_get_chat_api_endpoint,
             ipc_chats::cmd_save_initial_chat,
             ipc_chats::cmd_update_chat_message,
+            ipc_chats::cmd_fork_chat_at_message,
+            ipc_chats::cmd_switch_chat_branch,
             ipc_chats::cmd_find_recent_chats,
             ipc_chats::cmd_delete_chat_by_id,
+            ipc_providers::cmd_save_custom_provider,
+            ipc_providers::cmd_find_custom_providers,
+            ipc_providers::cmd_delete_custom_provider,
+            ipc_providers::cmd_list_models,
             ipc_pastebin::cmd_is_synced,
             ipc_pastebin::cmd_sync_app_to_remote_server,
             ipc_pastebin::cmd_get_paste_by_id,
             ipc_pastebin::cmd_save_remote_paste,
             ipc_pastebin::cmd_find_recent_local_pastes,
+            ipc_pastebin::cmd_rank_pastes_by_similarity,
             ipc_pastebin::cmd_delete_remote_paste_by_id,
             ipc_pastebin::cmd_delete_local_paste_by_id,
             ipc_pastebin::cmd_save_remote_paste_locally,
+            ipc_search::cmd_search,
+            ipc_diffs::cmd_compute_diff,
+            ipc_diffs::cmd_diff_stats,
+            ipc_diffs::cmd_merge_three_way,
+            ipc_diffs::cmd_resolve_merge_conflicts,
+            ipc_diffs::cmd_diff_directories,
+            ipc_diffs::cmd_unified_diff,
+            ipc_diffs::cmd_export_patch,
+            ipc_diffs::cmd_export_report,
+            ipc_diffs::cmd_apply_patch,
+            ipc_diffs::cmd_stream_diff,
+            ipc_diffs::cmd_watch_diff,
+            ipc_diffs::cmd_diff_images,
+            ipc_diffs::cmd_git_refs,
+            ipc_diffs::cmd_git_diff_worktree,
+            ipc_diffs::cmd_git_diff_revisions,
+            ipc_diffs::cmd_git_file_history,
+            ipc_diffs::cmd_save_diff,
+            ipc_diffs::cmd_get_diff_by_id,
+            ipc_diffs::cmd_find_recent_diffs,
+            ipc_diffs::cmd_delete_diff_by_id,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
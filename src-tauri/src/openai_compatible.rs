@@ -0,0 +1,475 @@
+use aisdk::core::language_model::{
+    LanguageModelOptions, LanguageModelResponse, LanguageModelResponseContentType,
+    LanguageModelStreamChunk, Usage,
+};
+use aisdk::core::{AssistantMessage, LanguageModel, LanguageModelStreamChunkType, Message};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use reqwest::Url;
+use reqwest_eventsource::retry::Never;
+use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+
+type ChunkStream = Pin<Box<dyn Stream<Item = aisdk::Result<Vec<LanguageModelStreamChunk>>> + Send>>;
+
+/// A model served over the OpenAI chat completions API, the format spoken by
+/// Ollama, LM Studio, vLLM and most gateways. Unlike the aisdk providers the
+/// model name is only known at runtime.
+#[derive(Debug, Clone)]
+pub struct OpenAICompatible {
+    base_url: Url,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAICompatible {
+    /// `base_url` includes the version path, eg `http://localhost:11434/v1`.
+    pub fn new(base_url: &str, api_key: Option<&str>, model: &str) -> aisdk::Result<Self> {
+        let base_url = parse_base_url(base_url)?;
+
+        if model.trim().is_empty() {
+            return Err(aisdk::Error::MissingField("model".to_string()));
+        }
+
+        Ok(Self {
+            base_url,
+            api_key: api_key
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string),
+            model: model.trim().to_string(),
+        })
+    }
+
+    fn request(&self, options: &LanguageModelOptions, stream: bool) -> reqwest::RequestBuilder {
+        let body = ChatRequest {
+            model: &self.model,
+            messages: chat_messages(&options.messages()),
+            stream,
+            temperature: options.temperature.map(|t| t as f32 / 100.0),
+            top_p: options.top_p.map(|p| p as f32 / 100.0),
+            max_tokens: options.max_output_tokens,
+            stop: options.stop_sequences.clone(),
+            seed: options.seed,
+        };
+
+        // The base url always ends with a slash, so this cannot fail.
+        let url = self
+            .base_url
+            .join("chat/completions")
+            .unwrap_or_else(|_| self.base_url.clone());
+
+        let request = reqwest::Client::new().post(url).json(&body);
+
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl LanguageModel for OpenAICompatible {
+    fn name(&self) -> String {
+        self.model.clone()
+    }
+
+    async fn generate_text(
+        &mut self,
+        options: LanguageModelOptions,
+    ) -> aisdk::Result<LanguageModelResponse> {
+        let response =
+            self.request(&options, false)
+                .send()
+                .await
+                .map_err(|e| aisdk::Error::ApiError {
+                    status_code: e.status(),
+                    details: e.to_string(),
+                })?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+
+        if !status.is_success() {
+            return Err(aisdk::Error::ApiError {
+                status_code: Some(status),
+                details: body,
+            });
+        }
+
+        let completion: ChatCompletion =
+            serde_json::from_str(&body).map_err(|e| aisdk::Error::ApiError {
+                status_code: Some(status),
+                details: format!("Failed to parse response: {}", e),
+            })?;
+
+        let text = completion
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default();
+
+        Ok(LanguageModelResponse {
+            usage: completion.usage.map(Usage::from),
+            ..LanguageModelResponse::new(text)
+        })
+    }
+
+    async fn stream_text(&mut self, options: LanguageModelOptions) -> aisdk::Result<ChunkStream> {
+        let mut source =
+            self.request(&options, true)
+                .eventsource()
+                .map_err(|e| aisdk::Error::ApiError {
+                    status_code: None,
+                    details: format!("SSE stream error: {}", e),
+                })?;
+
+        // A retry would send the whole prompt again.
+        source.set_retry_policy(Box::new(Never));
+
+        let state = StreamState {
+            source,
+            parser: ChunkParser::default(),
+            done: false,
+        };
+
+        let stream = futures::stream::unfold(state, |mut state| async move {
+            if state.done {
+                return None;
+            }
+
+            loop {
+                let event = match state.source.next().await {
+                    Some(event) => event,
+                    None => return Some((Ok(state.finish()), state)),
+                };
+
+                match event {
+                    Ok(Event::Open) => continue,
+                    Ok(Event::Message(message)) if message.data.trim() == "[DONE]" => {
+                        return Some((Ok(state.finish()), state));
+                    }
+                    Ok(Event::Message(message)) => {
+                        let chunk = match serde_json::from_str::<ChatCompletionChunk>(&message.data)
+                        {
+                            Ok(chunk) => chunk,
+                            Err(e) => {
+                                state.close();
+                                let error = aisdk::Error::Other(format!(
+                                    "Failed to parse stream event: {}",
+                                    e
+                                ));
+
+                                return Some((Err(error), state));
+                            }
+                        };
+
+                        let chunks = state.parser.apply(chunk);
+
+                        if !chunks.is_empty() {
+                            return Some((Ok(chunks), state));
+                        }
+                    }
+                    Err(reqwest_eventsource::Error::StreamEnded) => {
+                        return Some((Ok(state.finish()), state));
+                    }
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(status, response)) => {
+                        state.close();
+                        let error = aisdk::Error::ApiError {
+                            status_code: Some(status),
+                            details: response.text().await.unwrap_or_default(),
+                        };
+
+                        return Some((Err(error), state));
+                    }
+                    Err(e) => {
+                        state.close();
+                        let error = aisdk::Error::ApiError {
+                            status_code: None,
+                            details: e.to_string(),
+                        };
+
+                        return Some((Err(error), state));
+                    }
+                }
+            }
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Parses a provider's base URL, keeping its version path when joined with
+/// an endpoint.
+pub fn parse_base_url(base_url: &str) -> aisdk::Result<Url> {
+    // Without the trailing slash `join` would replace the version path.
+    let base_url = format!("{}/", base_url.trim().trim_end_matches('/'));
+
+    let base_url = Url::parse(&base_url)
+        .map_err(|e| aisdk::Error::InvalidInput(format!("Invalid base URL: {}", e)))?;
+
+    if !matches!(base_url.scheme(), "http" | "https") {
+        return Err(aisdk::Error::InvalidInput(format!(
+            "Base URL must start with http:// or https://, got {}",
+            base_url
+        )));
+    }
+
+    Ok(base_url)
+}
+
+struct StreamState {
+    source: EventSource,
+    parser: ChunkParser,
+    done: bool,
+}
+
+impl StreamState {
+    fn finish(&mut self) -> Vec<LanguageModelStreamChunk> {
+        self.close();
+
+        vec![self.parser.finish()]
+    }
+
+    fn close(&mut self) {
+        self.done = true;
+        self.source.close();
+    }
+}
+
+/// Turns chat completion chunks into aisdk deltas, keeping the text for the
+/// final message.
+#[derive(Default)]
+struct ChunkParser {
+    text: String,
+    usage: Option<Usage>,
+}
+
+impl ChunkParser {
+    fn apply(&mut self, chunk: ChatCompletionChunk) -> Vec<LanguageModelStreamChunk> {
+        if let Some(usage) = chunk.usage {
+            self.usage = Some(usage.into());
+        }
+
+        let mut chunks = vec![];
+
+        for choice in chunk.choices {
+            // vLLM names it `reasoning_content`, Ollama `reasoning`.
+            if let Some(reasoning) = choice.delta.reasoning_content.or(choice.delta.reasoning) {
+                if !reasoning.is_empty() {
+                    chunks.push(LanguageModelStreamChunk::Delta(
+                        LanguageModelStreamChunkType::Reasoning(reasoning),
+                    ));
+                }
+            }
+
+            if let Some(content) = choice.delta.content {
+                if !content.is_empty() {
+                    self.text.push_str(&content);
+                    chunks.push(LanguageModelStreamChunk::Delta(
+                        LanguageModelStreamChunkType::Text(content),
+                    ));
+                }
+            }
+        }
+
+        chunks
+    }
+
+    fn finish(&mut self) -> LanguageModelStreamChunk {
+        LanguageModelStreamChunk::Done(AssistantMessage::new(
+            std::mem::take(&mut self.text).into(),
+            self.usage.take(),
+        ))
+    }
+}
+
+/// Maps the conversation onto chat completion roles. Tool messages are
+/// dropped, tools are not offered to these models.
+fn chat_messages(messages: &[Message]) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .filter_map(|message| {
+            let (role, content) = match message {
+                Message::System(system) => ("system", system.content.clone()),
+                Message::Developer(content) => ("system", content.clone()),
+                Message::User(user) => ("user", user.content.clone()),
+                Message::Assistant(assistant) => match &assistant.content {
+                    LanguageModelResponseContentType::Text(text) => ("assistant", text.clone()),
+                    _ => return None,
+                },
+                Message::Tool(_) => return None,
+            };
+
+            (!content.is_empty()).then_some(ChatMessage { role, content })
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u32>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletion {
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatChoiceMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChatChunkChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChunkChoice {
+    delta: ChatDelta,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ChatDelta {
+    content: Option<String>,
+    reasoning: Option<String>,
+    reasoning_content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatUsage {
+    prompt_tokens: Option<usize>,
+    completion_tokens: Option<usize>,
+}
+
+impl From<ChatUsage> for Usage {
+    fn from(usage: ChatUsage) -> Self {
+        Usage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            reasoning_tokens: None,
+            cached_tokens: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url_and_api_key() {
+        let model = OpenAICompatible::new("http://localhost:11434/v1/", Some("  "), "llama3.2")
+            .expect("failed to create model");
+
+        assert_eq!(
+            model.base_url.join("chat/completions").unwrap().as_str(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert!(model.api_key.is_none());
+        assert_eq!(model.name(), "llama3.2");
+
+        assert!(OpenAICompatible::new("localhost:11434", None, "llama3.2").is_err());
+        assert!(OpenAICompatible::new("not a url", None, "llama3.2").is_err());
+        assert!(OpenAICompatible::new("http://localhost:11434/v1", None, " ").is_err());
+    }
+
+    #[test]
+    fn test_chat_messages() {
+        let messages = vec![
+            Message::System("Be brief.".into()),
+            Message::User("Hi".into()),
+            Message::Assistant("Hello".to_string().into()),
+            Message::System("".into()),
+        ];
+
+        assert_eq!(
+            chat_messages(&messages),
+            vec![
+                ChatMessage {
+                    role: "system",
+                    content: "Be brief.".to_string()
+                },
+                ChatMessage {
+                    role: "user",
+                    content: "Hi".to_string()
+                },
+                ChatMessage {
+                    role: "assistant",
+                    content: "Hello".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let chunk = |data: &str| serde_json::from_str::<ChatCompletionChunk>(data).unwrap();
+
+        let mut parser = ChunkParser::default();
+
+        let chunks = parser.apply(chunk(
+            r#"{"choices":[{"index":0,"delta":{"role":"assistant","reasoning":"Thinking"}}]}"#,
+        ));
+        assert!(matches!(
+            chunks.as_slice(),
+            [LanguageModelStreamChunk::Delta(LanguageModelStreamChunkType::Reasoning(r))] if r == "Thinking"
+        ));
+
+        parser.apply(chunk(
+            r#"{"choices":[{"index":0,"delta":{"content":"Hel"}}]}"#,
+        ));
+        parser.apply(chunk(
+            r#"{"choices":[{"index":0,"delta":{"content":"lo"}}]}"#,
+        ));
+        parser.apply(chunk(
+            r#"{"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":2,"total_tokens":7}}"#,
+        ));
+
+        match parser.finish() {
+            LanguageModelStreamChunk::Done(message) => {
+                assert!(matches!(
+                    &message.content,
+                    LanguageModelResponseContentType::Text(t) if t == "Hello"
+                ));
+                assert_eq!(
+                    message.usage.as_ref().and_then(|u| u.output_tokens),
+                    Some(2)
+                );
+            }
+            _ => panic!("expected a done chunk"),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::utils::Utils;
+use aisdk::core::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Length of the label given to chats created from their first message.
+const CHAT_LABEL_LEN: usize = 35;
+
+/// Body of `POST /api/chat`, as sent by `useChat`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatRequest {
+    /// Id of the chat, messages are saved under it.
+    pub id: String,
+    pub messages: Vec<UIMessage>,
+}
+
+/// Body of `POST /api/chat/regenerate`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegenerateRequest {
+    pub chat_id: String,
+    pub message_id: String,
+}
+
+/// A `UIMessage` of the ai sdk. Parts are kept as is so everything the chat
+/// views render survives a round trip through the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIMessage {
+    pub id: String,
+    pub role: String,
+
+    #[serde(default)]
+    pub parts: Vec<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+impl UIMessage {
+    pub fn user(text: &str) -> Self {
+        Self {
+            id: Utils::get_random_id(),
+            role: "user".to_string(),
+            parts: vec![json!({ "type": "text", "text": text })],
+            metadata: None,
+        }
+    }
+
+    pub fn assistant(text: &str, reasoning: &str) -> Self {
+        let mut parts = Vec::new();
+
+        if !reasoning.is_empty() {
+            parts.push(json!({ "type": "reasoning", "text": reasoning }));
+        }
+
+        parts.push(json!({ "type": "text", "text": text }));
+
+        Self {
+            id: Utils::get_random_id(),
+            role: "assistant".to_string(),
+            parts,
+            metadata: None,
+        }
+    }
+
+    /// Text parts joined together, other parts are skipped.
+    pub fn text(&self) -> String {
+        self.parts
+            .iter()
+            .filter(|part| part["type"] == "text")
+            .filter_map(|part| part["text"].as_str())
+            .collect()
+    }
+}
+
+/// Converts messages for the model, dropping roles it does not know.
+pub fn to_model_messages(messages: &[UIMessage]) -> Vec<Message> {
+    messages
+        .iter()
+        .filter_map(|msg| {
+            let content = msg.text();
+
+            match msg.role.as_str() {
+                "system" => Some(Message::System(content.into())),
+                "user" => Some(Message::User(content.into())),
+                "assistant" => Some(Message::Assistant(content.into())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Start of the first user message, used to label new chats.
+pub fn chat_label(messages: &[UIMessage]) -> String {
+    messages
+        .iter()
+        .find(|msg| msg.role == "user")
+        .map(|msg| msg.text().trim().chars().take(CHAT_LABEL_LEN).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_request() {
+        let body = r#"{
+            "id": "chat_1",
+            "trigger": "submit-message",
+            "messages": [
+                {
+                    "id": "m1",
+                    "role": "user",
+                    "parts": [
+                        { "type": "text", "text": "Why does this diff " },
+                        { "type": "file", "url": "data:," },
+                        { "type": "text", "text": "fail to apply?" }
+                    ]
+                },
+                {
+                    "id": "m2",
+                    "role": "assistant",
+                    "parts": [{ "type": "step-start" }, { "type": "text", "text": "The hunk offsets moved." }]
+                }
+            ]
+        }"#;
+
+        let request: ChatRequest = serde_json::from_str(body).expect("invalid request");
+        assert_eq!(request.id, "chat_1");
+
+        let messages = to_model_messages(&request.messages);
+        assert_eq!(messages.len(), 2);
+        assert!(
+            matches!(&messages[0], Message::User(m) if m.content == "Why does this diff fail to apply?")
+        );
+
+        assert_eq!(
+            chat_label(&request.messages),
+            "Why does this diff fail to apply?"
+        );
+
+        // Parts the model does not see are still saved.
+        let saved = serde_json::to_value(&request.messages).expect("failed to serialize");
+        assert_eq!(saved[0]["parts"][1]["type"], "file");
+        assert!(saved[0].get("metadata").is_none());
+    }
+
+    #[test]
+    fn test_assistant_message() {
+        let message = UIMessage::assistant("answer", "thinking");
+
+        assert_eq!(message.role, "assistant");
+        assert_eq!(message.parts[0]["type"], "reasoning");
+        assert_eq!(message.text(), "answer");
+
+        let message = UIMessage::assistant("answer", "");
+        assert_eq!(message.parts.len(), 1);
+    }
+}
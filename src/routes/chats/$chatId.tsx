@@ -1,10 +1,12 @@
 import { AIChat } from "@/components/ai-chat";
-import { useQueryAppConfig } from "@/hooks/use-app-utils";
-import { useQueryChatById, useUpdateChat } from "@/hooks/use-chat";
+import { ChatBranches } from "@/components/chat-branches";
+import { useQueryAppConfig, useQueryModels } from "@/hooks/use-app-utils";
+import { useQueryChatById, useSwitchChatBranch } from "@/hooks/use-chat";
 import { useMessages } from "@/hooks/use-messages";
 import { getEndpoint } from "@/lib/ipc/utils";
-import { Model, models } from "@/lib/llms";
+import { Model } from "@/lib/llms";
 import { useChat } from "@ai-sdk/react";
+import { useQueryClient } from "@tanstack/react-query";
 import { createFileRoute } from "@tanstack/react-router";
 import { DefaultChatTransport } from "ai";
 import { useEffect, useMemo, useState } from "react";
@@ -21,12 +23,31 @@ function RouteComponent() {
 	const endpoint = Route.useLoaderData();
 	const { data } = useQueryChatById(params.chatId);
 	const { data: config } = useQueryAppConfig();
+	const { data: models } = useQueryModels();
 	const [model, setModel] = useState<string>("");
 	const [selectedModel, setSelectedModel] = useState<Model | null>(null);
 
+	const queryClient = useQueryClient();
+	const { mutate: switchBranch, isPending: isSwitching } =
+		useSwitchChatBranch(params.chatId);
+
 	const transport = useMemo(() => {
 		return new DefaultChatTransport({
 			api: `${endpoint}/api/chat`,
+			prepareSendMessagesRequest({ id, messages, trigger, messageId, body }) {
+				if (trigger !== "regenerate-message") {
+					return { body: { ...body, id, messages, trigger, messageId } };
+				}
+
+				// Regenerated answers go on a new branch, the old one stays.
+				return {
+					api: `${endpoint}/api/chat/regenerate`,
+					body: {
+						chat_id: id,
+						message_id: messageId ?? messages.at(-1)?.id,
+					},
+				};
+			},
 		});
 	}, [endpoint]);
 
@@ -34,16 +55,20 @@ function RouteComponent() {
 		messages,
 		sendMessage,
 		status,
-		id,
 		regenerate,
 		setMessages,
 		error,
 	} = useChat({
+		id: params.chatId,
 		transport,
+		onFinish() {
+			// Reload the saved thread, it may be on a new branch.
+			queryClient.invalidateQueries({
+				queryKey: ["fetch_chat_by_id", params.chatId],
+			});
+		},
 	});
 
-	const { mutate: updateMessages } = useUpdateChat();
-
 	const {
 		containerRef: messagesContainerRef,
 		endRef: messagesEndRef,
@@ -57,30 +82,35 @@ function RouteComponent() {
 	// Init messages on load
 	useEffect(() => {
 		if (data) {
-			setMessages(JSON.parse(data.messages));
+			setMessages(data.messages);
 		}
 	}, [data]);
 
-	// Upsert chat if it is not streaming and has no error
-	useEffect(() => {
-		if (status === "ready" && !error) {
-			updateMessages({ chat_id: id, messages });
-		}
-	}, [status, messages, id, error]);
-
 	useEffect(() => {
 		if (config && config.selected_model) {
 			const model = models.find(
-				(m) => m.id === config.selected_model,
+				(m) =>
+					m.id === config.selected_model &&
+					m.chefSlug === config.selected_provider,
 			) as unknown as Model;
 
 			setModel(config.selected_model);
 			setSelectedModel(model);
 		}
-	}, [config]);
+	}, [config, models]);
 
 	return (
 		<AIChat
+			header={
+				data && (
+					<ChatBranches
+						branches={data.branches}
+						branchId={data.branch_id}
+						disabled={isSwitching || status === "streaming"}
+						onSwitch={(branchId) => switchBranch(branchId)}
+					/>
+				)
+			}
 			error={error}
 			model={model}
 			config={config}
@@ -1,9 +1,9 @@
 import { AIChat } from "@/components/ai-chat";
-import { useQueryAppConfig } from "@/hooks/use-app-utils";
-import { useSaveInitialChat } from "@/hooks/use-chat";
+import { useQueryAppConfig, useQueryModels } from "@/hooks/use-app-utils";
+import { useOpenSavedChat } from "@/hooks/use-chat";
 import { useMessages } from "@/hooks/use-messages";
 import { getEndpoint } from "@/lib/ipc/utils";
-import { Model, models } from "@/lib/llms";
+import { Model, parseChatError } from "@/lib/llms";
 import { useChat } from "@ai-sdk/react";
 import { createFileRoute } from "@tanstack/react-router";
 import { DefaultChatTransport } from "ai";
@@ -20,6 +20,7 @@ export const Route = createFileRoute("/chats/")({
 function RouteComponent() {
 	const endpoint = Route.useLoaderData();
 	const { data: config } = useQueryAppConfig();
+	const { data: models } = useQueryModels();
 	const [model, setModel] = useState<string>("");
 	const [selectedModel, setSelectedModel] = useState<Model | null>(null);
 	const [isFirstChat, setIsFirstChat] = useState(false);
@@ -50,7 +51,7 @@ function RouteComponent() {
 
 		onError(err) {
 			toast.error("Oops chat error!!", {
-				description: err.message,
+				description: parseChatError(err).message,
 				dismissible: true,
 			});
 
@@ -58,10 +59,7 @@ function RouteComponent() {
 		},
 	});
 
-	const { mutate: saveInitialChat } = useSaveInitialChat({
-		status,
-		isFirstChat,
-	});
+	const openSavedChat = useOpenSavedChat();
 
 	// const { mutate } = useMutation({
 	// 	mutationKey: ["save_initial_chat"],
@@ -102,32 +100,25 @@ function RouteComponent() {
 		status,
 	});
 
-	// Save the initial chat once it is ready ie. not streaming.
+	// The chat api saves the chat, open it once the answer is done.
 	useEffect(() => {
 		if (status === "ready" && !error && isFirstChat === true) {
-			let label = "";
-
-			let m = messages.at(0);
-			let p = m?.parts.at(0);
-
-			if (p && p.type === "text") {
-				label = p.text.slice(0, 35);
-			}
-
-			saveInitialChat({ id, label, messages });
+			openSavedChat(id);
 		}
-	}, [id, status, messages, error, isFirstChat]);
+	}, [id, status, error, isFirstChat]);
 
 	useEffect(() => {
 		if (config && config.selected_model) {
 			const model = models.find(
-				(m) => m.id === config.selected_model,
+				(m) =>
+					m.id === config.selected_model &&
+					m.chefSlug === config.selected_provider,
 			) as unknown as Model;
 
 			setModel(config.selected_model);
 			setSelectedModel(model);
 		}
-	}, [config]);
+	}, [config, models]);
 
 	return (
 		<AIChat